@@ -1,98 +1,545 @@
-use std::time::Duration;
-use pod::{client::{PodaClient, PodaClientTrait}, Address, FixedBytes};
+use std::{collections::{HashMap, HashSet}, sync::{Arc, Mutex}, time::{Duration, SystemTime}};
+use pod::{client::{PodaClientTrait, CHALLENGE_PERIOD}, Address, FixedBytes};
 use anyhow::Result;
-use rand::{random_range};
+use rand::seq::index;
 use common::{
+    clock::{system_clock, Clock},
     constants::TOTAL_SHARDS,
+    http::shared_client,
     log::{info, warn}
 };
+use storage_provider::http::{BatchRetrieveRequest, BatchRetrieveResponse};
 
-pub struct Challenger {
-    pub pod: PodaClient,
+// A chunk is not re-challenged before the contract's challenge period elapses
+pub const DEFAULT_CHALLENGE_COOLDOWN: Duration = CHALLENGE_PERIOD;
+
+pub struct Challenger<T: PodaClientTrait> {
+    pub pod: T,
     sample_size: usize,
     interval: Duration,
+    cooldown: Duration,
+    recently_challenged: Mutex<HashMap<(FixedBytes<32>, u16, Address), SystemTime>>,
+    clock: Arc<dyn Clock>,
+    // audit providers over HTTP instead of issuing on-chain challenges
+    dry_run: bool,
 }
 
 pub type Challenge = (FixedBytes<32>, FixedBytes<32>, u16, Address);
 
-impl Challenger {
-    pub fn new(pod: PodaClient, sample_size: usize, interval: Duration) -> Self {
-        Self { pod, sample_size, interval}
+/// Outcome of fetching a sampled chunk straight from its owner in dry-run mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PossessionCheck {
+    pub commitment: FixedBytes<32>,
+    pub chunk_id: u16,
+    pub provider: Address,
+    // the provider returned the chunk with a merkle proof against the commitment
+    pub verified: bool,
+}
+
+impl<T: PodaClientTrait> Challenger<T> {
+    pub fn new(pod: T, sample_size: usize, interval: Duration) -> Self {
+        Self {
+            pod,
+            sample_size,
+            interval,
+            cooldown: DEFAULT_CHALLENGE_COOLDOWN,
+            recently_challenged: Mutex::new(HashMap::new()),
+            clock: system_clock(),
+            dry_run: false,
+        }
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
     }
 
     pub async fn run(&self) -> Result<()> {
         loop {
             self.run_round(self.sample_size).await?;
-            tokio::time::sleep(self.interval).await;
+            self.clock.sleep(self.interval).await;
         }
     }
 
+    /// In dry-run mode nothing is slashed or challenged and the round only audits providers
     pub async fn run_round(&self, sample_size: usize) -> Result<Vec<Challenge>> {
+        if self.dry_run {
+            let checks = self.audit_samples(sample_size).await?;
+            let verified = checks.iter().filter(|check| check.verified).count();
+            info!(verified, sampled = checks.len(), "Dry run finished");
+            return Ok(vec![]);
+        }
+
         self.slash_expired_challenges().await?;
         let challenges = self.sample_challenges(sample_size).await?;
         Ok(challenges)
     }
 
     pub async fn sample_challenges(&self, sample_size: usize) -> Result<Vec<Challenge>> {
-        let commitment_list = self.pod.get_commitment_list().await?;
+        let mut challenges: Vec<Challenge> = vec![];
+        for (commitment, chunk_id, provider_address) in self.sample_available_chunks(sample_size).await? {
+            if let Some(challenge) = self.issue_challenge(commitment, chunk_id, provider_address).await {
+                challenges.push(challenge);
+            }
+        }
+
+        Ok(challenges)
+    }
+
+    /// Fetches sampled chunks from their owners and checks them against the commitment
+    /// off-chain, without issuing challenges
+    pub async fn audit_samples(&self, sample_size: usize) -> Result<Vec<PossessionCheck>> {
+        let samples = self.sample_available_chunks(sample_size).await?;
+        if samples.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let urls = self.pod.get_providers().await?
+            .into_iter()
+            .map(|provider| (provider.addr, provider.url))
+            .collect::<HashMap<_, _>>();
+
+        let mut checks = vec![];
+        for (commitment, chunk_id, provider) in samples {
+            let verified = match urls.get(&provider) {
+                Some(url) => fetch_and_verify_chunk(url, commitment, chunk_id).await.unwrap_or_else(|e| {
+                    warn!(%commitment, chunk_id, %provider, error = ?e, "Fetching chunk from provider failed");
+                    false
+                }),
+                None => {
+                    warn!(%commitment, chunk_id, %provider, "Chunk owner is not a registered provider");
+                    false
+                }
+            };
+
+            if !verified {
+                warn!(%commitment, chunk_id, %provider, "Provider failed possession check");
+            }
+            checks.push(PossessionCheck { commitment, chunk_id, provider, verified });
+        }
+
+        Ok(checks)
+    }
+
+    // Picks distinct random (commitment, chunk) pairs, at most as many as exist, and returns
+    // the available ones with their owners
+    async fn sample_available_chunks(&self, sample_size: usize) -> Result<Vec<(FixedBytes<32>, u16, Address)>> {
+        let mut seen = HashSet::new();
+        let commitment_list = self.pod.get_commitment_list().await?
+            .into_iter()
+            .filter(|commitment| seen.insert(*commitment))
+            .collect::<Vec<_>>();
 
         if commitment_list.is_empty() {
             info!("No commitments found yet, skipping challenge sampling");
             return Ok(vec![]);
         }
 
-        let mut samples: Vec<(FixedBytes<32>, u16)> = vec![];
+        let space = commitment_list.len() * TOTAL_SHARDS;
+        let mut samples: HashMap<FixedBytes<32>, Vec<u16>> = HashMap::new();
+        for i in index::sample(&mut rand::rng(), space, sample_size.min(space)) {
+            samples.entry(commitment_list[i / TOTAL_SHARDS]).or_default().push((i % TOTAL_SHARDS) as u16);
+        }
 
-        for _ in 0..sample_size {
-            let commitment = commitment_list[random_range(0..commitment_list.len())];
-            let chunk_id = random_range(0..TOTAL_SHARDS as u16);
+        // one call per commitment instead of two per sampled chunk
+        let mut available = vec![];
+        for (commitment, chunk_ids) in samples {
+            let chunk_info = self.pod.get_chunk_info_batch(commitment, chunk_ids.clone()).await?;
+            for (chunk_id, (provider_address, is_chunk_available)) in chunk_ids.into_iter().zip(chunk_info) {
+                if !is_chunk_available {
+                    warn!(%commitment, chunk_id, "Chunk not available");
+                    continue
+                }
 
-            samples.push((commitment, chunk_id));
+                available.push((commitment, chunk_id, provider_address));
+            }
         }
 
+        Ok(available)
+    }
+
+    /// Challenges every available chunk of `commitment`, skipping chunks that are still in cooldown
+    pub async fn challenge_commitment(&self, commitment: FixedBytes<32>) -> Result<Vec<Challenge>> {
+        let chunk_ids = self.pod.get_available_chunks(commitment).await?;
+        info!(%commitment, chunks = chunk_ids.len(), "Challenging available chunks of commitment");
+
         let mut challenges: Vec<Challenge> = vec![];
-        for (commitment, chunk_id) in samples {
+        for chunk_id in chunk_ids {
             let provider_address = self.pod.get_chunk_owner(commitment, chunk_id).await?;
-            let is_chunk_available = self.pod.is_chunk_available(commitment, chunk_id).await?;
-            if !is_chunk_available {
-                warn!("Chunk not available: {:?}", (commitment, chunk_id));
-                continue
+            if let Some(challenge) = self.issue_challenge(commitment, chunk_id, provider_address).await {
+                challenges.push(challenge);
             }
+        }
+
+        Ok(challenges)
+    }
 
-            let res =  self.pod.issue_chunk_challenge(commitment, chunk_id, provider_address).await;
-            if res.is_err() {
-                warn!("Issuing chunk challenge failed. It's probably already issued");
+    /// Challenges a random sample of the chunks `provider` holds across all commitments, to audit
+    /// a single suspect provider
+    pub async fn challenge_provider(&self, provider: Address, sample_size: usize) -> Result<Vec<Challenge>> {
+        let mut seen = HashSet::new();
+        let mut held = vec![];
+        for commitment in self.pod.get_commitment_list().await? {
+            if !seen.insert(commitment) {
                 continue
             }
 
-            let challenge = res.unwrap();
+            let chunk_ids = self.pod.get_provider_chunks(commitment, provider).await?;
+            held.extend(chunk_ids.into_iter().map(|chunk_id| (commitment, chunk_id)));
+        }
+        info!(%provider, chunks = held.len(), "Challenging chunks held by provider");
 
-            challenges.push((challenge.challenge.challengeId, commitment, chunk_id, provider_address));
-            info!("Challenged provider {:?} with commitment {:?} and chunk {:?}", provider_address, commitment, chunk_id);
+        let mut challenges: Vec<Challenge> = vec![];
+        for i in index::sample(&mut rand::rng(), held.len(), sample_size.min(held.len())) {
+            let (commitment, chunk_id) = held[i];
+            if let Some(challenge) = self.issue_challenge(commitment, chunk_id, provider).await {
+                challenges.push(challenge);
+            }
         }
 
         Ok(challenges)
     }
 
+    async fn issue_challenge(&self, commitment: FixedBytes<32>, chunk_id: u16, provider_address: Address) -> Option<Challenge> {
+        if self.is_cooling_down(commitment, chunk_id, provider_address) {
+            info!(%commitment, chunk_id, provider = %provider_address, "Chunk was challenged recently, skipping");
+            return None;
+        }
+
+        let res = self.pod.issue_chunk_challenge(commitment, chunk_id, provider_address).await;
+        if res.is_err() {
+            warn!(%commitment, chunk_id, provider = %provider_address, "Issuing chunk challenge failed. It's probably already issued");
+            return None;
+        }
+
+        let challenge = res.unwrap();
+        self.recently_challenged.lock().unwrap().insert((commitment, chunk_id, provider_address), self.clock.now());
+
+        info!(%commitment, chunk_id, provider = %provider_address, "Challenged provider");
+        Some((challenge.challenge.challengeId, commitment, chunk_id, provider_address))
+    }
+
+    fn is_cooling_down(&self, commitment: FixedBytes<32>, chunk_id: u16, provider_address: Address) -> bool {
+        let now = self.clock.now();
+        let mut recently_challenged = self.recently_challenged.lock().unwrap();
+        recently_challenged.retain(|_, challenged_at| now.duration_since(*challenged_at).unwrap_or_default() < self.cooldown);
+        recently_challenged.contains_key(&(commitment, chunk_id, provider_address))
+    }
+
     pub async fn slash_expired_challenges(&self) -> Result<()> {
-        let challenges = self.pod.get_provider_expired_challenges(self.pod.address).await?;
-        info!("Found {} expired challenges", challenges.len());
-        
-        for challenge in challenges {
-            let commitment = challenge.commitment;
-            let chunk_id = challenge.chunkId;
-            let provider_address = challenge.challenge.challenger;
-
-            warn!("Slashing provider {:?} with expired challenge {:?}", provider_address, challenge);
-            let slashed = self.pod.slash_expired_challenge(commitment, chunk_id, provider_address).await;
-            if slashed.is_err() {
-                warn!("Slashing expired challenge failed. It's probably already slashed");
-                continue
+        let providers = self.pod.get_providers().await?;
+
+        for provider in providers {
+            let challenges = self.pod.get_provider_expired_challenges(provider.addr).await?;
+            if !challenges.is_empty() {
+                info!(provider = %provider.addr, expired = challenges.len(), "Found expired challenges");
             }
 
-            info!("Slashed provider {:?} with expired challenge {:?}", provider_address, challenge);
+            for challenge in challenges {
+                let commitment = challenge.commitment;
+                let chunk_id = challenge.chunkId;
+
+                warn!(%commitment, chunk_id, provider = %provider.addr, "Slashing provider for expired challenge");
+                let slashed = self.pod.slash_expired_challenge(commitment, chunk_id, provider.addr).await;
+                if slashed.is_err() {
+                    warn!(%commitment, chunk_id, provider = %provider.addr, "Slashing expired challenge failed. It's probably already slashed");
+                    continue
+                }
+
+                info!(%commitment, chunk_id, provider = %provider.addr, "Slashed provider for expired challenge");
+            }
         }
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+async fn fetch_and_verify_chunk(url: &str, commitment: FixedBytes<32>, chunk_id: u16) -> Result<bool> {
+    let body = BatchRetrieveRequest { commitment, indices: vec![chunk_id] };
+    let response = shared_client().post(format!("{}/batch-retrieve", url)).json(&body).send().await?;
+    if !response.status().is_success() {
+        return Ok(false);
+    }
+
+    let message: BatchRetrieveResponse = response.json().await?;
+    let verified = match (message.chunks.into_iter().next().flatten(), message.proofs.into_iter().next().flatten()) {
+        (Some(chunk), Some(proof)) => chunk.index == chunk_id && merkle_tree::verify_proof(commitment, &chunk, proof),
+        _ => false,
+    };
+    Ok(verified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::clock::MockClock;
+    use pod::{client::{ChallengeInfo, ChunkChallenge, MockPodaClientTrait, ProviderInfo}, U256};
+    use common::types::Chunk;
+    use merkle_tree::{gen_merkle_tree, gen_proof};
+    use warp::Filter;
+
+    fn owner_of(chunk_id: u16) -> Address {
+        Address::with_last_byte(chunk_id as u8 % 3 + 1)
+    }
+
+    fn expect_challenges(pod: &mut MockPodaClientTrait, available_chunks: Vec<u16>, times: usize) {
+        pod.expect_get_available_chunks().returning(move |_| Ok(available_chunks.clone()));
+        pod.expect_get_chunk_owner().returning(|_, chunk_id| Ok(owner_of(chunk_id)));
+        pod.expect_issue_chunk_challenge()
+            .times(times)
+            .returning(|commitment, chunk_id, _| Ok(ChallengeInfo {
+                challenge: ChunkChallenge {
+                    challengeId: FixedBytes::with_last_byte(chunk_id as u8),
+                    challenger: Address::ZERO,
+                    issuedAt: 0,
+                },
+                commitment,
+                chunkId: chunk_id,
+            }));
+    }
+
+    #[tokio::test]
+    async fn test_challenge_commitment_issues_one_challenge_per_available_chunk() {
+        let commitment = FixedBytes::from([7u8; 32]);
+        let available_chunks = vec![0u16, 1, 2, 5, 8];
+
+        let mut pod = MockPodaClientTrait::new();
+        expect_challenges(&mut pod, available_chunks.clone(), available_chunks.len());
+
+        let challenger = Challenger::new(pod, 10, Duration::from_secs(10));
+        let challenges = challenger.challenge_commitment(commitment).await.unwrap();
+
+        assert_eq!(challenges.len(), available_chunks.len());
+        for (challenge, chunk_id) in challenges.iter().zip(&available_chunks) {
+            assert_eq!(challenge.1, commitment);
+            assert_eq!(challenge.2, *chunk_id);
+            assert_eq!(challenge.3, owner_of(*chunk_id));
+        }
+
+        // every chunk is now in cooldown, so nothing is re-issued
+        let challenges = challenger.challenge_commitment(commitment).await.unwrap();
+        assert!(challenges.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cooldown_expires_with_clock() {
+        let commitment = FixedBytes::from([7u8; 32]);
+        let available_chunks = vec![0u16, 3];
+        let cooldown = Duration::from_secs(600);
+
+        let mut pod = MockPodaClientTrait::new();
+        expect_challenges(&mut pod, available_chunks.clone(), available_chunks.len() * 2);
+
+        let clock = Arc::new(MockClock::default());
+        let challenger = Challenger::new(pod, 10, Duration::from_secs(10))
+            .with_cooldown(cooldown)
+            .with_clock(clock.clone());
+
+        assert_eq!(challenger.challenge_commitment(commitment).await.unwrap().len(), 2);
+
+        clock.advance(cooldown - Duration::from_secs(1));
+        assert!(challenger.challenge_commitment(commitment).await.unwrap().is_empty());
+
+        clock.sleep(Duration::from_secs(1)).await;
+        assert_eq!(challenger.challenge_commitment(commitment).await.unwrap().len(), 2);
+    }
+
+    // Stub provider serving `chunks` from /batch-retrieve, flipping a byte of each when `tampered`
+    async fn spawn_provider(chunks: Vec<Chunk>, tampered: bool) -> String {
+        let tree = gen_merkle_tree(&chunks);
+        let all_proofs = chunks.iter().map(|chunk| gen_proof(&tree, chunk.clone()).unwrap()).collect::<Vec<_>>();
+        let batch_retrieve = warp::path("batch-retrieve")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(move |req: BatchRetrieveRequest| {
+                let mut found = req.indices.iter().map(|i| chunks[*i as usize].clone()).collect::<Vec<_>>();
+                let proofs = req.indices.iter().map(|i| Some(all_proofs[*i as usize].clone())).collect();
+                if tampered {
+                    found.iter_mut().for_each(|chunk| chunk.data[0] ^= 0xff);
+                }
+                warp::reply::json(&BatchRetrieveResponse { chunks: found.into_iter().map(Some).collect(), proofs })
+            });
+        let (addr, server) = warp::serve(batch_retrieve).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        format!("http://{}", addr)
+    }
+
+    fn provider(addr: Address, url: String) -> ProviderInfo {
+        ProviderInfo {
+            name: format!("Provider {}", addr),
+            url,
+            addr,
+            registeredAt: 0,
+            challengeCount: 0,
+            challengeSuccessCount: 0,
+            active: true,
+            stakedAmount: U256::from(100),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_verifies_without_challenging() {
+        let chunks = (0..TOTAL_SHARDS as u16).map(|index| Chunk { index, data: vec![index as u8; 32] }).collect::<Vec<_>>();
+        let commitment = gen_merkle_tree(&chunks).root();
+        let honest = Address::with_last_byte(1);
+        let providers = vec![
+            provider(honest, spawn_provider(chunks.clone(), false).await),
+            provider(Address::with_last_byte(2), spawn_provider(chunks, true).await),
+        ];
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_list().returning(move || Ok(vec![commitment]));
+        pod.expect_get_chunk_info_batch().returning(|_, chunk_ids| {
+            Ok(chunk_ids.into_iter().map(|chunk_id| (Address::with_last_byte(chunk_id as u8 % 2 + 1), true)).collect())
+        });
+        pod.expect_get_providers().returning(move || Ok(providers.clone()));
+        pod.expect_issue_chunk_challenge().never();
+        pod.expect_get_provider_expired_challenges().never();
+
+        let challenger = Challenger::new(pod, 10, Duration::from_secs(10)).with_dry_run(true);
+
+        let checks = challenger.audit_samples(20).await.unwrap();
+        assert_eq!(checks.len(), 20);
+        for check in &checks {
+            assert_eq!(check.commitment, commitment);
+            assert_eq!(check.verified, check.provider == honest, "{:?}", check);
+        }
+
+        assert!(challenger.run_round(20).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sampling_never_repeats_a_target() {
+        let commitments = vec![FixedBytes::from([1u8; 32]), FixedBytes::from([2u8; 32]), FixedBytes::from([1u8; 32])];
+        let attempted = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = attempted.clone();
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_list().returning(move || Ok(commitments.clone()));
+        pod.expect_get_chunk_info_batch().returning(|_, chunk_ids| Ok(chunk_ids.into_iter().map(|chunk_id| (owner_of(chunk_id), true)).collect()));
+        pod.expect_issue_chunk_challenge().returning(move |commitment, chunk_id, provider| {
+            recorded.lock().unwrap().push((commitment, chunk_id, provider));
+            Err(anyhow::anyhow!("challenge already issued"))
+        });
+
+        let challenger = Challenger::new(pod, 50, Duration::from_secs(10));
+        challenger.sample_challenges(50).await.unwrap();
+
+        // two distinct commitments leave fewer targets than the sample size, and each is tried once
+        let attempted = attempted.lock().unwrap();
+        let distinct = attempted.iter().collect::<HashSet<_>>();
+        assert_eq!(attempted.len(), 2 * TOTAL_SHARDS);
+        assert_eq!(distinct.len(), attempted.len());
+    }
+
+    #[tokio::test]
+    async fn test_sampling_fetches_chunk_info_in_batches() {
+        let commitments = vec![FixedBytes::from([1u8; 32]), FixedBytes::from([2u8; 32])];
+        let batched = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = batched.clone();
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_list().returning(move || Ok(commitments.clone()));
+        pod.expect_get_chunk_info_batch().times(2).returning(move |commitment, chunk_ids| {
+            recorded.lock().unwrap().push((commitment, chunk_ids.clone()));
+            Ok(chunk_ids.into_iter().map(|chunk_id| (owner_of(chunk_id), chunk_id % 2 == 0)).collect())
+        });
+        pod.expect_get_chunk_owner().never();
+        pod.expect_is_chunk_available().never();
+        expect_challenges(&mut pod, vec![], TOTAL_SHARDS);
+
+        let challenger = Challenger::new(pod, 50, Duration::from_secs(10));
+        let challenges = challenger.sample_challenges(2 * TOTAL_SHARDS).await.unwrap();
+
+        // every chunk of both commitments was looked up, and only the available ones challenged
+        let batched = batched.lock().unwrap();
+        assert_eq!(batched.iter().map(|(_, chunk_ids)| chunk_ids.len()).sum::<usize>(), 2 * TOTAL_SHARDS);
+        assert_eq!(challenges.len(), TOTAL_SHARDS);
+        for (_, _, chunk_id, provider) in challenges {
+            assert_eq!(chunk_id % 2, 0);
+            assert_eq!(provider, owner_of(chunk_id));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_challenge_provider_only_targets_its_chunks() {
+        let commitments = vec![FixedBytes::from([1u8; 32]), FixedBytes::from([2u8; 32]), FixedBytes::from([1u8; 32])];
+        let target = owner_of(1);
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_list().returning(move || Ok(commitments.clone()));
+        // the duplicated commitment is only looked up once per call
+        pod.expect_get_provider_chunks()
+            .times(4)
+            .withf(move |_, provider| *provider == target)
+            .returning(|_, provider| Ok((0..TOTAL_SHARDS as u16).filter(|chunk_id| owner_of(*chunk_id) == provider).collect()));
+        pod.expect_issue_chunk_challenge()
+            .withf(move |_, chunk_id, provider| *provider == target && owner_of(*chunk_id) == target)
+            .returning(|commitment, chunk_id, _| Ok(ChallengeInfo {
+                challenge: ChunkChallenge {
+                    challengeId: FixedBytes::with_last_byte(chunk_id as u8),
+                    challenger: Address::ZERO,
+                    issuedAt: 0,
+                },
+                commitment,
+                chunkId: chunk_id,
+            }));
+
+        let challenger = Challenger::new(pod, 10, Duration::from_secs(10));
+
+        let challenges = challenger.challenge_provider(target, 5).await.unwrap();
+        assert_eq!(challenges.len(), 5);
+        assert!(challenges.iter().all(|challenge| challenge.3 == target));
+
+        // a sample larger than what the provider holds challenges each remaining chunk once
+        let held = 2 * (0..TOTAL_SHARDS as u16).filter(|chunk_id| owner_of(*chunk_id) == target).count();
+        let challenges = challenger.challenge_provider(target, 100).await.unwrap();
+        assert_eq!(challenges.len(), held - 5);
+    }
+
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_challenge_is_logged_with_fields() {
+        use tracing_subscriber::util::SubscriberInitExt;
+
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let _guard = tracing_subscriber::fmt().with_writer(move || writer.clone()).with_ansi(false).finish().set_default();
+
+        let commitment = FixedBytes::from([7u8; 32]);
+        let mut pod = MockPodaClientTrait::new();
+        expect_challenges(&mut pod, vec![2], 1);
+
+        let challenger = Challenger::new(pod, 10, Duration::from_secs(10));
+        challenger.challenge_commitment(commitment).await.unwrap();
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let line = logs.lines().find(|line| line.contains("Challenged provider")).expect("challenge was not logged");
+        assert!(line.contains(&format!("commitment={}", commitment)), "{}", line);
+        assert!(line.contains("chunk_id=2"), "{}", line);
+        assert!(line.contains(&format!("provider={}", owner_of(2))), "{}", line);
+    }
+}
@@ -1,13 +1,11 @@
-mod challenger;
-
 use std::{str::FromStr, time::Duration};
 use dotenv::dotenv;
 use pod::{client::PodaClient, Address, PrivateKeySigner};
-use common::log::init_logging;
+use common::{http::{init_shared_client, HttpTimeouts}, log::init_logging};
 
-use crate::challenger::Challenger;
+use challenger::challenger::{Challenger, DEFAULT_CHALLENGE_COOLDOWN};
 
-fn load_config() -> (String, Address, String, usize, u64) {
+fn load_config() -> (String, Address, String, usize, u64, Duration, bool) {
     dotenv().ok();
     init_logging();
 
@@ -16,17 +14,22 @@ fn load_config() -> (String, Address, String, usize, u64) {
     let private_key = std::env::var("CHALLENGER_PRIVATE_KEY").unwrap();
     let sample_size = std::env::var("CHALLENGER_SAMPLE_SIZE").unwrap_or("10".to_string()).parse::<usize>().unwrap();
     let interval = std::env::var("CHALLENGER_INTERVAL_SECS").unwrap_or("60".to_string()).parse::<u64>().unwrap();
+    let cooldown = std::env::var("CHALLENGER_COOLDOWN_SECS")
+        .map(|secs| Duration::from_secs(secs.parse::<u64>().unwrap()))
+        .unwrap_or(DEFAULT_CHALLENGE_COOLDOWN);
+    let dry_run = std::env::var("CHALLENGER_DRY_RUN").map(|v| v == "true").unwrap_or(false);
+    init_shared_client(HttpTimeouts::from_env());
 
-    (rpc_url, poda_address, private_key, sample_size, interval)
+    (rpc_url, poda_address, private_key, sample_size, interval, cooldown, dry_run)
 }
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
-    let (rpc_url, poda_address, private_key, sample_size, interval) = load_config();
+    let (rpc_url, poda_address, private_key, sample_size, interval, cooldown, dry_run) = load_config();
 
     let signer = PrivateKeySigner::from_str(&private_key).unwrap();
     let pod = PodaClient::new(signer, rpc_url.clone(), poda_address).await;
 
-    let challenger = Challenger::new(pod, sample_size, Duration::from_secs(interval));
+    let challenger = Challenger::new(pod, sample_size, Duration::from_secs(interval)).with_cooldown(cooldown).with_dry_run(dry_run);
     challenger.run().await.unwrap();
 }
\ No newline at end of file
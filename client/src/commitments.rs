@@ -0,0 +1,97 @@
+use anyhow::Result;
+use pod::{client::PodaClientTrait, FixedBytes};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitmentRow {
+    pub commitment: FixedBytes<32>,
+    pub size: u32,
+    pub available_chunks: u16,
+    pub total_chunks: u16,
+    pub is_recoverable: bool,
+}
+
+/// Fetches every commitment on-chain together with its availability
+pub async fn list_commitments<T: PodaClientTrait>(pod: &T, recoverable_only: bool) -> Result<Vec<CommitmentRow>> {
+    let commitments = pod.get_commitment_list().await?;
+    let statuses = pod.get_multiple_commitment_status(commitments.clone()).await?;
+
+    let mut rows = Vec::new();
+    for (commitment, is_recoverable) in commitments.into_iter().zip(statuses) {
+        if recoverable_only && !is_recoverable {
+            continue;
+        }
+
+        let (info, _) = pod.get_commitment_info(commitment).await?;
+        rows.push(CommitmentRow {
+            commitment,
+            size: info.size,
+            available_chunks: info.availableChunks,
+            total_chunks: info.totalChunks,
+            is_recoverable,
+        });
+    }
+
+    Ok(rows)
+}
+
+pub fn format_commitment_table(rows: &[CommitmentRow]) -> String {
+    let mut table = format!("{:<66}  {:>10}  {:>9}  {:>11}\n", "COMMITMENT", "SIZE", "CHUNKS", "RECOVERABLE");
+    for row in rows {
+        let chunks = format!("{}/{}", row.available_chunks, row.total_chunks);
+        table.push_str(&format!("{:<66}  {:>10}  {:>9}  {:>11}\n", row.commitment.to_string(), row.size, chunks, row.is_recoverable));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pod::{client::{Commitment, MockPodaClientTrait}, Bytes};
+
+    fn mock_pod() -> MockPodaClientTrait {
+        let commitments = vec![FixedBytes::from([1u8; 32]), FixedBytes::from([2u8; 32]), FixedBytes::from([3u8; 32])];
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_list().returning(move || Ok(commitments.clone()));
+        pod.expect_get_multiple_commitment_status().returning(|_| Ok(vec![true, false, true]));
+        pod.expect_get_commitment_info().returning(|commitment| {
+            let available = if commitment[0] == 2 { 10 } else { 24 };
+            Ok((Commitment {
+                size: commitment[0] as u32 * 100,
+                timestamp: 0,
+                totalChunks: 24,
+                requiredChunks: 16,
+                availableChunks: available,
+                kzgCommitment: Bytes::new(),
+            }, available >= 16))
+        });
+        pod
+    }
+
+    #[tokio::test]
+    async fn test_list_all_commitments() {
+        let rows = list_commitments(&mock_pod(), false).await.unwrap();
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1], CommitmentRow {
+            commitment: FixedBytes::from([2u8; 32]),
+            size: 200,
+            available_chunks: 10,
+            total_chunks: 24,
+            is_recoverable: false,
+        });
+    }
+
+    #[tokio::test]
+    async fn test_list_recoverable_only() {
+        let rows = list_commitments(&mock_pod(), true).await.unwrap();
+
+        let commitments = rows.iter().map(|r| r.commitment).collect::<Vec<_>>();
+        assert_eq!(commitments, vec![FixedBytes::from([1u8; 32]), FixedBytes::from([3u8; 32])]);
+
+        let table = format_commitment_table(&rows);
+        assert_eq!(table.lines().count(), 3);
+        assert!(table.contains("24/24"));
+        assert!(!table.contains(&FixedBytes::<32>::from([2u8; 32]).to_string()));
+    }
+}
@@ -1,14 +1,12 @@
-use common::log::error;
-use dispencer::http::{RetrieveDataRequest, RetrieveDataResponse, SubmitDataRequest, SubmitDataResponse};
+use common::{http::shared_client, log::error};
+use dispencer::http::{AuditRequest, AuditResponse, DataEncoding, RetrieveDataRequest, RetrieveDataResponse, SubmitDataRequest, SubmitDataResponse};
 use anyhow::Result;
 use pod::FixedBytes;
 
 pub async fn submit_data(dispencer_url: &str, data: &[u8]) -> Result<SubmitDataResponse> {
-    let client = reqwest::Client::new();
+    let client = shared_client();
     let url = format!("{}/submit", dispencer_url);
-    let request_body = SubmitDataRequest {
-        data: data.to_vec(),
-    };
+    let request_body = SubmitDataRequest::new(data, DataEncoding::Base64);
 
     let res = client.post(&url).json(&request_body).send().await?;
     if !res.status().is_success() {
@@ -21,10 +19,12 @@ pub async fn submit_data(dispencer_url: &str, data: &[u8]) -> Result<SubmitDataR
 }
 
 pub async fn retrieve_data(dispencer_url: &str, commitment: &FixedBytes<32>) -> Result<RetrieveDataResponse> {
-    let client = reqwest::Client::new();
+    let client = shared_client();
     let url = format!("{}/retrieve", dispencer_url);
     let request_body = RetrieveDataRequest {
         commitment: *commitment,
+        expected_hash: None,
+        strategy: None,
     };
 
     let res = client.post(&url).json(&request_body).send().await?;
@@ -36,4 +36,22 @@ pub async fn retrieve_data(dispencer_url: &str, commitment: &FixedBytes<32>) ->
 
     let response_body: RetrieveDataResponse = res.json().await?;
     Ok(response_body)
-}
\ No newline at end of file
+}
+
+pub async fn audit_chunk(dispencer_url: &str, commitment: &FixedBytes<32>, chunk_id: u16) -> Result<AuditResponse> {
+    let client = shared_client();
+    let url = format!("{}/audit", dispencer_url);
+    let request_body = AuditRequest {
+        commitment: *commitment,
+        chunk_id,
+    };
+
+    let res = client.post(&url).json(&request_body).send().await?;
+    if !res.status().is_success() {
+        let error: serde_json::Value = res.json().await?;
+        error!("Failed to audit chunk: {}", error["message"]);
+        return Err(anyhow::anyhow!("{}", error["message"]));
+    }
+
+    Ok(res.json().await?)
+}
@@ -1,5 +1,9 @@
 mod utils;
 mod dispencer_client;
+mod output;
+mod commitments;
 
-pub use utils::{health_check, get_actors, get_provider_for_signer, faucet_if_needed};
-pub use dispencer_client::{retrieve_data, submit_data}; 
\ No newline at end of file
+pub use utils::{env_file_content, health_check, health_check_all, health_check_urls, health_check_with_retries, get_actors, get_provider_for_signer, faucet_if_needed, DEFAULT_FAUCET_AMOUNT, DEFAULT_FAUCET_MIN_BALANCE};
+pub use dispencer_client::{audit_chunk, retrieve_data, submit_data};
+pub use output::{encode_data, write_output, OutputEncoding};
+pub use commitments::{format_commitment_table, list_commitments, CommitmentRow};
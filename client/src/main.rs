@@ -1,17 +1,20 @@
 pub mod utils;
 mod dispencer_client;
 
-use utils::{faucet_if_needed, get_provider_for_signer, get_actors};
+use utils::{env_file_content, faucet_if_needed, get_provider_for_signer, get_actors, DEFAULT_FAUCET_AMOUNT, DEFAULT_FAUCET_MIN_BALANCE};
+use challenger::challenger::Challenger;
 use clap::{Parser, Subcommand};
+use common::http::{init_shared_client, HttpTimeouts};
 use common::log::{error, info, init_logging};
 use common::{
     types::FixedBytes,
 };
-use crate::dispencer_client::{retrieve_data, submit_data};
-use crate::utils::health_check;
+use crate::dispencer_client::{audit_chunk, retrieve_data, submit_data};
+use crate::utils::{health_check_urls, health_check_with_retries};
+use client::{format_commitment_table, list_commitments, write_output, OutputEncoding};
 use pod::client::PodaClientTrait;
 use pod::{client::PodaClient, Address, PrivateKeySigner};
-use std::{fs, str::FromStr};
+use std::{fs, path::PathBuf, str::FromStr, time::Duration};
 
 #[derive(Parser)]
 #[command(name = "poda-localnet")]
@@ -25,13 +28,19 @@ static FAUCET_PRIVATE_KEY: &str = "6df79891f22b0f3c9e9fb53b966a8861fd6fef69f9977
 static DEFAULT_RPC_URL: &str = "http://localhost:8545";
 static DISPENCER_URL: &str = "http://localhost:8000";
 static DEFAULT_STORAGE_PROVIDER_STAKE: u128 = 1000000000000000000;
-static N_STORAGE_PROVIDERS: usize = 3; // DO NOT CHANGE THIS. IT MESSES UP EVERYTHING.
+static DEFAULT_N_STORAGE_PROVIDERS: usize = 3;
 static ENV_FILE_NAME: &str = "localnet.env";
+// Host localnet providers register under, so the dispenser's container can reach them
+static LOCALNET_PROVIDER_HOST: &str = "host.docker.internal";
 
 #[derive(Subcommand)]
 enum Commands {
     /// Setup blockchain infrastructure (accounts, funding, contract deployment)
     Setup {
+        /// Storage providers to register, at most the number of actors minus the dispenser
+        /// and challenger. docker-compose.yml runs three, so more need matching services
+        #[arg(long, default_value_t = DEFAULT_N_STORAGE_PROVIDERS)]
+        providers: usize,
     },
     /// Get all active challenges issued from the challenger for a given address
     GetActiveChallenges {
@@ -43,6 +52,23 @@ enum Commands {
         chunk_id: u16,
         provider: Address,
     },
+    /// List all commitments with their availability
+    ListCommitments {
+        /// Only show commitments that can currently be reconstructed
+        #[arg(long)]
+        recoverable_only: bool,
+    },
+    /// Show the minimum stake required to register as a storage provider
+    MinStake {
+    },
+    /// Show the storage efficiency of a commitment and of the whole network
+    StorageStats {
+        commitment: String,
+    },
+    /// Challenge every available chunk of a commitment right away
+    ChallengeCommitment {
+        commitment: String,
+    },
     /// Submit data to the dispenser
     SubmitData {
         // Bytes of data to submit
@@ -51,9 +77,29 @@ enum Commands {
     /// Retrieve data from the dispenser
     RetrieveData {
         commitment: String,
+        /// Write the raw bytes to this file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Encoding used when printing to stdout
+        #[arg(long, value_enum, default_value_t = OutputEncoding::Hex)]
+        encoding: OutputEncoding,
+    },
+    /// Fetch a chunk from the provider holding it and check it against its commitment
+    AuditChunk {
+        commitment: String,
+        chunk_id: u16,
     },
     /// Check the health of the dispenser and storage providers
     HealthCheck {
+        /// Number of extra attempts before declaring a service down
+        #[arg(long, default_value_t = 0)]
+        retries: u32,
+        /// Milliseconds to wait between attempts
+        #[arg(long, default_value_t = 1000)]
+        interval: u64,
+        /// Storage provider URL to probe instead of the ones registered on-chain, repeatable
+        #[arg(long = "url")]
+        urls: Vec<String>,
     },
 }
 
@@ -69,14 +115,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let env_file_path = project_root.join(ENV_FILE_NAME);
     
     init_logging();
+    init_shared_client(HttpTimeouts::from_env());
     let cli = Cli::parse();
 
     match &cli.command {
-        Commands::Setup { } => {
+        Commands::Setup { providers } => {
             info!("🔗 Setting up Poda Blockchain Infrastructure");
             info!("==============================================");
             
-            let setup_result = setup_poda_localnet(DEFAULT_RPC_URL, DEFAULT_STORAGE_PROVIDER_STAKE).await;
+            let setup_result = setup_poda_localnet(DEFAULT_RPC_URL, DEFAULT_STORAGE_PROVIDER_STAKE, *providers).await;
             
             match setup_result {
                 Ok(_) => {
@@ -94,8 +141,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             dotenv::from_filename(&env_file_path).ok();
 
             let poda_address = std::env::var("PODA_ADDRESS").unwrap();
-            let signer = PrivateKeySigner::from_str(FAUCET_PRIVATE_KEY).unwrap();
-            let poda_client = PodaClient::new(signer, DEFAULT_RPC_URL.to_string(), Address::from_str(&poda_address).unwrap()).await;
+            let poda_client = PodaClient::new_read_only(DEFAULT_RPC_URL.to_string(), Address::from_str(&poda_address).unwrap()).await;
             let challenges = poda_client.get_provider_active_challenges(*address).await.unwrap();
             info!("🔍 Active challenges: {:?}", challenges);
         }
@@ -105,11 +151,97 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             let commitment: FixedBytes<32> = FixedBytes::from_str(commitment).unwrap();
             let poda_address = std::env::var("PODA_ADDRESS").unwrap();
-            let signer = PrivateKeySigner::from_str(FAUCET_PRIVATE_KEY).unwrap();
-            let poda_client = PodaClient::new(signer, DEFAULT_RPC_URL.to_string(), Address::from_str(&poda_address).unwrap()).await;
+            let poda_client = PodaClient::new_read_only(DEFAULT_RPC_URL.to_string(), Address::from_str(&poda_address).unwrap()).await;
             let challenge = poda_client.get_chunk_challenge(commitment, *chunk_id, *provider).await.unwrap();
             info!("🔍 Challenge: {:?}", challenge);
         },
+        Commands::ListCommitments { recoverable_only } => {
+            dotenv::dotenv().ok();
+            dotenv::from_filename(&env_file_path).ok();
+
+            let poda_address = std::env::var("PODA_ADDRESS").unwrap();
+            let poda_client = PodaClient::new_read_only(DEFAULT_RPC_URL.to_string(), Address::from_str(&poda_address).unwrap()).await;
+
+            match list_commitments(&poda_client, *recoverable_only).await {
+                Ok(rows) => {
+                    info!("🔍 Found {} commitments", rows.len());
+                    print!("{}", format_commitment_table(&rows));
+                }
+                Err(e) => {
+                    error!("❌ Failed to list commitments: {:?}", e);
+                }
+            }
+        },
+        Commands::MinStake { } => {
+            dotenv::dotenv().ok();
+            dotenv::from_filename(&env_file_path).ok();
+
+            let poda_address = std::env::var("PODA_ADDRESS").unwrap();
+            let poda_client = PodaClient::new_read_only(DEFAULT_RPC_URL.to_string(), Address::from_str(&poda_address).unwrap()).await;
+
+            match poda_client.get_min_stake().await {
+                Ok(min_stake) => {
+                    info!("🔍 Minimum stake: {} wei", min_stake);
+                }
+                Err(e) => {
+                    error!("❌ Failed to get minimum stake: {:?}", e);
+                }
+            }
+        },
+        Commands::StorageStats { commitment } => {
+            dotenv::dotenv().ok();
+            dotenv::from_filename(&env_file_path).ok();
+
+            let commitment: FixedBytes<32> = FixedBytes::from_str(commitment).unwrap();
+            let poda_address = std::env::var("PODA_ADDRESS").unwrap();
+            let poda_client = PodaClient::new_read_only(DEFAULT_RPC_URL.to_string(), Address::from_str(&poda_address).unwrap()).await;
+
+            match poda_client.get_storage_efficiency(commitment).await {
+                Ok(efficiency) => {
+                    info!("🔍 Original size: {} bytes", efficiency.original_size);
+                    info!("🔍 Total stored size: {} bytes", efficiency.total_stored_size);
+                    info!("🔍 Redundancy ratio: {:.2}x", efficiency.redundancy_ratio as f64 / 10000.0);
+                }
+                Err(e) => {
+                    error!("❌ Failed to get storage efficiency: {:?}", e);
+                }
+            }
+
+            match poda_client.get_network_storage_stats().await {
+                Ok(stats) => {
+                    info!("🔍 Network commitments: {}", stats.total_commitments);
+                    info!("🔍 Network original data: {} bytes", stats.total_original_data);
+                    info!("🔍 Network stored data: {} bytes", stats.total_stored_data);
+                    info!("🔍 Network average redundancy: {:.2}x", stats.average_efficiency as f64 / 10000.0);
+                }
+                Err(e) => {
+                    error!("❌ Failed to get network storage stats: {:?}", e);
+                }
+            }
+        },
+        Commands::ChallengeCommitment { commitment } => {
+            dotenv::dotenv().ok();
+            dotenv::from_filename(&env_file_path).ok();
+
+            let commitment: FixedBytes<32> = FixedBytes::from_str(commitment).unwrap();
+            let poda_address = std::env::var("PODA_ADDRESS").unwrap();
+            let private_key = std::env::var("CHALLENGER_PRIVATE_KEY").unwrap();
+            let signer = PrivateKeySigner::from_str(&private_key).unwrap();
+            let poda_client = PodaClient::new(signer, DEFAULT_RPC_URL.to_string(), Address::from_str(&poda_address).unwrap()).await;
+
+            let challenger = Challenger::new(poda_client, 0, Duration::ZERO);
+            match challenger.challenge_commitment(commitment).await {
+                Ok(challenges) => {
+                    info!("🔍 Issued {} challenges", challenges.len());
+                    for (challenge_id, _, chunk_id, provider) in challenges {
+                        info!("🔍 Chunk {} of provider {}: {}", chunk_id, provider, challenge_id);
+                    }
+                }
+                Err(e) => {
+                    error!("❌ Failed to challenge commitment: {:?}", e);
+                }
+            }
+        },
         Commands::SubmitData { data } => {
             let data = data.clone();
             let response = submit_data(DISPENCER_URL, &data).await;
@@ -117,9 +249,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 Ok(response) => {
                     info!("🔍 Submitted data: [{} bytes]", data.len());
                     info!("🔍 Commitment: {}", response.commitment);
+                    info!("🔍 Chunks promised: {}/{} ({} required)", response.promised_chunks, response.total_chunks, response.required_chunks);
                     info!("🔍 Chunk Assignments:");
-                    for (provider_id, chunk_ids) in response.assignments.iter() {
-                        info!("🔍 Provider {}: {:?}", provider_id, chunk_ids);
+                    for (provider, chunk_ids) in response.assignments.iter() {
+                        info!("🔍 Provider {}: {:?}", provider, chunk_ids);
                     }
                 }
                 Err(e) => {
@@ -127,40 +260,71 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             }
         },
-        Commands::RetrieveData { commitment } => {
+        Commands::RetrieveData { commitment, out, encoding } => {
             let commitment: FixedBytes<32> = FixedBytes::from_str(commitment).unwrap();
             let response = retrieve_data(DISPENCER_URL, &commitment).await;
             match response {
                 Ok(response) => {
                     let data = response.data.unwrap();
                     info!("🔍 Retrieved data: [{} bytes]", data.len());
-                    info!("🔍 Data: {:?}", data);
+                    if let Err(e) = write_output(&data, out.as_deref(), *encoding) {
+                        error!("❌ Failed to write data: {:?}", e);
+                    } else if let Some(out) = out {
+                        info!("🔍 Data written to {}", out.display());
+                    }
                 }
                 Err(e) => {
                     error!("❌ Failed to retrieve data: {:?}", e);
                 }
             }
         },
-        Commands::HealthCheck { } => {
-            let response = health_check(DISPENCER_URL.to_string()).await;
+        Commands::AuditChunk { commitment, chunk_id } => {
+            let commitment: FixedBytes<32> = FixedBytes::from_str(commitment).unwrap();
+            match audit_chunk(DISPENCER_URL, &commitment, *chunk_id).await {
+                Ok(audit) if audit.passed => {
+                    info!("🔍 Chunk {} held by {} passed the audit", audit.chunk_id, audit.provider);
+                }
+                Ok(audit) => {
+                    error!("❌ Chunk {} held by {} failed the audit (retrieved: {}, merkle: {}, kzg: {})", audit.chunk_id, audit.provider, audit.retrieved, audit.merkle_valid, audit.kzg_valid);
+                }
+                Err(e) => {
+                    error!("❌ Failed to audit chunk: {:?}", e);
+                }
+            }
+        },
+        Commands::HealthCheck { retries, interval, urls } => {
+            dotenv::dotenv().ok();
+            dotenv::from_filename(&env_file_path).ok();
+
+            let interval = Duration::from_millis(*interval);
+            let (response, attempts) = health_check_with_retries(DISPENCER_URL, *retries, interval).await;
             match response {
                 Ok(_) => {
-                    info!("🔍 Dispencer is up and running!");
+                    info!("🔍 Dispencer is up and running! ({} attempts)", attempts);
                 }
                 Err(_) => {
-                    error!("❌ Dispencer is down");
+                    error!("❌ Dispencer is down after {} attempts", attempts);
                 }
             }
 
-            for i in 0..N_STORAGE_PROVIDERS {
-                let response = health_check(format!("http://localhost:{}", 8001 + i as u16)).await;
-                match response {
-                    Ok(_) => {
-                        info!("🔍 Storage provider {} is up and running!", i + 1);
-                    }
-                    Err(_) => {
-                        info!("❌ Storage provider {} is down", i + 1);
-                    }
+            let (names, urls) = if urls.is_empty() {
+                let poda_address = std::env::var("PODA_ADDRESS")
+                    .map_err(|_| format!("PODA_ADDRESS is not set in the environment or {}. Run setup first, or pass the providers to probe with --url", ENV_FILE_NAME))?;
+                let poda_address = Address::from_str(&poda_address).map_err(|e| format!("Invalid PODA_ADDRESS {}: {}", poda_address, e))?;
+                let poda_client = PodaClient::new_read_only(DEFAULT_RPC_URL.to_string(), poda_address).await;
+                let providers = poda_client.get_providers().await?;
+                // localnet providers register a host that only resolves inside docker, their ports are published on localhost
+                let urls = providers.iter().map(|p| p.url.replace(LOCALNET_PROVIDER_HOST, "localhost")).collect::<Vec<_>>();
+                (providers.iter().map(|p| p.addr.to_string()).collect::<Vec<_>>(), urls)
+            } else {
+                ((1..=urls.len()).map(|i| i.to_string()).collect(), urls.clone())
+            };
+
+            for ((name, url), up) in names.iter().zip(&urls).zip(health_check_urls(&urls, *retries, interval).await) {
+                if up {
+                    info!("🔍 Storage provider {} at {} is up and running!", name, url);
+                } else {
+                    error!("❌ Storage provider {} at {} is down after {} attempts", name, url, retries + 1);
                 }
             }
         }
@@ -172,11 +336,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 async fn setup_poda_localnet(
     rpc_url: &str, 
     storage_provider_stake: u128,
+    n_storage_providers: usize,
 ) -> Result<(), Box<dyn std::error::Error>> {
     info!("🔍 Initializing Poda Localnet");
 
     let actors = get_actors();
     info!("🔍 Loaded {} actors from localnet/actors.json", actors.len());
+    // the first two actors are the dispenser and the challenger
+    let max_providers = actors.len() - 2;
+    if n_storage_providers == 0 || n_storage_providers > max_providers {
+        return Err(format!("Between 1 and {} storage providers are supported, got {}", max_providers, n_storage_providers).into());
+    }
+    let provider_actors = &actors[2..n_storage_providers + 2];
 
     info!("💰 Funding service accounts so that they have more than 1.5 ETH...");
     let faucet_signer = PrivateKeySigner::from_str(FAUCET_PRIVATE_KEY).unwrap();
@@ -184,7 +355,7 @@ async fn setup_poda_localnet(
     let faucet_address = faucet_signer.address();
     info!("🔍 Faucet address: {:?}", faucet_address);
     let faucet = get_provider_for_signer(faucet_signer, rpc_url).await;
-    faucet_if_needed(&faucet, &actors).await;
+    faucet_if_needed(&faucet, &actors, DEFAULT_FAUCET_MIN_BALANCE, DEFAULT_FAUCET_AMOUNT).await;
     info!("💰 Funding service accounts so that they have more than 1.5 ETH... done");
 
     info!("🔍 Deploying Poda contract...");
@@ -193,10 +364,10 @@ async fn setup_poda_localnet(
 
     info!("Registering storage providers...");
     let port_start_from = 8001; 
-    for (i, actor) in actors[2..N_STORAGE_PROVIDERS + 2].iter().enumerate() {
+    for (i, actor) in provider_actors.iter().enumerate() {
         let signer = PrivateKeySigner::from_str(&actor.private_key).unwrap();
         let client = PodaClient::new(signer, rpc_url.to_string(), poda_address).await;
-        let base_url = format!("http://host.docker.internal:{}", port_start_from + i as u16);
+        let base_url = format!("http://{}:{}", LOCALNET_PROVIDER_HOST, port_start_from + i as u16);
 
         let name = format!("storage-provider-{}", i);
         let res = client.register_provider(name, base_url.clone(), storage_provider_stake).await;
@@ -209,12 +380,12 @@ async fn setup_poda_localnet(
     info!("Network architecture:");
     info!("  - Challenger: {} with no exposed http server", actors[1].address);
     info!("  - Dispencer: {} at {}", actors[0].address, format!("http://localhost:{}", 8000));
-    for (i, actor) in actors[2..N_STORAGE_PROVIDERS + 2].iter().enumerate() {
+    for (i, actor) in provider_actors.iter().enumerate() {
         info!("  - Storage Provider {}: {} at {}", i, actor.address, format!("http://localhost:{}", 8001 + i as u16));
     }
 
     info!("🔍 Generating {} file...", ENV_FILE_NAME);
-    let storage_provider_private_keys = actors[2..N_STORAGE_PROVIDERS + 2].iter().map(|actor| actor.private_key.clone()).collect();
+    let storage_provider_private_keys = provider_actors.iter().map(|actor| actor.private_key.clone()).collect::<Vec<_>>();
     let regenerate_env_file = generate_env_file(FAUCET_PRIVATE_KEY, FAUCET_PRIVATE_KEY, poda_address, &storage_provider_private_keys).await;
     if regenerate_env_file.is_err() {
         error!("Failed to generate {} file: {:?}", ENV_FILE_NAME, regenerate_env_file.err());
@@ -223,26 +394,8 @@ async fn setup_poda_localnet(
     Ok(())
 }
 
-async fn generate_env_file(dispenser_private_key: &str, challenger_private_key: &str, poda_address: Address, storage_provider_private_keys: &Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
-    let env_file = format!(
-"# Blockchain Configuration
-RPC_URL=http://host.docker.internal:8545
-PODA_ADDRESS={}
-
-# Service Configuration
-DISPENCER_PRIVATE_KEY={}
-CHALLENGER_PRIVATE_KEY={}
-
-# Storage Provider Private Keys
-STORAGE_PROVIDER_1_PRIVATE_KEY={}
-STORAGE_PROVIDER_2_PRIVATE_KEY={}
-STORAGE_PROVIDER_3_PRIVATE_KEY={}     ", 
-        poda_address, 
-        dispenser_private_key, challenger_private_key,
-        storage_provider_private_keys[0],
-        storage_provider_private_keys[1],
-        storage_provider_private_keys[2]
-    );
+async fn generate_env_file(dispenser_private_key: &str, challenger_private_key: &str, poda_address: Address, storage_provider_private_keys: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let env_file = env_file_content(dispenser_private_key, challenger_private_key, poda_address, storage_provider_private_keys);
 
     let current_dir = std::env::current_dir()?;
     let project_root = if current_dir.ends_with("client") {
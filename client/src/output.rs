@@ -0,0 +1,56 @@
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::ValueEnum;
+use std::{fs, path::Path};
+
+/// How retrieved bytes are rendered when printed to stdout
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputEncoding {
+    #[default]
+    Hex,
+    Base64,
+    Utf8,
+}
+
+pub fn encode_data(data: &[u8], encoding: OutputEncoding) -> String {
+    match encoding {
+        OutputEncoding::Hex => format!("0x{}", hex::encode(data)),
+        OutputEncoding::Base64 => STANDARD.encode(data),
+        OutputEncoding::Utf8 => String::from_utf8_lossy(data).into_owned(),
+    }
+}
+
+/// Writes the raw bytes to `out` if given, otherwise prints them to stdout using `encoding`
+pub fn write_output(data: &[u8], out: Option<&Path>, encoding: OutputEncoding) -> Result<()> {
+    match out {
+        Some(path) => fs::write(path, data)?,
+        None => println!("{}", encode_data(data, encoding)),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_roundtrip_through_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("retrieved.bin");
+        let data: Vec<u8> = (0..=255u8).chain([0, 0xff, 0x80, 0x00]).collect();
+
+        write_output(&data, Some(&path), OutputEncoding::Utf8).unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), data);
+    }
+
+    #[test]
+    fn test_encode_data() {
+        let data = b"poda";
+
+        assert_eq!(encode_data(data, OutputEncoding::Hex), "0x706f6461");
+        assert_eq!(encode_data(data, OutputEncoding::Base64), "cG9kYQ==");
+        assert_eq!(encode_data(data, OutputEncoding::Utf8), "poda");
+    }
+}
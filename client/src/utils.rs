@@ -1,10 +1,17 @@
 use anyhow::Result;
+use std::time::Duration;
 use common::{
     constants::ONE_ETH,
+    http::shared_client,
 };
-use pod::{Address, EthereumWallet, PodProvider, PodProviderBuilder, PrivateKeySigner, Provider, U256};
+use futures::future::join_all;
+use pod::{client::PodaClientTrait, Address, EthereumWallet, PodProvider, PodProviderBuilder, PrivateKeySigner, Provider, U256};
 use serde::Deserialize;
 
+// Actors below this balance are topped up by the faucet
+pub const DEFAULT_FAUCET_MIN_BALANCE: u128 = 3 * ONE_ETH / 2;
+pub const DEFAULT_FAUCET_AMOUNT: u128 = ONE_ETH;
+
 #[derive(Deserialize)]
 pub struct Actor {
     pub address: Address,
@@ -49,13 +56,40 @@ pub fn get_actors() -> Vec<Actor> {
     ]
 }
 
-pub async fn faucet_if_needed(faucet: &PodProvider, actors: &Vec<Actor>) {
+/// Renders the localnet env file, numbering provider keys STORAGE_PROVIDER_1_PRIVATE_KEY onwards
+pub fn env_file_content(dispenser_private_key: &str, challenger_private_key: &str, poda_address: Address, storage_provider_private_keys: &[String]) -> String {
+    let mut env_file = format!(
+"# Blockchain Configuration
+RPC_URL=http://host.docker.internal:8545
+PODA_ADDRESS={}
+
+# Service Configuration
+DISPENCER_PRIVATE_KEY={}
+CHALLENGER_PRIVATE_KEY={}
+
+# Storage Provider Private Keys
+",
+        poda_address,
+        dispenser_private_key, challenger_private_key,
+    );
+    for (i, private_key) in storage_provider_private_keys.iter().enumerate() {
+        env_file.push_str(&format!("STORAGE_PROVIDER_{}_PRIVATE_KEY={}\n", i + 1, private_key));
+    }
+
+    env_file
+}
+
+fn needs_faucet(balance: U256, min_balance: u128) -> bool {
+    balance < U256::from(min_balance)
+}
+
+/// Sends `amount` wei to every actor holding less than `min_balance` wei
+pub async fn faucet_if_needed(faucet: &PodProvider, actors: &Vec<Actor>, min_balance: u128, amount: u128) {
     for actor in actors {
-        let min_balance = U256::from(ONE_ETH) * U256::from(1.5);
         let balance = faucet.get_balance(actor.address).await.unwrap();
 
-        if balance < min_balance {
-            faucet.transfer(actor.address, U256::from(ONE_ETH)).await.unwrap();
+        if needs_faucet(balance, min_balance) {
+            faucet.transfer(actor.address, U256::from(amount)).await.unwrap();
         }
     }
 }
@@ -69,11 +103,150 @@ pub async fn get_provider_for_signer(signer: PrivateKeySigner, rpc_url: &str) ->
 }
 
 pub async fn health_check(url: String) -> Result<()> {
-    let client = reqwest::Client::new();
-    let res = client.get(url + "/health").send().await?;
+    let res = shared_client().get(url + "/health").send().await?;
     if !res.status().is_success() {
         return Err(anyhow::anyhow!("Failed to check health, status: {}", res.status()));
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Retries the health check up to `retries` extra times, returning the outcome and the number of attempts made
+pub async fn health_check_with_retries(url: &str, retries: u32, interval: Duration) -> (Result<()>, u32) {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let res = health_check(url.to_string()).await;
+        if res.is_ok() || attempts > retries {
+            return (res, attempts);
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Probes every URL's /health concurrently, returning whether each answered within `retries`
+/// extra attempts
+pub async fn health_check_urls(urls: &[String], retries: u32, interval: Duration) -> Vec<bool> {
+    let probes = urls.iter().map(|url| async move {
+        let (res, _) = health_check_with_retries(url, retries, interval).await;
+        res.is_ok()
+    });
+
+    join_all(probes).await
+}
+
+/// Probes every registered provider's /health concurrently, returning each provider's
+/// address, URL and whether it answered within `retries` extra attempts
+pub async fn health_check_all<T: PodaClientTrait>(pod: &T, retries: u32, interval: Duration) -> Result<Vec<(Address, String, bool)>> {
+    let providers = pod.get_providers().await?;
+    let urls = providers.iter().map(|provider| provider.url.clone()).collect::<Vec<_>>();
+    let up = health_check_urls(&urls, retries, interval).await;
+
+    Ok(providers.into_iter().zip(up).map(|(provider, up)| (provider.addr, provider.url, up)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pod::client::{MockPodaClientTrait, ProviderInfo};
+    use std::str::FromStr;
+    use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpListener};
+    use warp::Filter;
+
+    #[tokio::test]
+    async fn test_health_check_retries_until_service_is_up() {
+        // Reserve a port, then leave it closed for a while to simulate a service that is briefly down
+        let port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            let listener = TcpListener::bind(("127.0.0.1", port)).await.unwrap();
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 1024];
+                let _ = socket.read(&mut buf).await;
+                socket.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n").await.unwrap();
+            }
+        });
+
+        let url = format!("http://127.0.0.1:{}", port);
+        let (res, attempts) = health_check_with_retries(&url, 10, Duration::from_millis(50)).await;
+
+        assert!(res.is_ok());
+        assert!(attempts > 1 && attempts <= 11, "unexpected number of attempts: {}", attempts);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_gives_up_after_retries() {
+        let port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+
+        let url = format!("http://127.0.0.1:{}", port);
+        let (res, attempts) = health_check_with_retries(&url, 2, Duration::from_millis(10)).await;
+
+        assert!(res.is_err());
+        assert_eq!(attempts, 3);
+    }
+
+    fn provider(addr: Address, url: String) -> ProviderInfo {
+        ProviderInfo {
+            name: format!("Provider {}", addr),
+            url,
+            addr,
+            registeredAt: 0,
+            challengeCount: 0,
+            challengeSuccessCount: 0,
+            active: true,
+            stakedAmount: U256::from(100),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_check_all_reports_each_provider() {
+        let live = warp::path("health").map(|| "ok");
+        let (live_addr, server) = warp::serve(live).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        let failing = warp::path("health").map(|| warp::reply::with_status("down", warp::http::StatusCode::SERVICE_UNAVAILABLE));
+        let (failing_addr, server) = warp::serve(failing).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        let closed_port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+
+        let providers = vec![
+            provider(Address::with_last_byte(1), format!("http://{}", live_addr)),
+            provider(Address::with_last_byte(2), format!("http://{}", failing_addr)),
+            provider(Address::with_last_byte(3), format!("http://127.0.0.1:{}", closed_port)),
+        ];
+        let expected = providers.iter().zip([true, false, false]).map(|(p, up)| (p.addr, p.url.clone(), up)).collect::<Vec<_>>();
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_providers().returning(move || Ok(providers.clone()));
+
+        assert_eq!(health_check_all(&pod, 0, Duration::ZERO).await.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_faucet_tops_up_actors_below_the_default_threshold() {
+        assert!(needs_faucet(U256::ZERO, DEFAULT_FAUCET_MIN_BALANCE));
+        // an actor left with a single faucet payment is still topped up
+        assert!(needs_faucet(U256::from(ONE_ETH), DEFAULT_FAUCET_MIN_BALANCE));
+        assert!(needs_faucet(U256::from(DEFAULT_FAUCET_MIN_BALANCE - 1), DEFAULT_FAUCET_MIN_BALANCE));
+        assert!(!needs_faucet(U256::from(DEFAULT_FAUCET_MIN_BALANCE), DEFAULT_FAUCET_MIN_BALANCE));
+        assert!(!needs_faucet(U256::from(2 * ONE_ETH), DEFAULT_FAUCET_MIN_BALANCE));
+    }
+
+    #[test]
+    fn test_env_file_lists_every_provider_key() {
+        let actors = get_actors();
+        let keys = actors[2..7].iter().map(|actor| actor.private_key.clone()).collect::<Vec<_>>();
+        let content = env_file_content(&actors[0].private_key, &actors[1].private_key, Address::with_last_byte(9), &keys);
+
+        let provider_keys = content.lines()
+            .filter_map(|line| line.strip_prefix("STORAGE_PROVIDER_"))
+            .map(|line| line.split_once("_PRIVATE_KEY=").unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(provider_keys.len(), 5);
+        for (i, ((n, key), actor)) in provider_keys.iter().zip(&actors[2..7]).enumerate() {
+            assert_eq!(n.parse::<usize>().unwrap(), i + 1);
+            assert_eq!(*key, actor.private_key);
+            assert!(PrivateKeySigner::from_str(key).is_ok());
+        }
+    }
+}
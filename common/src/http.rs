@@ -0,0 +1,53 @@
+use std::{sync::OnceLock, time::Duration};
+
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+static SHARED_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpTimeouts {
+    pub connect: Duration,
+    // covers the whole request, from connecting until the response body is read
+    pub request: Duration,
+}
+
+impl Default for HttpTimeouts {
+    fn default() -> Self {
+        Self { connect: DEFAULT_CONNECT_TIMEOUT, request: DEFAULT_REQUEST_TIMEOUT }
+    }
+}
+
+impl HttpTimeouts {
+    /// Reads HTTP_CONNECT_TIMEOUT_SECS and HTTP_REQUEST_TIMEOUT_SECS, falling back to the defaults
+    pub fn from_env() -> Self {
+        let secs = |name: &str, default: Duration| std::env::var(name)
+            .map(|secs| Duration::from_secs(secs.parse::<u64>().unwrap_or_else(|_| panic!("{} must be a number of seconds", name))))
+            .unwrap_or(default);
+
+        Self {
+            connect: secs("HTTP_CONNECT_TIMEOUT_SECS", DEFAULT_CONNECT_TIMEOUT),
+            request: secs("HTTP_REQUEST_TIMEOUT_SECS", DEFAULT_REQUEST_TIMEOUT),
+        }
+    }
+}
+
+pub fn build_client(timeouts: HttpTimeouts) -> reqwest::Client {
+    reqwest::Client::builder()
+        .connect_timeout(timeouts.connect)
+        .timeout(timeouts.request)
+        .build()
+        .expect("HTTP client is built from timeouts only")
+}
+
+/// Sets the timeouts of the shared client. Only the first call has an effect, and only before the
+/// client was first used, so binaries call it at startup
+pub fn init_shared_client(timeouts: HttpTimeouts) {
+    SHARED_CLIENT.get_or_init(|| build_client(timeouts));
+}
+
+/// Client for every outbound HTTP call, so connections are pooled and no call can hang forever.
+/// Cloning it is cheap, clones share the connection pool
+pub fn shared_client() -> reqwest::Client {
+    SHARED_CLIENT.get_or_init(|| build_client(HttpTimeouts::default())).clone()
+}
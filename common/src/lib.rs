@@ -1,3 +1,5 @@
+pub mod clock;
 pub mod constants;
+pub mod http;
 pub mod log;
 pub mod types;
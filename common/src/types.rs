@@ -13,4 +13,68 @@ impl Chunk {
         let data = self.data.as_slice();
         keccak256((self.index, keccak256(data)).abi_encode())
     }
-}
\ No newline at end of file
+
+    /// keccak256(commitment || index || data || provider), the packed layout the contract checks
+    /// challenge responses against. Anyone holding the data can compute it, so it is a
+    /// self-consistency check on the provider's answers rather than a proof of storage
+    pub fn possession_proof(&self, commitment: FixedBytes<32>, provider: Address) -> FixedBytes<32> {
+        keccak256([commitment.as_slice(), &self.index.to_be_bytes(), &self.data, provider.as_slice()].concat())
+    }
+}
+
+pub fn verify_possession_proof(commitment: FixedBytes<32>, chunk: &Chunk, provider: Address, proof: FixedBytes<32>) -> bool {
+    chunk.possession_proof(commitment, provider) == proof
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ChunkIndexError {
+    Duplicate(u16),
+    OutOfRange { index: u16, total: usize },
+}
+
+impl std::fmt::Display for ChunkIndexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChunkIndexError::Duplicate(index) => write!(f, "Chunk index {} appears more than once", index),
+            ChunkIndexError::OutOfRange { index, total } => write!(f, "Chunk index {} out of range (total chunks: {})", index, total),
+        }
+    }
+}
+
+impl std::error::Error for ChunkIndexError {}
+
+/// Checks that a batch of chunk indices is free of duplicates and within `total`, since a
+/// repeated index is a repeated point in KZG multi-verification
+pub fn check_chunk_indices(indices: &[u16], total: usize) -> Result<(), ChunkIndexError> {
+    let mut seen = std::collections::HashSet::new();
+    for &index in indices {
+        if index as usize >= total {
+            return Err(ChunkIndexError::OutOfRange { index, total });
+        }
+        if !seen.insert(index) {
+            return Err(ChunkIndexError::Duplicate(index));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_possession_proof_over_wrong_data_is_rejected() {
+        let commitment = FixedBytes::from([3u8; 32]);
+        let provider = Address::repeat_byte(7);
+        let chunk = Chunk { index: 5, data: b"the stored chunk".to_vec() };
+        let proof = chunk.possession_proof(commitment, provider);
+
+        assert!(verify_possession_proof(commitment, &chunk, provider, proof));
+
+        let forged = Chunk { index: 5, data: b"some other chunk".to_vec() }.possession_proof(commitment, provider);
+        assert!(!verify_possession_proof(commitment, &chunk, provider, forged));
+        assert!(!verify_possession_proof(commitment, &chunk, Address::repeat_byte(8), proof));
+        assert!(!verify_possession_proof(commitment, &Chunk { index: 6, ..chunk.clone() }, provider, proof));
+    }
+}
@@ -43,6 +43,9 @@ contract Poda {
     // Core commitment data
     bytes32[] public commitmentList;
     mapping(bytes32 => Commitment) public commitments;
+    // Running totals over every commitment, so network stats never iterate commitmentList
+    uint256 public totalOriginalDataSize;
+    uint256 public totalStoredDataSize;
 
     // Provider management
     address[] public providerList;
@@ -54,6 +57,9 @@ contract Poda {
     mapping(bytes32 => mapping(uint16 => address)) public chunkOwners; // commitment => chunkId => provider
     mapping(bytes32 => mapping(address => uint16[])) public providerChunks; // commitment => provider => chunk list
     mapping(bytes32 => uint16[]) public availableChunkList; // commitment => list of available chunks
+    // commitment => chunkId => keccak256(commitment, chunkId, chunkData, provider), set by attestations with proof.
+    // Only a self-consistency check: it is never verified at attestation, so it does not show the chunk was held
+    mapping(bytes32 => mapping(uint16 => bytes32)) public possessionProofs;
     
     // Bit-packed chunk availability for gas efficiency
     mapping(bytes32 => mapping(uint256 => uint256)) public chunkAvailability; // commitment => word => bitfield
@@ -77,6 +83,7 @@ contract Poda {
     event ChunkAttestation(bytes32 indexed commitment, address indexed provider, uint16 chunkId);
     event CommitmentReady(bytes32 indexed commitment, uint16 availableChunks);
     event ChunkChallengeIssued(bytes32 indexed challengeId, bytes32 indexed commitment, uint16 chunkId, address indexed provider);
+    event ProviderUrlUpdated(address indexed provider, string url);
 
     // =============================================================================
     // MODIFIERS (unchanged)
@@ -120,6 +127,12 @@ contract Poda {
         providerUrls[msg.sender] = url;
     }
 
+    function updateProviderUrl(string calldata url) external onlyRegisteredProvider {
+        require(bytes(url).length > 0 && bytes(url).length <= 128, "Invalid URL length");
+        providerUrls[msg.sender] = url;
+        emit ProviderUrlUpdated(msg.sender, url);
+    }
+
     // =============================================================================
     // REED-SOLOMON COMMITMENT OPERATIONS
     // =============================================================================
@@ -145,6 +158,8 @@ contract Poda {
         );
 
         commitmentList.push(commitment);
+        totalOriginalDataSize += size;
+        totalStoredDataSize += (uint256(size) * totalChunks) / requiredChunks;
         commitments[commitment] = Commitment({
             size: size,
             timestamp: uint32(block.timestamp),
@@ -161,6 +176,27 @@ contract Poda {
         bytes32 commitment, 
         uint16[] calldata chunkIds
     ) external onlyRegisteredProvider validCommitment(commitment) {
+        _attestChunks(commitment, chunkIds);
+    }
+
+    // Attests chunks together with a possession proof per chunk, which challenge responses
+    // for these chunks must then match. Nothing is verified here, the proof only binds the
+    // attester to the data it later answers with, which the merkle proof already pins down
+    function submitChunkAttestationsWithProof(
+        bytes32 commitment,
+        uint16[] calldata chunkIds,
+        bytes32[] calldata proofs
+    ) external onlyRegisteredProvider validCommitment(commitment) {
+        require(chunkIds.length == proofs.length, "Proof count mismatch");
+        _attestChunks(commitment, chunkIds);
+
+        for (uint256 i = 0; i < chunkIds.length;) {
+            possessionProofs[commitment][chunkIds[i]] = proofs[i];
+            unchecked { ++i; }
+        }
+    }
+
+    function _attestChunks(bytes32 commitment, uint16[] calldata chunkIds) internal {
         require(chunkIds.length > 0 && chunkIds.length <= 50, "Invalid chunk count"); // Limit batch size
         
         Commitment storage comm = commitments[commitment];
@@ -277,7 +313,7 @@ contract Poda {
         return chunkOwners[commitment][chunkId];
     }
     
-    function isChunkAvailable(bytes32 commitment, uint16 chunkId) external view returns (bool) {
+    function isChunkAvailable(bytes32 commitment, uint16 chunkId) public view returns (bool) {
         uint256 wordIndex = chunkId / 256;
         uint256 bitIndex = chunkId % 256;
         return (chunkAvailability[commitment][wordIndex] & (1 << bitIndex)) != 0;
@@ -296,6 +332,21 @@ contract Poda {
         return statuses;
     }
 
+    function getChunkInfoBatch(bytes32 commitment, uint16[] calldata chunkIds) external view returns (
+        address[] memory owners,
+        bool[] memory available
+    ) {
+        uint256 length = chunkIds.length;
+        owners = new address[](length);
+        available = new bool[](length);
+
+        for (uint256 i = 0; i < length;) {
+            owners[i] = chunkOwners[commitment][chunkIds[i]];
+            available[i] = isChunkAvailable(commitment, chunkIds[i]);
+            unchecked { ++i; }
+        }
+    }
+
     // =============================================================================
     // CHALLENGE SYSTEM
     // =============================================================================
@@ -442,7 +493,13 @@ contract Poda {
         require(proof.length > 0, "Invalid proof");
         require(activeChunkChallenges[commitment][chunkId][msg.sender].issuedAt + CHALLENGE_PERIOD > block.timestamp, "Challenge expired");
 
-        if (verifyChunkProof(proof, commitment, chunkId, chunkData)) {
+        // the merkle proof below already fixes chunkData, so this only catches a provider answering
+        // with different data than it committed to itself, it proves nothing about storage
+        bytes32 possessionProof = possessionProofs[commitment][chunkId];
+        bool possessionValid = possessionProof == bytes32(0)
+            || possessionProof == keccak256(abi.encodePacked(commitment, chunkId, chunkData, msg.sender));
+
+        if (possessionValid && verifyChunkProof(proof, commitment, chunkId, chunkData)) {
             providers[msg.sender].challengeSuccessCount++;
         }
         else {
@@ -473,6 +530,7 @@ contract Poda {
         
         // Remove chunk from provider
         chunkOwners[commitment][chunkId] = address(0);
+        delete possessionProofs[commitment][chunkId];
         
         // Update availability count
         Commitment storage comm = commitments[commitment];
@@ -540,4 +598,21 @@ contract Poda {
         
         return (originalSize, totalStoredSize, redundancyRatio);
     }
+
+    function getNetworkStorageStats() external view returns (
+        uint256 totalCommitments,
+        uint256 totalOriginalData,
+        uint256 totalStoredData,
+        uint256 averageEfficiency // stored/original in basis points (10000 = 100%)
+    ) {
+        totalCommitments = commitmentList.length;
+        totalOriginalData = totalOriginalDataSize;
+        totalStoredData = totalStoredDataSize;
+
+        if (totalOriginalData > 0) {
+            averageEfficiency = (totalStoredData * 10000) / totalOriginalData;
+        }
+
+        return (totalCommitments, totalOriginalData, totalStoredData, averageEfficiency);
+    }
 }
\ No newline at end of file
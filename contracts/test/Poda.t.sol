@@ -105,6 +105,27 @@ contract PodaTest is Test {
         poda.registerProvider("Dave", longUrl);
     }
 
+    function test_UpdateProviderUrl() public {
+        vm.prank(alice);
+        poda.updateProviderUrl("https://alice.example.org");
+
+        Poda.ProviderInfo memory info = poda.getProviderInfo(alice);
+        assertEq(info.url, "https://alice.example.org");
+        assertEq(info.name, "Provider Alice");
+    }
+
+    function test_UpdateProviderUrl_Unregistered() public {
+        vm.prank(dave);
+        vm.expectRevert("Provider not registered or inactive");
+        poda.updateProviderUrl("https://dave.com");
+    }
+
+    function test_UpdateProviderUrl_InvalidURL() public {
+        vm.prank(alice);
+        vm.expectRevert("Invalid URL length");
+        poda.updateProviderUrl("");
+    }
+
     // =============================================================================
     // REED-SOLOMON COMMITMENT TESTS
     // =============================================================================
@@ -219,6 +240,41 @@ contract PodaTest is Test {
         poda.submitChunkAttestations(COMMITMENT_1, chunks);
     }
     
+    function test_SubmitChunkAttestationsWithProof() public {
+        vm.prank(alice);
+        poda.submitCommitment(COMMITMENT_1, DATA_SIZE, TOTAL_CHUNKS, REQUIRED_CHUNKS, KZG_COMMITMENT_1);
+
+        uint16[] memory chunks = new uint16[](2);
+        chunks[0] = 0;
+        chunks[1] = 1;
+        bytes32[] memory proofs = new bytes32[](2);
+        proofs[0] = keccak256(abi.encodePacked(COMMITMENT_1, uint16(0), bytes("chunk 0"), bob));
+        proofs[1] = keccak256(abi.encodePacked(COMMITMENT_1, uint16(1), bytes("chunk 1"), bob));
+
+        vm.prank(bob);
+        poda.submitChunkAttestationsWithProof(COMMITMENT_1, chunks, proofs);
+
+        assertEq(poda.getChunkOwner(COMMITMENT_1, 0), bob);
+        assertEq(poda.possessionProofs(COMMITMENT_1, 0), proofs[0]);
+        assertEq(poda.possessionProofs(COMMITMENT_1, 1), proofs[1]);
+        (Poda.Commitment memory commitment,) = poda.getCommitmentInfo(COMMITMENT_1);
+        assertEq(commitment.availableChunks, 2);
+    }
+
+    function test_SubmitChunkAttestationsWithProof_ProofCountMismatch() public {
+        vm.prank(alice);
+        poda.submitCommitment(COMMITMENT_1, DATA_SIZE, TOTAL_CHUNKS, REQUIRED_CHUNKS, KZG_COMMITMENT_1);
+
+        uint16[] memory chunks = new uint16[](2);
+        chunks[0] = 0;
+        chunks[1] = 1;
+        bytes32[] memory proofs = new bytes32[](1);
+
+        vm.prank(bob);
+        vm.expectRevert("Proof count mismatch");
+        poda.submitChunkAttestationsWithProof(COMMITMENT_1, chunks, proofs);
+    }
+
     function test_CommitmentRecoverable() public {
         // Create commitment requiring 4 chunks
         vm.prank(alice);
@@ -418,6 +474,32 @@ contract PodaTest is Test {
         assertFalse(statuses[2]);  // COMMITMENT_3 doesn't exist
     }
 
+    function test_GetChunkInfoBatch() public {
+        vm.prank(alice);
+        poda.submitCommitment(COMMITMENT_1, DATA_SIZE, TOTAL_CHUNKS, REQUIRED_CHUNKS, KZG_COMMITMENT_1);
+
+        uint16[] memory chunks = new uint16[](2);
+        chunks[0] = 0;
+        chunks[1] = 2;
+        vm.prank(alice);
+        poda.submitChunkAttestations(COMMITMENT_1, chunks);
+
+        uint16[] memory chunkIds = new uint16[](3);
+        chunkIds[0] = 2;
+        chunkIds[1] = 1;
+        chunkIds[2] = 0;
+
+        (address[] memory owners, bool[] memory available) = poda.getChunkInfoBatch(COMMITMENT_1, chunkIds);
+
+        assertEq(owners.length, 3);
+        assertEq(owners[0], alice);
+        assertEq(owners[1], address(0)); // chunk 1 was never attested
+        assertEq(owners[2], alice);
+        assertTrue(available[0]);
+        assertFalse(available[1]);
+        assertTrue(available[2]);
+    }
+
     // =============================================================================
     // STORAGE EFFICIENCY TESTS
     // =============================================================================
@@ -434,6 +516,21 @@ contract PodaTest is Test {
         assertEq(redundancyRatio, (TOTAL_CHUNKS * 10000) / REQUIRED_CHUNKS); // 6 * 10000 / 4 = 15000 (150%)
     }
 
+    function test_GetNetworkStorageStats() public {
+        vm.startPrank(alice);
+        poda.submitCommitment(COMMITMENT_1, DATA_SIZE, TOTAL_CHUNKS, REQUIRED_CHUNKS, KZG_COMMITMENT_1);
+        poda.submitCommitment(COMMITMENT_2, DATA_SIZE * 2, 8, 4, KZG_COMMITMENT_1);
+        vm.stopPrank();
+
+        (uint256 totalCommitments, uint256 totalOriginalData, uint256 totalStoredData, uint256 averageEfficiency) =
+            poda.getNetworkStorageStats();
+
+        assertEq(totalCommitments, 2);
+        assertEq(totalOriginalData, DATA_SIZE * 3); // 1024 + 2048
+        assertEq(totalStoredData, 1536 + 4096);
+        assertEq(averageEfficiency, (uint256(1536 + 4096) * 10000) / (DATA_SIZE * 3));
+    }
+
     // =============================================================================
     // INTEGRATION TESTS
     // =============================================================================
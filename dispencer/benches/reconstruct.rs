@@ -0,0 +1,49 @@
+//! Retrieval-side cost of rebuilding a payload from its chunks: erasure decoding from the
+//! worst-case subset (every parity shard, the rest data shards) followed by re-encoding the
+//! result and recomputing the merkle root it must match.
+//!
+//! Runs in memory against a mock pod client. `cargo bench -p dispencer` to run.
+
+use common::{constants::{REQUIRED_SHARDS, TOTAL_SHARDS}, types::Chunk};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use dispencer::dispenser::Dispenser;
+use merkle_tree::gen_merkle_tree;
+use pod::client::MockPodaClientTrait;
+
+const PAYLOAD_SIZES: [usize; 3] = [64 * 1024, 1024 * 1024, 8 * 1024 * 1024];
+const SHARD_COUNTS: [(usize, usize); 2] = [(REQUIRED_SHARDS, TOTAL_SHARDS), (32, 48)];
+
+// Drops the first `total - required` data shards so decoding has to rebuild as many as possible
+fn worst_case_subset(chunks: &[Chunk], required: usize, total: usize) -> Vec<Option<Chunk>> {
+    let missing = total - required;
+    chunks.iter().map(|chunk| (chunk.index as usize >= missing).then(|| chunk.clone())).collect()
+}
+
+fn reconstruct_verify(c: &mut Criterion) {
+    let dispenser = Dispenser::new(MockPodaClientTrait::new());
+    let mut group = c.benchmark_group("reconstruct_verify");
+    group.sample_size(10);
+
+    for (required, total) in SHARD_COUNTS {
+        for size in PAYLOAD_SIZES {
+            let data = (0..size).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+            let chunks = dispenser.erasure_encode(&data, required, total);
+            let root = gen_merkle_tree(&chunks).root();
+            let subset = worst_case_subset(&chunks, required, total);
+
+            group.throughput(Throughput::Bytes(size as u64));
+            group.bench_with_input(BenchmarkId::new(format!("{}of{}", required, total), size), &subset, |b, subset| {
+                b.iter(|| {
+                    let (decoded, _) = dispenser.erasure_decode(subset.clone(), required, total, size).unwrap();
+                    let recommitted = gen_merkle_tree(&dispenser.erasure_encode(&decoded, required, total)).root();
+                    assert_eq!(recommitted, root);
+                });
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, reconstruct_verify);
+criterion_main!(benches);
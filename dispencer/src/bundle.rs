@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use common::types::Chunk;
+use kzg::{kzg_multi_prove, kzg_multi_verify, types::{KzgCommitment, KzgProof}};
+use merkle_tree::{gen_merkle_tree, gen_proof, verify_proof, MerkleProof};
+use pod::FixedBytes;
+use serde::{Deserialize, Serialize};
+
+// Number of chunks sampled into an exported bundle
+pub const BUNDLE_SAMPLE_SIZE: usize = 8;
+
+/// Self-contained evidence that a commitment is internally consistent: the on-chain
+/// parameters together with a sample of chunks and their merkle and KZG proofs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationBundle {
+    pub commitment: FixedBytes<32>,
+    pub kzg_commitment: KzgCommitment,
+    pub size: u32,
+    pub total_chunks: u16,
+    pub required_chunks: u16,
+    pub chunks: Vec<Chunk>,
+    pub merkle_proofs: Vec<MerkleProof>,
+    pub kzg_proof: KzgProof,
+}
+
+/// Builds a bundle from the full set of encoded chunks, sampling `sample_size` of them
+/// evenly across the data and parity shards.
+pub fn build_bundle(
+    chunks: &[Chunk],
+    kzg_commitment: KzgCommitment,
+    size: u32,
+    required_chunks: u16,
+    sample_size: usize,
+) -> Result<VerificationBundle> {
+    if chunks.is_empty() {
+        return Err(anyhow::anyhow!("Cannot build a bundle without chunks"));
+    }
+
+    let total = chunks.len();
+    let sample_size = sample_size.clamp(1, total);
+    let indices = (0..sample_size).map(|i| i * total / sample_size).collect::<Vec<_>>();
+
+    let merkle_tree = gen_merkle_tree(chunks);
+    let sampled = indices.iter().map(|&i| chunks[i].clone()).collect::<Vec<_>>();
+    let merkle_proofs = sampled.iter()
+        .map(|chunk| gen_proof(&merkle_tree, chunk.clone()))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(VerificationBundle {
+        commitment: merkle_tree.root(),
+        kzg_commitment,
+        size,
+        total_chunks: total as u16,
+        required_chunks,
+        chunks: sampled,
+        merkle_proofs,
+        kzg_proof: kzg_multi_prove(chunks, &indices),
+    })
+}
+
+/// Checks a bundle for internal consistency without touching the network
+pub fn verify_bundle(bundle: &VerificationBundle) -> bool {
+    if bundle.required_chunks == 0 || bundle.total_chunks < bundle.required_chunks {
+        return false;
+    }
+    if bundle.chunks.is_empty() || bundle.chunks.len() != bundle.merkle_proofs.len() {
+        return false;
+    }
+
+    let shard_len = bundle.chunks[0].data.len();
+    if (shard_len * bundle.required_chunks as usize) < bundle.size as usize {
+        return false;
+    }
+
+    let mut seen = HashSet::new();
+    for (chunk, proof) in bundle.chunks.iter().zip(&bundle.merkle_proofs) {
+        if chunk.index >= bundle.total_chunks || !seen.insert(chunk.index) || chunk.data.len() != shard_len {
+            return false;
+        }
+        if !verify_proof(bundle.commitment, chunk, proof.clone()) {
+            return false;
+        }
+    }
+
+    let indices = bundle.chunks.iter().map(|c| c.index as usize).collect::<Vec<_>>();
+    kzg_multi_verify(&bundle.chunks, &indices, bundle.kzg_commitment.clone(), bundle.kzg_proof.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispenser::Dispenser;
+    use common::constants::REQUIRED_SHARDS;
+    use kzg::kzg_commit;
+    use pod::client::MockPodaClientTrait;
+
+    fn sample_bundle() -> VerificationBundle {
+        let dispenser = Dispenser::new(MockPodaClientTrait::new());
+        let data = "Bundle me up".repeat(100);
+        let chunks = dispenser.encode_payload(data.as_bytes());
+        let (kzg_commitment, _) = kzg_commit(&chunks);
+
+        build_bundle(&chunks, kzg_commitment, data.len() as u32, REQUIRED_SHARDS as u16, BUNDLE_SAMPLE_SIZE).unwrap()
+    }
+
+    #[test]
+    fn test_bundle_roundtrip() {
+        let bundle = sample_bundle();
+        assert_eq!(bundle.chunks.len(), BUNDLE_SAMPLE_SIZE);
+
+        let json = serde_json::to_string(&bundle).unwrap();
+        let decoded: VerificationBundle = serde_json::from_str(&json).unwrap();
+
+        assert!(verify_bundle(&decoded));
+    }
+
+    #[test]
+    fn test_tampered_bundle_fails() {
+        let mut bundle = sample_bundle();
+        bundle.chunks[1].data[0] ^= 0xff;
+        assert!(!verify_bundle(&bundle));
+
+        let mut bundle = sample_bundle();
+        bundle.chunks[1].index = bundle.chunks[2].index;
+        assert!(!verify_bundle(&bundle));
+
+        let mut bundle = sample_bundle();
+        bundle.size = u32::MAX;
+        assert!(!verify_bundle(&bundle));
+    }
+}
@@ -0,0 +1,232 @@
+use anyhow::{anyhow, bail, Result};
+use reed_solomon_erasure::{galois_16, galois_8, Field, ReedSolomon};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::{atomic::{AtomicUsize, Ordering}, Arc, Mutex}};
+use common::{log::debug, types::Chunk};
+
+/// Galois field the Reed-Solomon code works over. GF(2^8) allows at most 256 shards in total,
+/// GF(2^16) lifts that to 65536 at the cost of working on 2 byte symbols.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErasureField {
+    Galois8,
+    Galois16,
+}
+
+impl ErasureField {
+    /// The smallest field that fits `total_shards`, so encoding and decoding pick the same one
+    /// from the shard count recorded on-chain
+    pub fn for_total_shards(total_shards: usize) -> Self {
+        if total_shards <= galois_8::Field::ORDER {
+            ErasureField::Galois8
+        } else {
+            ErasureField::Galois16
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodingParams {
+    pub required_shards: usize,
+    pub total_shards: usize,
+    pub field: ErasureField,
+}
+
+impl CodingParams {
+    pub fn new(required_shards: usize, total_shards: usize) -> Self {
+        Self { required_shards, total_shards, field: ErasureField::for_total_shards(total_shards) }
+    }
+
+    pub fn with_field(mut self, field: ErasureField) -> Self {
+        self.field = field;
+        self
+    }
+
+    pub fn parity_shards(&self) -> usize {
+        self.total_shards - self.required_shards
+    }
+}
+
+// GF(2^16) works on 2 byte symbols, shards stay plain bytes outside this module
+fn to_symbols(shard: &[u8]) -> Result<Vec<[u8; 2]>> {
+    if !shard.len().is_multiple_of(2) {
+        bail!("Shard of {} bytes is not a whole number of 2 byte symbols", shard.len());
+    }
+    Ok(shard.chunks_exact(2).map(|pair| [pair[0], pair[1]]).collect())
+}
+
+fn to_bytes(symbols: &[[u8; 2]]) -> Vec<u8> {
+    symbols.iter().flatten().copied().collect()
+}
+
+fn codec<F: Field>(params: &CodingParams) -> Result<ReedSolomon<F>> {
+    ReedSolomon::<F>::new(params.required_shards, params.parity_shards())
+        .map_err(|e| anyhow!("Invalid erasure coding parameters: {:?}", e))
+}
+
+// Size of each shard when `data_len` bytes are split across `data_shards`, rounded up to an even
+// length
+pub(crate) fn shard_size(data_len: usize, data_shards: usize) -> usize {
+    let chunk_size = data_len.div_ceil(data_shards);
+    chunk_size + chunk_size % 2
+}
+
+fn split_to_chunks(data: &[u8], data_shards: usize) -> Vec<Vec<u8>> {
+    let chunk_size = shard_size(data.len(), data_shards);
+
+    let mut chunks = Vec::with_capacity(data_shards);
+    
+    for i in 0..data_shards {
+        let start = i * chunk_size;
+        let end = std::cmp::min(start + chunk_size, data.len());
+        
+        let mut chunk = vec![0u8; chunk_size];
+        if start < data.len() {
+            chunk[..end - start].copy_from_slice(&data[start..end]);
+        }
+        chunks.push(chunk);
+    }
+
+    chunks
+}
+
+pub fn create_shards(data: &[u8], required_shards: usize, total_shards: usize) -> Vec<Vec<u8>> {
+    let parity_shards = total_shards - required_shards;
+
+    let split_data = split_to_chunks(data, required_shards);
+    let split_data_len = split_data[0].len();
+
+    // add parity shareds of the same size as the data shards
+    let mut shards = Vec::with_capacity(total_shards);
+
+    // add the data shards
+    shards.extend(split_data);
+
+    // add the parity shards
+    shards.extend(vec![vec![0; split_data_len]; parity_shards]);
+
+    shards
+}
+
+type CodecMap<F> = Mutex<HashMap<(usize, usize), Arc<ReedSolomon<F>>>>;
+
+/// Reed-Solomon codecs built once per shard configuration and reused, since building one sets up
+/// the coding matrix
+#[derive(Default)]
+pub struct CodecCache {
+    galois_8: CodecMap<galois_8::Field>,
+    galois_16: CodecMap<galois_16::Field>,
+    // codecs constructed so far, cache hits do not count
+    built: AtomicUsize,
+}
+
+impl CodecCache {
+    pub fn built(&self) -> usize {
+        self.built.load(Ordering::Relaxed)
+    }
+
+    fn get<F: Field>(&self, codecs: &CodecMap<F>, params: &CodingParams) -> Result<Arc<ReedSolomon<F>>> {
+        let mut codecs = codecs.lock().unwrap();
+        let key = (params.required_shards, params.parity_shards());
+        if let Some(codec) = codecs.get(&key) {
+            return Ok(codec.clone());
+        }
+
+        let codec = Arc::new(codec::<F>(params)?);
+        self.built.fetch_add(1, Ordering::Relaxed);
+        codecs.insert(key, codec.clone());
+        Ok(codec)
+    }
+
+    /// Fills in the parity shards, which must already be allocated after the data shards
+    pub fn encode(&self, params: &CodingParams, shards: &mut [Vec<u8>]) -> Result<()> {
+        match params.field {
+            ErasureField::Galois8 => self.get(&self.galois_8, params)?.encode(shards),
+            ErasureField::Galois16 => {
+                let mut symbols = shards.iter().map(|s| to_symbols(s)).collect::<Result<Vec<_>>>()?;
+                self.get(&self.galois_16, params)?.encode(&mut symbols).map(|()| {
+                    for (shard, symbols) in shards.iter_mut().zip(symbols) {
+                        *shard = to_bytes(&symbols);
+                    }
+                })
+            }
+        }.map_err(|e| anyhow!("Failed to encode shards: {:?}", e))
+    }
+
+    /// Rebuilds every missing shard from the ones present
+    pub fn reconstruct(&self, params: &CodingParams, shards: &mut [Option<Vec<u8>>]) -> Result<()> {
+        match params.field {
+            ErasureField::Galois8 => self.get(&self.galois_8, params)?.reconstruct(shards),
+            ErasureField::Galois16 => {
+                let mut symbols = shards.iter().map(|s| s.as_deref().map(to_symbols).transpose()).collect::<Result<Vec<_>>>()?;
+                self.get(&self.galois_16, params)?.reconstruct(&mut symbols).map(|()| {
+                    for (shard, symbols) in shards.iter_mut().zip(symbols) {
+                        *shard = symbols.map(|s| to_bytes(&s));
+                    }
+                })
+            }
+        }.map_err(|e| anyhow!("Failed to reconstruct shards: {:?}", e))
+    }
+
+    /// Splits `data` into data shards and encodes them into `params.total_shards` chunks
+    pub fn encode_chunks(&self, data: &[u8], params: &CodingParams) -> Vec<Chunk> {
+        let total_shards = params.total_shards;
+        let mut master_copy = create_shards(data, params.required_shards, total_shards);
+
+        self.encode(params, &mut master_copy).unwrap();
+
+        let chunks = master_copy.iter().enumerate().map(|(index, shard)| Chunk {
+            index: index as u16,
+            data: shard.to_vec(),
+        }).collect::<Vec<_>>();
+
+        if chunks.len() != total_shards {
+            panic!("Invalid number of chunks: {}", chunks.len());
+        }
+
+        chunks
+    }
+
+    /// Reconstructs the data from the chunks present, returning it trimmed to `original_length`
+    /// along with the data chunks
+    pub fn decode_chunks(&self, chunks: Vec<Option<Chunk>>, params: &CodingParams, original_length: usize) -> Result<(Vec<u8>, Vec<Chunk>)> {
+        let required_shards = params.required_shards;
+
+        // Convert chunks to shards for reconstruction
+        let mut shards: Vec<Option<Vec<u8>>> = chunks.into_iter()
+            .map(|chunk| chunk.map(|c| c.data))
+            .collect();
+
+        let mut lengths = shards.iter().flatten().map(|shard| shard.len());
+        if let Some(first) = lengths.next() && let Some(other) = lengths.find(|len| *len != first) {
+            return Err(anyhow!("Shards have inconsistent lengths: {} and {} bytes", first, other));
+        }
+
+        debug!("Before reconstruction - shards: {:?}", shards);
+        self.reconstruct(params, &mut shards)?;
+        debug!("After reconstruction - shards: {:?}", shards);
+
+        // Get the reconstructed data chunks (first required_shards are the data shards)
+        let mut reconstructed_chunks: Vec<Chunk> = Vec::new();
+        let mut decoded = Vec::new();
+        
+        for (i, shard) in shards.iter().enumerate().take(required_shards) {
+            if let Some(data) = shard {
+                let chunk = Chunk {
+                    index: i as u16,
+                    data: data.to_owned(),
+                };
+
+                reconstructed_chunks.push(chunk);
+                decoded.extend_from_slice(data);
+            } else {
+                return Err(anyhow!("Missing data chunk after reconstruction"));
+            }
+        }
+        
+        // Trim to original length
+        decoded.truncate(original_length);
+        
+        Ok((decoded, reconstructed_chunks))
+    }
+}
@@ -1,109 +1,767 @@
-use std::{collections::HashMap, iter::zip};
+use std::{collections::{HashMap, HashSet}, iter::zip, sync::Arc, time::{SystemTime, UNIX_EPOCH}};
 
 use anyhow::Result;
-use merkle_tree::{gen_merkle_tree, MerkleProof};
-use pod::{client::{PodaClientTrait, ProviderInfo}, FixedBytes, U256};
-use storage_provider::http::{BatchRetrieveRequest, BatchRetrieveResponse, BatchStoreRequest};
-use common::{constants::{REQUIRED_SHARDS, TOTAL_SHARDS}, log::{debug, error, info, warn}, types::Chunk};
-use reed_solomon_erasure::ReedSolomon;
+use serde::{Deserialize, Serialize};
+use merkle_tree::{gen_merkle_tree, gen_multi_proof, MerkleMultiProof, MerkleProof};
+use pod::{client::{Commitment, PodaClientTrait, ProviderInfo, DEFAULT_AVAILABILITY_TIMEOUT}, Address, Bytes, FixedBytes, U256};
+use storage_provider::http::{BatchRetrieveRequest, BatchRetrieveResponse, BatchStoreRequest, ProofFormat, ProtocolFeature, StoreReceipt};
+use common::{clock::{system_clock, Clock}, constants::{REQUIRED_SHARDS, TOTAL_SHARDS}, log::{debug, error, info, warn}, types::{check_chunk_indices, keccak256, Chunk}};
 use sha3::{Digest, Keccak256};
-use kzg::{kzg_commit, kzg_multi_prove, types::KzgProof};
-type ChunkAssignment = HashMap<String, Vec<Chunk>>;
+use kzg::{kzg_commit, kzg_multi_prove_with_polynomial, kzg_prove_with_polynomial, kzg_verify, types::{KzgCommitment, KzgProof}, KZGPolynomial};
+use crate::bundle::{build_bundle, VerificationBundle, BUNDLE_SAMPLE_SIZE};
+use crate::error::{DispenserError, RetrieveError};
+use crate::coding::{create_shards, shard_size, CodecCache, CodingParams};
+use crate::metadata::{CommitmentMetadata, MetadataStore};
+use crate::transport::{HttpTransport, ProviderTransport};
 
-const MIN_DATA_SIZE: usize = 16;
+// Chunks per provider, keyed by provider address since names are not unique on-chain
+pub type ChunkAssignment = HashMap<Address, Vec<Chunk>>;
+
+// Submissions smaller than this are refused unless the dispenser is configured otherwise
+pub const DEFAULT_MIN_DATA_SIZE: usize = 16;
+// Small payloads are zero-padded up to this size so that every shard carries at least 32 bytes
+pub const DEFAULT_MIN_PAYLOAD_SIZE: usize = REQUIRED_SHARDS * 32;
+
+// Domain tag prefixed to the selection seed input, so the seed never collides with other
+// keccak256 hashes over a commitment
+const SELECTION_DOMAIN: &[u8] = b"poda.provider-selection.v1";
+
+// Deterministic per-chunk seed, so every dispenser assigns a chunk to the same provider:
+// the first 8 bytes, little-endian, of keccak256(SELECTION_DOMAIN || commitment || chunk_index as u16 LE)
+fn selection_seed(commitment: &FixedBytes<32>, chunk_index: u16) -> u64 {
+    let mut hasher = Keccak256::new();
+    hasher.update(SELECTION_DOMAIN);
+    hasher.update(commitment);
+    hasher.update(chunk_index.to_le_bytes());
+    let seed = hasher.finalize();
+    u64::from_le_bytes(seed[0..8].try_into().unwrap())
+}
+
+// Zero-pads the payload up to `min_payload_size` and erasure encodes it
+fn encode_padded(codecs: &CodecCache, data: &[u8], min_payload_size: usize) -> Vec<Chunk> {
+    let params = CodingParams::new(REQUIRED_SHARDS, TOTAL_SHARDS);
+    if data.len() >= min_payload_size {
+        return codecs.encode_chunks(data, &params);
+    }
+
+    let mut padded = data.to_vec();
+    padded.resize(min_payload_size, 0);
+    codecs.encode_chunks(&padded, &params)
+}
+
+// Data shards are equally sized, so re-encoding them yields the original parity shards
+fn reencode_data_chunks(codecs: &CodecCache, data_chunks: Vec<Chunk>) -> Vec<Chunk> {
+    let shards = data_chunks.into_iter().flat_map(|c| c.data).collect::<Vec<_>>();
+    codecs.encode_chunks(&shards, &CodingParams::new(REQUIRED_SHARDS, TOTAL_SHARDS))
+}
+
+/// Whether `merkle_root` and `kzg_commitment` were both computed from `chunks`, in that order.
+/// The contract stores the two side by side and cannot check this itself.
+pub fn verify_commitment_binding(chunks: &[Chunk], merkle_root: FixedBytes<32>, kzg_commitment: KzgCommitment) -> bool {
+    if chunks.is_empty() || gen_merkle_tree(chunks).root() != merkle_root {
+        return false;
+    }
+
+    let (expected, _) = kzg_commit(chunks);
+    expected.to_bytes() == kzg_commitment.to_bytes()
+}
+
+// Splits a /batch-retrieve response into the chunks that are the requested chunk and carry a valid
+// merkle proof, and the indices whose chunk did not. Chunks the provider did not return are neither.
+fn verify_served_chunks(commitment: FixedBytes<32>, chunk_ids: &[u16], response: BatchRetrieveResponse) -> (Vec<Chunk>, Vec<u16>) {
+    let mut verified = Vec::new();
+    let mut rejected = Vec::new();
+    let proofs = response.proofs.into_iter().chain(std::iter::repeat(None));
+    for ((&index, chunk), proof) in zip(zip(chunk_ids, response.chunks), proofs) {
+        let Some(chunk) = chunk else {
+            continue;
+        };
+        let valid = chunk.index == index
+            && (index as usize) < TOTAL_SHARDS
+            && proof.is_some_and(|proof| merkle_tree::verify_proof(commitment, &chunk, proof));
+        if valid {
+            verified.push(chunk);
+        } else {
+            rejected.push(index);
+        }
+    }
+
+    (verified, rejected)
+}
+
+// Stake-weighted assignment divides by the total stake, so it needs at least one staked provider
+fn ensure_eligible_providers(providers: &[ProviderInfo]) -> Result<(), DispenserError> {
+    if providers.iter().all(|p| p.stakedAmount.is_zero()) {
+        return Err(DispenserError::NoEligibleProviders);
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct SubmitResult {
+    pub commitment: FixedBytes<32>,
+    pub assignments: ChunkAssignment,
+    pub total_chunks: usize,
+    pub required_chunks: usize,
+    // chunks that were accepted by their assigned providers
+    pub promised_chunks: usize,
+    // receipts signed by the providers for the chunks sent to them in this submission
+    pub receipts: Vec<StoreReceipt>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RetrievalReport {
+    pub data: Vec<u8>,
+    // original size of the data as recorded on-chain
+    pub bytes: usize,
+    pub total_shards: usize,
+    pub required_shards: usize,
+    // chunks that were fetched from providers
+    pub shards_used: usize,
+    pub shards_missing: Vec<u16>,
+    // names of the providers that answered /batch-retrieve with at least one verified chunk, or no
+    // invalid ones
+    pub providers_responded: Vec<String>,
+    // names of the providers that were asked but did not answer or served invalid chunks, with the reason
+    pub providers_failed: Vec<(String, RetrieveError)>,
+}
+
+// Chunks gathered for a commitment, with which providers answered and why the others did not
+struct RetrievedChunks {
+    commitment_info: Commitment,
+    chunks: Vec<Option<Chunk>>,
+    providers_responded: Vec<String>,
+    providers_failed: Vec<(String, RetrieveError)>,
+}
+
+impl RetrievedChunks {
+    // Every chunk is in, or enough to decode when retrieval stops early
+    fn is_done(&self, stop_early: bool) -> bool {
+        let present = self.chunks.iter().filter(|c| c.is_some()).count();
+        present == self.chunks.len() || (stop_early && present >= REQUIRED_SHARDS)
+    }
+}
+
+/// Which providers a retrieval asks for chunks, and in which order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetrievalStrategy {
+    // asked before every other provider, in this order
+    pub preferred: Vec<Address>,
+    // stop asking providers once enough distinct chunks to decode are in hand
+    pub stop_early: bool,
+}
+
+/// Expected shape of a submission, worked out without encoding or sending any data
+#[derive(Debug, Clone)]
+pub struct SubmitEstimate {
+    pub total_chunks: usize,
+    pub required_chunks: usize,
+    // chunks each provider would be assigned, keyed by provider address
+    pub per_provider: HashMap<Address, usize>,
+    // size of all chunks together, parity included
+    pub total_stored_bytes: usize,
+}
+
+/// Outcome of checking one provider's copy of a chunk against its commitment
+#[derive(Debug, Clone)]
+pub struct AuditResult {
+    pub commitment: FixedBytes<32>,
+    pub chunk_id: u16,
+    // the provider the chunk is assigned to on-chain
+    pub provider: Address,
+    // false when the provider did not return the chunk, in which case neither check passes
+    pub retrieved: bool,
+    pub merkle_valid: bool,
+    pub kzg_valid: bool,
+}
+
+impl AuditResult {
+    pub fn passed(&self) -> bool {
+        self.merkle_valid && self.kzg_valid
+    }
+}
 
 pub struct Dispenser<T: PodaClientTrait> {
     pub pod: T,
+    min_data_size: usize,
+    min_payload_size: usize,
+    clock: Arc<dyn Clock>,
+    transport: Arc<dyn ProviderTransport>,
+    codecs: Arc<CodecCache>,
+    // whether providers that accept merkle multiproofs are sent one instead of per-chunk proofs
+    multiproof: bool,
+    // recorded assignments are a hint for retrieval, chain state stays authoritative
+    metadata: Option<Arc<dyn MetadataStore>>,
 }
 
 impl<T: PodaClientTrait> Dispenser<T> {
     pub fn new(pod: T) -> Self {
         info!("Creating dispenser");
-        Self { pod }
+        Self {
+            pod,
+            min_data_size: DEFAULT_MIN_DATA_SIZE,
+            min_payload_size: DEFAULT_MIN_PAYLOAD_SIZE,
+            clock: system_clock(),
+            transport: Arc::new(HttpTransport::default()),
+            codecs: Arc::new(CodecCache::default()),
+            multiproof: false,
+            metadata: None,
+        }
+    }
+
+    /// Records the metadata of submitted commitments, `None` (the default) works from chain state alone
+    pub fn with_metadata_store(mut self, metadata: Option<Arc<dyn MetadataStore>>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn with_transport(mut self, transport: Arc<dyn ProviderTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Sends a merkle multiproof to providers that accept one. Those providers keep no per-chunk
+    /// proof and cannot answer storage challenges for the chunks, so this is off by default.
+    pub fn with_multiproof(mut self, multiproof: bool) -> Self {
+        self.multiproof = multiproof;
+        self
+    }
+
+    pub fn with_min_data_size(mut self, min_data_size: usize) -> Self {
+        self.min_data_size = min_data_size;
+        self
+    }
+
+    pub fn with_min_payload_size(mut self, min_payload_size: usize) -> Self {
+        self.min_payload_size = min_payload_size;
+        self
+    }
+
+    pub fn min_data_size(&self) -> usize {
+        self.min_data_size
+    }
+
+    pub fn min_payload_size(&self) -> usize {
+        self.min_payload_size
+    }
+
+    // Runs CPU-bound coding and commitment work on the blocking pool, so that one large submission
+    // or retrieval does not hold up the other requests the runtime is serving
+    async fn run_blocking<R: Send + 'static>(&self, work: impl FnOnce(&CodecCache) -> R + Send + 'static) -> Result<R, DispenserError> {
+        let codecs = self.codecs.clone();
+        tokio::task::spawn_blocking(move || work(&codecs)).await.map_err(|e| DispenserError::Internal(e.into()))
+    }
+
+    // `encode_payload` off the runtime, along with the merkle root of the chunks
+    async fn encode_in_background(&self, data: &[u8]) -> Result<(Vec<Chunk>, FixedBytes<32>), DispenserError> {
+        let (data, min_payload_size) = (data.to_vec(), self.min_payload_size);
+        self.run_blocking(move |codecs| {
+            let chunks = encode_padded(codecs, &data, min_payload_size);
+            let root = gen_merkle_tree(&chunks).root();
+            (chunks, root)
+        }).await
+    }
+
+    // Decodes retrieved chunks off the runtime, returning the data and every chunk re-encoded from it
+    async fn decode_in_background(&self, chunks: Vec<Option<Chunk>>, original_length: usize) -> Result<(Vec<u8>, Vec<Chunk>), DispenserError> {
+        self.run_blocking(move |codecs| {
+            let (data, data_chunks) = codecs.decode_chunks(chunks, &CodingParams::new(REQUIRED_SHARDS, TOTAL_SHARDS), original_length)?;
+            anyhow::Ok((data, reencode_data_chunks(codecs, data_chunks)))
+        }).await?.map_err(DispenserError::from)
+    }
+
+    async fn kzg_commit_in_background(&self, chunks: &[Chunk]) -> Result<(KzgCommitment, KZGPolynomial), DispenserError> {
+        let chunks = chunks.to_vec();
+        self.run_blocking(move |_| kzg_commit(&chunks)).await
+    }
+
+    // `kzg_commit_in_background` for chunks already committed to by `root`. Debug builds check the
+    // root in the same pass, so the two commitments cannot come from different chunks
+    async fn kzg_commit_bound_to_root(&self, chunks: &[Chunk], root: FixedBytes<32>) -> Result<(KzgCommitment, KZGPolynomial), DispenserError> {
+        let chunks = chunks.to_vec();
+        self.run_blocking(move |_| {
+            debug_assert_eq!(gen_merkle_tree(&chunks).root(), root, "merkle root and KZG commitment were computed from different chunks");
+            kzg_commit(&chunks)
+        }).await
     }
 
-    pub async fn submit_data(&self, data: &[u8]) -> Result<(FixedBytes<32>, ChunkAssignment)> {
-        if data.len() < MIN_DATA_SIZE {
-            return Err(anyhow::anyhow!("Data size is too small. Must be at least {} bytes", MIN_DATA_SIZE));
+    pub async fn submit_data(&self, data: &[u8]) -> Result<SubmitResult, DispenserError> {
+        if data.len() < self.min_data_size {
+            return Err(DispenserError::DataTooSmall { size: data.len(), min: self.min_data_size });
         }
-        let storage_providers = self.pod.get_providers().await?.to_vec();
-        let chunks = self.erasure_encode(data, REQUIRED_SHARDS, TOTAL_SHARDS);
-        let merkle_tree = gen_merkle_tree(&chunks);
+        let started_at = self.clock.now();
+        let storage_providers = self.pod.get_eligible_providers().await.map_err(DispenserError::Pod)?.to_vec();
+        ensure_eligible_providers(&storage_providers)?;
+        let (chunks, root) = self.encode_in_background(data).await?;
 
-        let (kzg_commitment, _) = kzg_commit(&chunks);
-        let res = self.pod.submit_commitment(merkle_tree.root(), data.len() as u32, TOTAL_SHARDS as u16, REQUIRED_SHARDS as u16, kzg_commitment.try_into().unwrap()).await;
-        if res.is_err() {
-            error!("Failed to submit commitment: {:?}", res.err());
-            return Err(anyhow::anyhow!("Failed to submit commitment. Submit already exists"));
+        // the merkle root is much cheaper than the KZG commitment, so resubmissions stop here
+        if self.pod.commitment_exists(root).await.map_err(DispenserError::Pod)? {
+            info!("Commitment {:?} was already submitted", root);
+            return Err(DispenserError::AlreadySubmitted(root));
+        }
+
+        let (kzg_commitment, polynomial) = self.kzg_commit_bound_to_root(&chunks, root).await?;
+        if let Err(e) = self.pod.submit_commitment(root, data.len() as u32, TOTAL_SHARDS as u16, REQUIRED_SHARDS as u16, kzg_commitment.into()).await {
+            error!("Failed to submit commitment: {:?}", e);
+            return Err(DispenserError::Pod(e));
         }
         info!("Submitted commitment");
 
         let assignments = self.assign_chunks(&chunks, &storage_providers)?;
+        let (promised_chunks, receipts) = self.distribute_chunks(&chunks, &polynomial, &assignments, &storage_providers, &HashMap::new()).await;
 
-        let mut promised_chunks: usize = 0;
-        for (provider_id, provider_chunks) in &assignments {
-            let chunk_ids = provider_chunks.iter().map(|c| c.index as usize).collect::<Vec<_>>();
+        let result = self.finish_submission(root, assignments, promised_chunks, receipts, started_at).await?;
+        self.record_submission(&result, data.len()).await;
+        Ok(result)
+    }
+
+    /// Estimates a submission of `data_len` bytes. Only the eligible providers are read; nothing is
+    /// encoded, committed or sent to providers. Placement depends on the chunk contents, so the
+    /// assignment is simulated on zero-filled chunks and `per_provider` only shows the expected spread.
+    pub async fn estimate(&self, data_len: usize) -> Result<SubmitEstimate, DispenserError> {
+        if data_len < self.min_data_size {
+            return Err(DispenserError::DataTooSmall { size: data_len, min: self.min_data_size });
+        }
+        let storage_providers = self.pod.get_eligible_providers().await.map_err(DispenserError::Pod)?.to_vec();
+        ensure_eligible_providers(&storage_providers)?;
+
+        let chunk_size = shard_size(data_len.max(self.min_payload_size), REQUIRED_SHARDS);
+        let chunks = (0..TOTAL_SHARDS as u16).map(|index| Chunk { index, data: vec![0; chunk_size] }).collect::<Vec<_>>();
+        let per_provider = self.assign_chunks(&chunks, &storage_providers)?
+            .into_iter()
+            .map(|(provider, chunks)| (provider, chunks.len()))
+            .collect();
+
+        Ok(SubmitEstimate {
+            total_chunks: TOTAL_SHARDS,
+            required_chunks: REQUIRED_SHARDS,
+            per_provider,
+            total_stored_bytes: chunk_size * TOTAL_SHARDS,
+        })
+    }
+
+    /// Resumes a submission whose commitment is already on-chain but whose chunks were not
+    /// fully distributed. Chunks that providers already report holding are not re-sent.
+    pub async fn resubmit_data(&self, commitment: FixedBytes<32>, data: &[u8]) -> Result<SubmitResult, DispenserError> {
+        let (chunks, root) = self.encode_in_background(data).await?;
+        if root != commitment {
+            return Err(DispenserError::DataMismatch(commitment));
+        }
+
+        if !self.pod.commitment_exists(commitment).await.map_err(DispenserError::Pod)? {
+            info!("Commitment {:?} not found on-chain, submitting from scratch", commitment);
+            return self.submit_data(data).await;
+        }
+
+        let started_at = self.clock.now();
+        let storage_providers = self.pod.get_eligible_providers().await.map_err(DispenserError::Pod)?.to_vec();
+        ensure_eligible_providers(&storage_providers)?;
+        let assignments = self.assign_chunks(&chunks, &storage_providers)?;
+
+        let mut stored = HashMap::new();
+        for provider in &storage_providers {
+            let chunk_ids = self.pod.get_provider_chunks(commitment, provider.addr).await.map_err(DispenserError::Pod)?;
+            stored.insert(provider.addr, chunk_ids);
+        }
+
+        let (_, polynomial) = self.kzg_commit_in_background(&chunks).await?;
+        let (promised_chunks, receipts) = self.distribute_chunks(&chunks, &polynomial, &assignments, &storage_providers, &stored).await;
+
+        let result = self.finish_submission(commitment, assignments, promised_chunks, receipts, started_at).await?;
+        self.record_submission(&result, data.len()).await;
+        Ok(result)
+    }
+
+    /// Rebuilds a commitment's chunks from its retrievable data and re-sends every chunk the network
+    /// no longer holds to an eligible provider other than the one that lost it. Returns how many
+    /// chunks were repaired.
+    pub async fn repair(&self, commitment: FixedBytes<32>) -> Result<usize, DispenserError> {
+        let data = self.retrieve_data(commitment, None, None).await?;
+        let (chunks, root) = self.encode_in_background(&data).await?;
+        if root != commitment {
+            return Err(DispenserError::DataMismatch(commitment));
+        }
+
+        let mut holders: HashMap<u16, Vec<Address>> = HashMap::new();
+        for (provider, chunk_ids) in self.placement(commitment).await? {
+            for index in chunk_ids {
+                holders.entry(index).or_default().push(provider.addr);
+            }
+        }
+
+        let mut missing = Vec::new();
+        for chunk in &chunks {
+            // slashed chunks stay in their provider's list, so availability is checked on-chain as well
+            if !holders.contains_key(&chunk.index) || !self.pod.is_chunk_available(commitment, chunk.index).await.map_err(DispenserError::Pod)? {
+                missing.push(chunk.clone());
+            }
+        }
+        if missing.is_empty() {
+            return Ok(0);
+        }
+
+        let storage_providers = self.pod.get_eligible_providers().await.map_err(DispenserError::Pod)?;
+        ensure_eligible_providers(&storage_providers)?;
+
+        let mut assignments = ChunkAssignment::new();
+        for chunk in missing {
+            let lost_by = holders.get(&chunk.index).cloned().unwrap_or_default();
+            let fresh = storage_providers.iter().filter(|p| !lost_by.contains(&p.addr)).cloned().collect::<Vec<_>>();
+            let candidates = if ensure_eligible_providers(&fresh).is_ok() { fresh } else { storage_providers.clone() };
+
+            let total_stake = candidates.iter().map(|p| p.stakedAmount).sum::<U256>();
+            let provider = self.select_provider_for_chunk(&chunk.hash(), chunk.index, &candidates, total_stake)?;
+            assignments.entry(provider.addr).or_default().push(chunk);
+        }
+
+        let (_, polynomial) = self.kzg_commit_in_background(&chunks).await?;
+        let (repaired, _) = self.distribute_chunks(&chunks, &polynomial, &assignments, &storage_providers, &HashMap::new()).await;
+        info!("Repaired {} chunks of commitment {:?}", repaired, commitment);
+
+        if let Some(mut metadata) = self.recorded_metadata(commitment).await {
+            for (provider, moved) in &assignments {
+                for chunk in moved {
+                    metadata.assignment.values_mut().for_each(|ids| ids.retain(|id| *id != chunk.index));
+                    metadata.assignment.entry(*provider).or_default().push(chunk.index);
+                }
+            }
+            self.record_metadata(commitment, metadata).await;
+        }
+
+        Ok(repaired)
+    }
 
-            let kzg_proof = kzg_multi_prove(&chunks, &chunk_ids);
-            let merkle_proofs = provider_chunks.iter().map(|c| merkle_tree::gen_proof(&merkle_tree, c.clone()).unwrap()).collect::<Vec<_>>();
+    // Sends every assigned chunk that is not already in `stored` and returns how many
+    // assigned chunks are now held by their providers, along with the receipts they signed.
+    // `polynomial` is the one `kzg_commit` returned for `chunks`, so proving per provider skips
+    // the interpolation
+    async fn distribute_chunks(&self, chunks: &[Chunk], polynomial: &KZGPolynomial, assignments: &ChunkAssignment, providers: &[ProviderInfo], stored: &HashMap<Address, Vec<u16>>) -> (usize, Vec<StoreReceipt>) {
+        let merkle_tree = gen_merkle_tree(chunks);
 
-            let provider = storage_providers.iter().find(|p| p.name == *provider_id).unwrap();
-            let result = self.batch_submit_to_provider(provider_chunks.clone(), merkle_tree.root(), provider, kzg_proof, merkle_proofs).await;
-            if result.is_err() {
-                warn!("Failed to submit chunks to provider {}: {:?}", provider_id, result.err());
+        let mut promised_chunks: usize = 0;
+        let mut receipts = Vec::new();
+        for (provider_addr, provider_chunks) in assignments {
+            let already_stored = stored.get(provider_addr).cloned().unwrap_or_default();
+            let (held, missing): (Vec<Chunk>, Vec<Chunk>) = provider_chunks.iter().cloned().partition(|c| already_stored.contains(&c.index));
+            promised_chunks += held.len();
+            if missing.is_empty() {
+                continue;
+            }
+
+            let indices = missing.iter().map(|c| c.index).collect::<Vec<_>>();
+            if let Err(e) = check_chunk_indices(&indices, chunks.len()) {
+                warn!("Refusing to prove chunks for provider {}: {}", provider_addr, e);
                 continue;
             }
+            let chunk_ids = indices.iter().map(|&index| index as usize).collect::<Vec<_>>();
+
+            let kzg_proof = kzg_multi_prove_with_polynomial(polynomial, &chunk_ids);
+
+            let provider = providers.iter().find(|p| p.addr == *provider_addr).unwrap();
+            let format = if self.multiproof { self.negotiate_proof_format(provider).await } else { ProofFormat::PerChunk };
+            let (merkle_proofs, merkle_multi_proof) = match format {
+                ProofFormat::MultiProof => (vec![], Some(gen_multi_proof(&merkle_tree, &missing).unwrap())),
+                ProofFormat::PerChunk => (missing.iter().map(|c| merkle_tree::gen_proof(&merkle_tree, c.clone()).unwrap()).collect::<Vec<_>>(), None),
+            };
+            let receipt = match self.batch_submit_to_provider(missing, merkle_tree.root(), provider, kzg_proof, merkle_proofs, merkle_multi_proof).await {
+                Ok(receipt) => receipt,
+                Err(e) => {
+                    warn!("Failed to submit chunks to provider {} ({}): {:?}", provider.name, provider_addr, e);
+                    continue;
+                }
+            };
             promised_chunks += chunk_ids.len();
+
+            match receipt {
+                Some(receipt) if receipt.verify() && receipt.provider_address == *provider_addr && receipt.commitment == merkle_tree.root() && receipt.indices == indices => receipts.push(receipt),
+                Some(receipt) => warn!("Discarding invalid store receipt from provider {}: {:?}", provider.name, receipt),
+                None => debug!("Provider {} returned no store receipt", provider.name),
+            }
+        }
+
+        (promised_chunks, receipts)
+    }
+
+    async fn record_submission(&self, result: &SubmitResult, size: usize) {
+        let metadata = CommitmentMetadata {
+            size,
+            coding: CodingParams::new(REQUIRED_SHARDS, TOTAL_SHARDS),
+            assignment: result.assignments.iter().map(|(provider, chunks)| (*provider, chunks.iter().map(|c| c.index).collect())).collect(),
+            timestamp: self.clock.now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        };
+        self.record_metadata(result.commitment, metadata).await;
+    }
+
+    // The metadata store is only an aid, so failing to write to it does not fail the operation
+    async fn record_metadata(&self, commitment: FixedBytes<32>, metadata: CommitmentMetadata) {
+        let Some(store) = &self.metadata else { return };
+        if let Err(e) = store.put(commitment, metadata).await {
+            warn!("Failed to record metadata of commitment {:?}: {:?}", commitment, e);
         }
+    }
+
+    // `None` when there is no store, it has no entry for the commitment or it cannot be read
+    async fn recorded_metadata(&self, commitment: FixedBytes<32>) -> Option<CommitmentMetadata> {
+        let store = self.metadata.as_ref()?;
+        store.get(commitment).await
+            .inspect_err(|e| warn!("Failed to read metadata of commitment {:?}: {:?}", commitment, e))
+            .ok()
+            .flatten()
+    }
 
+    async fn finish_submission(&self, commitment: FixedBytes<32>, assignments: ChunkAssignment, promised_chunks: usize, receipts: Vec<StoreReceipt>, started_at: SystemTime) -> Result<SubmitResult, DispenserError> {
         if promised_chunks < REQUIRED_SHARDS {
-            return Err(anyhow::anyhow!("Not enough chunks where promised to providers"));
+            return Err(DispenserError::NotEnoughChunks { available: promised_chunks, required: REQUIRED_SHARDS });
         }
 
-        self.pod.wait_for_availability(merkle_tree.root()).await?;
+        self.pod.wait_for_availability(commitment, DEFAULT_AVAILABILITY_TIMEOUT).await.map_err(DispenserError::Pod)?;
+        let elapsed = self.clock.now().duration_since(started_at).unwrap_or_default();
+        info!("Commitment {:?} is available after {:?}", commitment, elapsed);
+
+        Ok(SubmitResult {
+            commitment,
+            assignments,
+            total_chunks: TOTAL_SHARDS,
+            required_chunks: REQUIRED_SHARDS,
+            promised_chunks,
+            receipts,
+        })
+    }
 
-        Ok((merkle_tree.root(), assignments))
+    /// Retrieves and reconstructs the data of a commitment. If `expected_hash` is given, the
+    /// keccak256 of the reconstructed data must match it. Every provider holding chunks is asked
+    /// unless `strategy` says otherwise.
+    pub async fn retrieve_data(&self, commitment: FixedBytes<32>, expected_hash: Option<FixedBytes<32>>, strategy: Option<&RetrievalStrategy>) -> Result<Vec<u8>, DispenserError> {
+        Ok(self.retrieve_data_detailed(commitment, expected_hash, strategy).await?.data)
     }
 
-    pub async fn retrieve_data(&self, commitment: FixedBytes<32>) -> Result<Vec<u8>> {
+    /// Like `retrieve_data`, but also reports the coding parameters and which chunks and
+    /// providers the data was reconstructed from.
+    pub async fn retrieve_data_detailed(&self, commitment: FixedBytes<32>, expected_hash: Option<FixedBytes<32>>, strategy: Option<&RetrievalStrategy>) -> Result<RetrievalReport, DispenserError> {
         info!("Retrieving data for commitment: {:?}", commitment);
-        let (commitment_info, is_recoverable) = self.pod.get_commitment_info(commitment).await?;
-        if !is_recoverable {
-            return Err(anyhow::anyhow!("Commitment is not recoverable"));
+        let RetrievedChunks { commitment_info, chunks, providers_responded, providers_failed } = self.retrieve_chunks(commitment, strategy.cloned().unwrap_or_default()).await?;
+        let shards_missing = chunks.iter().enumerate().filter(|(_, c)| c.is_none()).map(|(i, _)| i as u16).collect::<Vec<_>>();
+        let shards_used = chunks.len() - shards_missing.len();
+        let (data, reencoded) = self.decode_in_background(chunks, commitment_info.size as usize).await?;
+
+        // chunks are not checked one by one, so consistent but wrong ones only show up here
+        if gen_merkle_tree(&reencoded).root() != commitment {
+            error!(%commitment, "Reconstructed data does not match its commitment");
+            return Err(DispenserError::ReconstructionMismatch(commitment));
+        }
+
+        if let Some(expected) = expected_hash {
+            let actual = keccak256(&data);
+            if actual != expected {
+                return Err(DispenserError::HashMismatch { expected, actual });
+            }
+        }
+
+        Ok(RetrievalReport {
+            data,
+            bytes: commitment_info.size as usize,
+            total_shards: TOTAL_SHARDS,
+            required_shards: REQUIRED_SHARDS,
+            shards_used,
+            shards_missing,
+            providers_responded,
+            providers_failed,
+        })
+    }
+
+    /// Checks that `data` is what `commitment` was made from: the re-encoded chunks must have
+    /// `commitment` as their merkle root and the on-chain size and KZG commitment must match.
+    pub async fn verify_commitment(&self, commitment: FixedBytes<32>, data: &[u8]) -> Result<bool, DispenserError> {
+        let (commitment_info, _) = self.pod.get_commitment_info(commitment).await.map_err(DispenserError::Pod)?;
+        if commitment_info.size as usize != data.len() {
+            return Ok(false);
+        }
+
+        let (chunks, root) = self.encode_in_background(data).await?;
+        if root != commitment {
+            return Ok(false);
+        }
+
+        let (kzg_commitment, _) = self.kzg_commit_in_background(&chunks).await?;
+        let kzg_commitment: Bytes = kzg_commitment.into();
+        Ok(kzg_commitment == commitment_info.kzgCommitment)
+    }
+
+    /// Rebuilds the full chunk set of a commitment and packages a sample of it with proofs
+    /// so that it can be checked offline with `verify_bundle`.
+    pub async fn export_verification_bundle(&self, commitment: FixedBytes<32>) -> Result<VerificationBundle, DispenserError> {
+        let RetrievedChunks { commitment_info, chunks, .. } = self.retrieve_chunks(commitment, RetrievalStrategy::default()).await?;
+        let (_, chunks) = self.decode_in_background(chunks, commitment_info.size as usize).await?;
+
+        let kzg_commitment = KzgCommitment::try_from(commitment_info.kzgCommitment)?;
+        let bundle = build_bundle(&chunks, kzg_commitment, commitment_info.size, commitment_info.requiredChunks, BUNDLE_SAMPLE_SIZE)?;
+        if bundle.commitment != commitment {
+            return Err(DispenserError::DataMismatch(commitment));
+        }
+
+        Ok(bundle)
+    }
+
+    /// Rebuilds the chunk at `index` of a commitment together with the merkle and KZG proofs a
+    /// provider needs to store it again.
+    pub async fn reconstruct_chunk(&self, commitment: FixedBytes<32>, index: u16) -> Result<(Chunk, MerkleProof, KzgProof), DispenserError> {
+        if index as usize >= TOTAL_SHARDS {
+            return Err(DispenserError::InvalidChunkIndex { index, total: TOTAL_SHARDS });
+        }
+
+        let (_, chunks) = self.rebuild_chunks(commitment, None).await?;
+
+        let merkle_tree = gen_merkle_tree(&chunks);
+        if merkle_tree.root() != commitment {
+            return Err(DispenserError::DataMismatch(commitment));
+        }
+
+        let chunk = chunks[index as usize].clone();
+        let merkle_proof = merkle_tree::gen_proof(&merkle_tree, chunk.clone())?;
+        let (_, polynomial) = self.kzg_commit_in_background(&chunks).await?;
+        let kzg_proof = kzg_prove_with_polynomial(&polynomial, index as usize);
+
+        Ok((chunk, merkle_proof, kzg_proof))
+    }
+
+    /// Fetches chunk `chunk_id` and its merkle proof from the provider it is assigned to and
+    /// checks both the merkle proof and a KZG opening against the on-chain commitment. The KZG
+    /// proof is computed from the other chunks, so the audited copy never vouches for itself.
+    pub async fn audit_chunk(&self, commitment: FixedBytes<32>, chunk_id: u16) -> Result<AuditResult, DispenserError> {
+        if chunk_id as usize >= TOTAL_SHARDS {
+            return Err(DispenserError::InvalidChunkIndex { index: chunk_id, total: TOTAL_SHARDS });
+        }
+
+        // chunks move after slashes, failed stores and re-attestations, so blame goes by chain state
+        let owner = self.pod.get_chunk_owner(commitment, chunk_id).await.map_err(DispenserError::Pod)?;
+        if owner == Address::ZERO {
+            return Err(DispenserError::ChunkNotAssigned { commitment, index: chunk_id });
+        }
+        let provider = self.pod.get_provider_info(owner).await.map_err(DispenserError::Pod)?;
+
+        let request = BatchRetrieveRequest { commitment, indices: vec![chunk_id] };
+        let response = self.transport.batch_retrieve(&provider, &request).await.map_err(|e| DispenserError::Internal(e.into()))?;
+        let chunk = response.chunks.into_iter().next().flatten();
+        let proof = response.proofs.into_iter().next().flatten();
+
+        let mut result = AuditResult { commitment, chunk_id, provider: owner, retrieved: chunk.is_some(), merkle_valid: false, kzg_valid: false };
+        let Some(chunk) = chunk.filter(|c| c.index == chunk_id) else {
+            warn!(%commitment, chunk_id, provider = %owner, "Audited chunk was not returned by its provider");
+            return Ok(result);
+        };
+
+        result.merkle_valid = proof.is_some_and(|proof| merkle_tree::verify_proof(commitment, &chunk, proof));
+
+        let (commitment_info, chunks) = self.rebuild_chunks(commitment, Some(chunk_id)).await?;
+        if gen_merkle_tree(&chunks).root() != commitment {
+            return Err(DispenserError::ReconstructionMismatch(commitment));
+        }
+        let (_, polynomial) = self.kzg_commit_in_background(&chunks).await?;
+        let kzg_proof = kzg_prove_with_polynomial(&polynomial, chunk_id as usize);
+        let kzg_commitment = KzgCommitment::try_from(commitment_info.kzgCommitment)?;
+        result.kzg_valid = kzg_verify(&chunk, chunk_id as usize, kzg_commitment, kzg_proof);
+
+        info!(%commitment, chunk_id, provider = %owner, merkle_valid = result.merkle_valid, kzg_valid = result.kzg_valid, "Audited chunk");
+        Ok(result)
+    }
+
+    // Every chunk of a commitment, decoded from what the providers return and re-encoded. The
+    // chunk at `skip` is left out of the decode even if a provider returned it.
+    async fn rebuild_chunks(&self, commitment: FixedBytes<32>, skip: Option<u16>) -> Result<(Commitment, Vec<Chunk>), DispenserError> {
+        let RetrievedChunks { commitment_info, mut chunks, .. } = self.retrieve_chunks(commitment, RetrievalStrategy::default()).await?;
+        if let Some(skip) = skip {
+            chunks[skip as usize] = None;
         }
 
-        let storage_providers = self.pod.get_providers().await?.to_vec();
+        let (_, chunks) = self.decode_in_background(chunks, commitment_info.size as usize).await?;
+        Ok((commitment_info, chunks))
+    }
 
-        const NO_CHUNK: Option<Chunk> = None;
-        let mut chunks = [NO_CHUNK; TOTAL_SHARDS];
-        for provider in storage_providers {
-            let chunk_ids = self.pod.get_provider_chunks(commitment, provider.addr).await?;
+    /// Every provider holding chunks of a commitment, with the chunk ids it holds on-chain.
+    /// Providers that have since become ineligible may still hold chunks, so all of them are asked.
+    pub async fn placement(&self, commitment: FixedBytes<32>) -> Result<Vec<(ProviderInfo, Vec<u16>)>, DispenserError> {
+        let mut placement = Vec::new();
+        for provider in self.pod.get_providers().await.map_err(DispenserError::Pod)? {
+            let chunk_ids = self.pod.get_provider_chunks(commitment, provider.addr).await.map_err(DispenserError::Pod)?;
             debug!("Chunk ids for provider {}: {:?}", provider.name, chunk_ids);
-            let provider_chunks = self.batch_retrieve_from_provider(commitment, &chunk_ids, &provider).await;
-            if provider_chunks.is_err() {
-                warn!("Failed to retrieve chunks from provider {}: {:?}", provider.name, provider_chunks.err());
-                for chunk_id in chunk_ids {
-                    chunks[chunk_id as usize] = NO_CHUNK.clone();
-                }
-                continue;
+            if !chunk_ids.is_empty() {
+                placement.push((provider, chunk_ids));
             }
+        }
+
+        Ok(placement)
+    }
+
+    /// The chunk ids this dispenser assigned to each provider when it distributed or last repaired
+    /// the commitment, or `None` when it kept no metadata for it. Unlike `placement` this still
+    /// names the provider of a chunk whose attestation was cleared.
+    pub async fn recorded_assignment(&self, commitment: FixedBytes<32>) -> Option<HashMap<Address, Vec<u16>>> {
+        self.recorded_metadata(commitment).await.map(|metadata| metadata.assignment)
+    }
+
+    // The chunk ids this dispenser assigned to each registered provider, if it recorded the
+    // commitment's metadata. Chunks may have moved since, so this is only a hint.
+    async fn recorded_placement(&self, commitment: FixedBytes<32>) -> Result<Option<Vec<(ProviderInfo, Vec<u16>)>>, DispenserError> {
+        let Some(metadata) = self.recorded_metadata(commitment).await else {
+            return Ok(None);
+        };
+
+        let providers = self.pod.get_providers().await.map_err(DispenserError::Pod)?;
+        Ok(Some(providers.into_iter()
+            .filter_map(|provider| {
+                let chunk_ids = metadata.assignment.get(&provider.addr).filter(|ids| !ids.is_empty())?.clone();
+                Some((provider, chunk_ids))
+            })
+            .collect()))
+    }
+
+    async fn retrieve_chunks(&self, commitment: FixedBytes<32>, strategy: RetrievalStrategy) -> Result<RetrievedChunks, DispenserError> {
+        let (commitment_info, is_recoverable) = self.pod.get_commitment_info(commitment).await.map_err(DispenserError::Pod)?;
+        if !is_recoverable {
+            return Err(DispenserError::NotRecoverable(commitment));
+        }
+
+        // preferred providers first, in the order given, then everyone else as registered
+        let by_preference = |placement: &mut Vec<(ProviderInfo, Vec<u16>)>| {
+            placement.sort_by_key(|(provider, _)| strategy.preferred.iter().position(|addr| *addr == provider.addr).unwrap_or(usize::MAX));
+        };
+        let mut retrieved = RetrievedChunks { commitment_info, chunks: vec![None; TOTAL_SHARDS], providers_responded: Vec::new(), providers_failed: Vec::new() };
 
-            let provider_chunks = provider_chunks.unwrap();
-            for (index, chunk) in zip(chunk_ids, provider_chunks) {
-                chunks[index as usize] = chunk;
+        let mut asked = HashSet::new();
+        if let Some(mut hint) = self.recorded_placement(commitment).await? {
+            by_preference(&mut hint);
+            asked.extend(hint.iter().flat_map(|(provider, ids)| ids.iter().map(|id| (provider.addr, *id))));
+            self.fetch_chunks(commitment, hint, strategy.stop_early, &mut retrieved).await;
+        }
+
+        // chunks the recorded assignment did not yield are looked up on-chain
+        if !retrieved.is_done(strategy.stop_early) {
+            let mut placement = self.placement(commitment).await?;
+            for (provider, ids) in &mut placement {
+                ids.retain(|id| !asked.contains(&(provider.addr, *id)) && retrieved.chunks.get(*id as usize).is_some_and(Option::is_none));
             }
+            placement.retain(|(_, ids)| !ids.is_empty());
+            by_preference(&mut placement);
+            self.fetch_chunks(commitment, placement, strategy.stop_early, &mut retrieved).await;
         }
 
-        let retrieved_chunks = chunks.iter().filter(|c| c.is_some()).count();
+        let retrieved_chunks = retrieved.chunks.iter().filter(|c| c.is_some()).count();
         info!("Retrieved {} chunks out of {} for commitment: {:?}", retrieved_chunks, TOTAL_SHARDS, commitment);
 
         if retrieved_chunks < REQUIRED_SHARDS {
             error!("Not enough chunks retrieved to reconstruct data");
-            return Err(anyhow::anyhow!("Not enough chunks retrieved to reconstruct data"));
+            return Err(DispenserError::NotEnoughChunks { available: retrieved_chunks, required: REQUIRED_SHARDS });
         }
 
         // reality check
-        for (index, chunk) in chunks.iter().enumerate() {
+        for (index, chunk) in retrieved.chunks.iter().enumerate() {
             if chunk.is_none() {
                 warn!("Chunk at index {} is none", index);
             }
@@ -112,101 +770,117 @@ impl<T: PodaClientTrait> Dispenser<T> {
             }
         }
 
-        let (data, _) = self.erasure_decode(chunks.to_vec(), REQUIRED_SHARDS, TOTAL_SHARDS, commitment_info.size as usize)?;
-
-        Ok(data)
+        Ok(retrieved)
     }
 
-    pub fn erasure_encode(&self, data: &[u8], required_shards: usize, total_shards: usize) -> Vec<Chunk> {
-        let parity_shards = total_shards - required_shards;
-        let r = ReedSolomon::<reed_solomon_erasure::galois_8::Field>::new(required_shards, parity_shards).unwrap();
-        let mut master_copy = self.create_shards(data, required_shards, total_shards);
-
-        r.encode(&mut master_copy).unwrap();
+    // Asks each provider in `placement`, in order, for its chunks and keeps the verified ones
+    async fn fetch_chunks(&self, commitment: FixedBytes<32>, placement: Vec<(ProviderInfo, Vec<u16>)>, stop_early: bool, retrieved: &mut RetrievedChunks) {
+        for (provider, chunk_ids) in placement {
+            if stop_early && retrieved.is_done(true) {
+                debug!("Enough chunks retrieved, not asking provider {}", provider.name);
+                break;
+            }
 
-        let chunks = master_copy.iter().enumerate().map(|(index, shard)| Chunk {
-            index: index as u16,
-            data: shard.to_vec(),
-        }).collect::<Vec<_>>();
+            let (provider_chunks, rejected) = match self.batch_retrieve_from_provider(commitment, &chunk_ids, &provider).await {
+                Ok(served) => served,
+                Err(reason) => {
+                    warn!(provider = %provider.name, %reason, "Failed to retrieve chunks from provider");
+                    retrieved.providers_failed.push((provider.name.clone(), reason));
+                    continue;
+                }
+            };
 
-        if chunks.len() != total_shards {
-            panic!("Invalid number of chunks: {}", chunks.len());
+            // a provider that only served invalid chunks did not contribute anything
+            if (!provider_chunks.is_empty() || rejected.is_empty()) && !retrieved.providers_responded.contains(&provider.name) {
+                retrieved.providers_responded.push(provider.name.clone());
+            }
+            if !rejected.is_empty() {
+                warn!(provider = %provider.name, ?rejected, "Provider served chunks that failed verification");
+                retrieved.providers_failed.push((provider.name.clone(), RetrieveError::InvalidChunks(rejected)));
+            }
+            for chunk in provider_chunks {
+                let index = chunk.index as usize;
+                retrieved.chunks[index] = Some(chunk);
+            }
         }
+    }
 
-        chunks
+    /// Pads the payload up to the configured floor and erasure encodes it.
+    /// The original length is recorded on-chain and used to trim on retrieval.
+    pub fn encode_payload(&self, data: &[u8]) -> Vec<Chunk> {
+        encode_padded(&self.codecs, data, self.min_payload_size)
     }
 
-    pub fn erasure_decode(&self, chunks: Vec<Option<Chunk>>, required_shards: usize, total_shards: usize, original_length: usize) -> Result<(Vec<u8>, Vec<Chunk>)> {
-        let parity_shards = total_shards - required_shards;
-        let r = ReedSolomon::<reed_solomon_erasure::galois_8::Field>::new(required_shards, parity_shards).unwrap();
+    pub fn erasure_encode(&self, data: &[u8], required_shards: usize, total_shards: usize) -> Vec<Chunk> {
+        self.erasure_encode_with(data, &CodingParams::new(required_shards, total_shards))
+    }
 
-        // Convert chunks to shards for reconstruction
-        let mut shards: Vec<Option<Vec<u8>>> = chunks.iter()
-            .map(|chunk| chunk.as_ref().map(|c| c.data.clone()))
-            .collect();
+    pub fn erasure_encode_with(&self, data: &[u8], params: &CodingParams) -> Vec<Chunk> {
+        self.codecs.encode_chunks(data, params)
+    }
 
-        debug!("Before reconstruction - shards: {:?}", shards);
-        r.reconstruct(&mut shards).unwrap();
-        debug!("After reconstruction - shards: {:?}", shards);
+    pub fn erasure_decode(&self, chunks: Vec<Option<Chunk>>, required_shards: usize, total_shards: usize, original_length: usize) -> Result<(Vec<u8>, Vec<Chunk>)> {
+        self.erasure_decode_with(chunks, &CodingParams::new(required_shards, total_shards), original_length)
+    }
 
-        // Get the reconstructed data chunks (first required_shards are the data shards)
-        let mut reconstructed_chunks: Vec<Chunk> = Vec::new();
-        let mut decoded = Vec::new();
-        
-        for (i, shard) in shards.iter().enumerate().take(required_shards) {
-            if let Some(data) = shard {
-                let chunk = Chunk {
-                    index: i as u16,
-                    data: data.to_owned(),
-                };
+    pub fn erasure_decode_with(&self, chunks: Vec<Option<Chunk>>, params: &CodingParams, original_length: usize) -> Result<(Vec<u8>, Vec<Chunk>)> {
+        self.codecs.decode_chunks(chunks, params, original_length)
+    }
 
-                reconstructed_chunks.push(chunk);
-                decoded.extend_from_slice(data);
-            } else {
-                return Err(anyhow::anyhow!("Missing data chunk after reconstruction"));
-            }
+    /// Decodes an unordered set of chunks, which may come from any mix of data and parity
+    /// indices, as long as at least `required_shards` distinct indices are present.
+    pub fn reconstruct_from(&self, chunks: &[Chunk], total_shards: usize, required_shards: usize, original_length: usize) -> Result<Vec<u8>> {
+        let mut positioned: Vec<Option<Chunk>> = vec![None; total_shards];
+        for chunk in chunks {
+            let slot = positioned.get_mut(chunk.index as usize)
+                .ok_or_else(|| anyhow::anyhow!("Chunk index {} out of range (total chunks: {})", chunk.index, total_shards))?;
+            *slot = Some(chunk.clone());
         }
-        
-        // Trim to original length
-        decoded.truncate(original_length);
-        
-        Ok((decoded, reconstructed_chunks))
+
+        let present = positioned.iter().filter(|c| c.is_some()).count();
+        if present < required_shards {
+            anyhow::bail!("Not enough chunks to reconstruct: {} of the {} required", present, required_shards);
+        }
+
+        let (decoded, _) = self.erasure_decode(positioned, required_shards, total_shards, original_length)?;
+        Ok(decoded)
     }
 
-    async fn batch_retrieve_from_provider(&self, commitment: FixedBytes<32>, chunk_ids: &[u16], storage_provider: &ProviderInfo) -> Result<Vec<Option<Chunk>>> {
-        let url = format!("{}/batch-retrieve", storage_provider.url);
+    // The chunks a provider served for `chunk_ids` that are the requested chunk and prove into the
+    // commitment, along with the indices whose chunk was rejected
+    async fn batch_retrieve_from_provider(&self, commitment: FixedBytes<32>, chunk_ids: &[u16], storage_provider: &ProviderInfo) -> Result<(Vec<Chunk>, Vec<u16>), RetrieveError> {
         let body = BatchRetrieveRequest {
             commitment,
             indices: chunk_ids.to_owned()
         };
 
-        let response = reqwest::Client::new().post(url).json(&body).send().await?;
-        if !response.status().is_success() {
-            return Err(anyhow::anyhow!("Failed to retrieve chunks: {:?}", response.text().await.unwrap()));
-        }
+        let response = self.transport.batch_retrieve(storage_provider, &body).await?;
+        Ok(verify_served_chunks(commitment, chunk_ids, response))
+    }
 
-        let message: BatchRetrieveResponse = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    // Providers that predate /version only advertise their proof formats through /capabilities
+    async fn negotiate_proof_format(&self, storage_provider: &ProviderInfo) -> ProofFormat {
+        if let Ok(version) = self.transport.version(storage_provider).await {
+            debug!("Provider {} runs {} (protocol {}) with {:?}", storage_provider.name, version.crate_version, version.protocol_version, version.features);
+            return if version.features.contains(&ProtocolFeature::Multiproof) { ProofFormat::MultiProof } else { ProofFormat::PerChunk };
+        }
 
-        Ok(message.chunks)
+        match self.transport.capabilities(storage_provider).await {
+            Ok(capabilities) if capabilities.proof_formats.contains(&ProofFormat::MultiProof) => ProofFormat::MultiProof,
+            _ => ProofFormat::PerChunk,
+        }
     }
 
-    pub async fn batch_submit_to_provider(&self, chunks: Vec<Chunk>, commitment: FixedBytes<32>, storage_provider: &ProviderInfo, proof: KzgProof, merkle_proofs: Vec<MerkleProof>) -> Result<()> {
-        let url = format!("{}/batch-store", storage_provider.url);
+    pub async fn batch_submit_to_provider(&self, chunks: Vec<Chunk>, commitment: FixedBytes<32>, storage_provider: &ProviderInfo, proof: KzgProof, merkle_proofs: Vec<MerkleProof>, merkle_multi_proof: Option<MerkleMultiProof>) -> Result<Option<StoreReceipt>> {
         let body = BatchStoreRequest {
             commitment,
             chunks,
             kzg_proof: proof,
             merkle_proofs,
+            merkle_multi_proof,
         };
 
-        let response = reqwest::Client::new().post(url).json(&body).send().await?;
-
-        if !response.status().is_success() {
-            let error: serde_json::Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
-            return Err(anyhow::anyhow!("Failed to submit chunks: {:?}", error["message"]));
-        }
-
-        Ok(())
+        self.transport.batch_store(storage_provider, &body).await
     }
 
     fn assign_chunks(&self, chunks: &Vec<Chunk>, providers: &Vec<ProviderInfo>) -> Result<ChunkAssignment> {
@@ -214,9 +888,9 @@ impl<T: PodaClientTrait> Dispenser<T> {
         let total_stake = providers.iter().map(|p| p.stakedAmount).sum::<U256>();
         
         // Create assignment map
-        let mut assignments: HashMap<String, Vec<Chunk>> = HashMap::with_capacity(providers.len());
+        let mut assignments: ChunkAssignment = HashMap::with_capacity(providers.len());
         for provider in providers {
-            assignments.insert(provider.name.clone(), Vec::new());
+            assignments.insert(provider.addr, Vec::new());
         }
         
         // Assign each chunk individually using deterministic round-robin
@@ -228,25 +902,19 @@ impl<T: PodaClientTrait> Dispenser<T> {
                 total_stake
             ).unwrap();
 
-            if let Some(provider) = assignments.get_mut(&provider.name) {
-                provider.push(chunk.clone());
-            } else {
-                assignments.insert(provider.name.clone(), vec![chunk.clone()]);
-            }
+            assignments.entry(provider.addr).or_default().push(chunk.clone());
         }
         
         Ok(assignments)
     }
     
-    fn select_provider_for_chunk(&self, commitment: &FixedBytes<32>, chunk_index: u16, providers: &Vec<ProviderInfo>, total_stake: U256) -> Result<ProviderInfo> {
-        // Create deterministic seed for this specific chunk
-        let mut seed_input = commitment.to_vec();
-        seed_input.extend_from_slice(chunk_index.to_string().as_bytes());
-        let seed = Keccak256::digest(&seed_input);
-        let random_value = u64::from_le_bytes(seed[0..8].try_into().unwrap()); // Use first 8 bytes
-        
-        // Weighted selection based on stake
-        let target = U256::from(random_value) % total_stake;
+    fn select_provider_for_chunk(&self, commitment: &FixedBytes<32>, chunk_index: u16, providers: &[ProviderInfo], total_stake: U256) -> Result<ProviderInfo> {
+        self.select_provider_with_seed(selection_seed(commitment, chunk_index), providers, total_stake)
+    }
+
+    // Stake-weighted pick driven only by `seed`, so tests can feed it exact values
+    fn select_provider_with_seed(&self, seed: u64, providers: &[ProviderInfo], total_stake: U256) -> Result<ProviderInfo> {
+        let target = U256::from(seed) % total_stake;
         let mut cumulative_stake = U256::ZERO;
         
         for provider in providers {
@@ -261,54 +929,128 @@ impl<T: PodaClientTrait> Dispenser<T> {
     }
 
     pub fn create_shards(&self, data: &[u8], required_shards: usize, total_shards: usize) -> Vec<Vec<u8>> {
-        let parity_shards = total_shards - required_shards;
-
-        let split_data = self.split_to_chunks(data, required_shards);
-        let split_data_len = split_data[0].len();
+        create_shards(data, required_shards, total_shards)
+    }
+}
 
-        // add parity shareds of the same size as the data shards
-        let mut shards = Vec::with_capacity(total_shards);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use merkle_tree::gen_proof;
+    use pod::client::MockPodaClientTrait;
+    use common::constants::REQUIRED_SHARDS;
+    use std::sync::{atomic::{AtomicBool, Ordering}, Mutex};
+    use warp::Filter;
+    use storage_provider::http::{CapabilitiesResponse, VersionResponse, PROTOCOL_VERSION};
+    use crate::coding::ErasureField;
+    use crate::transport::MockProviderTransport;
+    use common::clock::MockClock;
+    use crate::metadata::InMemoryMetadataStore;
+    use pod::PrivateKeySigner;
 
-        // add the data shards
-        shards.extend(split_data);
+    async fn spawn_stub_provider() -> String {
+        let batch_store = warp::path("batch-store")
+            .and(warp::post())
+            .map(|| warp::reply::json(&serde_json::json!({ "success": true })));
+        let (addr, server) = warp::serve(batch_store).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        format!("http://{}", addr)
+    }
 
-        // add the parity shards
-        shards.extend(vec![vec![0; split_data_len]; parity_shards]);
+    // Stub provider that records the chunk indices it accepts and rejects stores while `up` is false
+    async fn spawn_recording_provider(up: Arc<AtomicBool>, received: Arc<Mutex<Vec<u16>>>) -> String {
+        let batch_store = warp::path("batch-store")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(move |req: BatchStoreRequest| {
+                if !up.load(Ordering::SeqCst) {
+                    let body = warp::reply::json(&serde_json::json!({ "success": false, "message": "provider down" }));
+                    return warp::reply::with_status(body, warp::http::StatusCode::SERVICE_UNAVAILABLE);
+                }
+                received.lock().unwrap().extend(req.chunks.iter().map(|c| c.index));
+                warp::reply::with_status(warp::reply::json(&serde_json::json!({ "success": true })), warp::http::StatusCode::OK)
+            });
+        let (addr, server) = warp::serve(batch_store).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        format!("http://{}", addr)
+    }
 
-        shards
+    // Stub provider advertising `formats` that verifies each batch-store against `root` and records
+    // the proof format it was sent
+    async fn spawn_proof_checking_provider(formats: Vec<ProofFormat>, root: FixedBytes<32>, received: Arc<Mutex<Vec<ProofFormat>>>) -> String {
+        let capabilities = warp::path("capabilities")
+            .and(warp::get())
+            .map(move || warp::reply::json(&CapabilitiesResponse { proof_formats: formats.clone() }));
+        let batch_store = warp::path("batch-store")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(move |req: BatchStoreRequest| {
+                let (format, valid) = match req.merkle_multi_proof {
+                    Some(proof) => (ProofFormat::MultiProof, merkle_tree::verify_multi_proof(root, &req.chunks, proof)),
+                    None => (ProofFormat::PerChunk, req.chunks.len() == req.merkle_proofs.len()
+                        && zip(&req.chunks, req.merkle_proofs).all(|(chunk, proof)| merkle_tree::verify_proof(root, chunk, proof))),
+                };
+                if !valid {
+                    let body = warp::reply::json(&serde_json::json!({ "success": false, "message": "invalid merkle proof" }));
+                    return warp::reply::with_status(body, warp::http::StatusCode::BAD_REQUEST);
+                }
+                received.lock().unwrap().push(format);
+                warp::reply::with_status(warp::reply::json(&serde_json::json!({ "success": true })), warp::http::StatusCode::OK)
+            });
+        let (addr, server) = warp::serve(capabilities.or(batch_store)).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        format!("http://{}", addr)
     }
 
-    fn split_to_chunks(&self, data: &[u8], data_shards: usize) -> Vec<Vec<u8>> {
-        // Calculate chunk size, ensuring it's even
-        let mut chunk_size = data.len().div_ceil(data_shards);
-        if chunk_size % 2 != 0 {
-            chunk_size += 1;
-        }
-        
-        let mut chunks = Vec::with_capacity(data_shards);
-        
-        for i in 0..data_shards {
-            let start = i * chunk_size;
-            let end = std::cmp::min(start + chunk_size, data.len());
-            
-            let mut chunk = vec![0u8; chunk_size];
-            if start < data.len() {
-                chunk[..end - start].copy_from_slice(&data[start..end]);
-            }
-            chunks.push(chunk);
+    // What a provider holding every one of `chunks` answers to /batch-retrieve for `indices`
+    fn serve_chunks(chunks: &[Chunk], indices: &[u16]) -> BatchRetrieveResponse {
+        let merkle_tree = gen_merkle_tree(chunks);
+        BatchRetrieveResponse {
+            chunks: indices.iter().map(|i| chunks.get(*i as usize).cloned()).collect(),
+            proofs: indices.iter().map(|i| chunks.get(*i as usize).and_then(|c| gen_proof(&merkle_tree, c.clone()).ok())).collect(),
         }
+    }
 
-        chunks
+    // Stub provider serving the given chunks, with their merkle proofs, from /batch-retrieve
+    async fn spawn_retrieval_provider(chunks: Vec<Chunk>) -> String {
+        let batch_retrieve = warp::path("batch-retrieve")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(move |req: BatchRetrieveRequest| warp::reply::json(&serve_chunks(&chunks, &req.indices)));
+        let (addr, server) = warp::serve(batch_retrieve).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        format!("http://{}", addr)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use pod::{client::MockPodaClientTrait, Address, FixedBytes};
-    use common::constants::REQUIRED_SHARDS;
+    // Dispenser whose pod reports `data` as a recoverable commitment held by a single stub provider
+    async fn create_retrieval_dispenser(data: &[u8]) -> (Dispenser<MockPodaClientTrait>, FixedBytes<32>) {
+        let chunks = create_test_dispenser().await.encode_payload(data);
+        let commitment = gen_merkle_tree(&chunks).root();
+        let mut provider = create_test_providers().remove(0);
+        provider.url = spawn_retrieval_provider(chunks).await;
 
-    async fn create_test_dispenser() -> Dispenser<MockPodaClientTrait> {
+        let size = data.len();
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(move |_| Ok((recoverable_commitment(size, Bytes::new()), true)));
+        pod.expect_get_providers().returning(move || Ok(vec![provider.clone()]));
+        pod.expect_get_provider_chunks().returning(|_, _| Ok((0..TOTAL_SHARDS as u16).collect()));
+
+        (Dispenser::new(pod), commitment)
+    }
+
+    // On-chain record of a recoverable `size` byte commitment with every chunk available
+    fn recoverable_commitment(size: usize, kzg_commitment: Bytes) -> Commitment {
+        Commitment {
+            size: size as u32,
+            timestamp: 0,
+            totalChunks: TOTAL_SHARDS as u16,
+            requiredChunks: REQUIRED_SHARDS as u16,
+            availableChunks: TOTAL_SHARDS as u16,
+            kzgCommitment: kzg_commitment,
+        }
+    }
+
+    async fn create_test_dispenser() -> Dispenser<MockPodaClientTrait> {
         let pod = MockPodaClientTrait::new();
         Dispenser::new(pod)
     }
@@ -318,7 +1060,7 @@ mod tests {
             ProviderInfo {
                 name: "Test Provider 1".to_string(),
                 url: "https://test-provider-1.com".to_string(),
-                addr: Address::default(),
+                addr: Address::repeat_byte(1),
                 registeredAt: 0,
                 challengeCount: 0,
                 challengeSuccessCount: 0,
@@ -327,7 +1069,7 @@ mod tests {
             },
             ProviderInfo {
                 name: "Test Provider 2".to_string(),
-                addr: Address::default(),
+                addr: Address::repeat_byte(2),
                 url: "https://test-provider-2.com".to_string(),
                 registeredAt: 0,
                 challengeCount: 0,
@@ -337,7 +1079,7 @@ mod tests {
             },
             ProviderInfo {
                 name: "Test Provider 3".to_string(),
-                addr: Address::default(),
+                addr: Address::repeat_byte(3),
                 url: "https://test-provider-3.com".to_string(),
                 registeredAt: 0,
                 challengeCount: 0,
@@ -348,6 +1090,14 @@ mod tests {
         ]
     }
 
+    // `create_test_providers`, all served by the stub at `url`
+    fn create_test_providers_at(url: &str) -> Vec<ProviderInfo> {
+        create_test_providers().into_iter().map(|mut p| {
+            p.url = url.to_string();
+            p
+        }).collect()
+    }
+
     #[tokio::test]
     async fn test_erasure_coding_roundtrip() {
         let dispenser = create_test_dispenser().await;
@@ -357,6 +1107,7 @@ mod tests {
         // Test encoding
         let chunks = dispenser.erasure_encode(original_data, REQUIRED_SHARDS, TOTAL_SHARDS);
         assert_eq!(chunks.len(), TOTAL_SHARDS);
+        let original_chunks = chunks.clone();
 
         // Test decoding with all chunks
         let shards: Vec<Option<Chunk>> = chunks.into_iter()
@@ -370,7 +1121,7 @@ mod tests {
         // Verify each reconstructed chunk has the correct index and hash
         for (i, chunk) in reconstructed_chunks.iter().enumerate() {
             assert_eq!(chunk.index, i as u16);
-            assert_eq!(chunk.hash(), FixedBytes::from_slice(&Keccak256::digest(&chunk.data)));
+            assert_eq!(chunk.hash(), original_chunks[i].hash());
         }
 
         // Test encoding again for the missing chunks test
@@ -394,8 +1145,785 @@ mod tests {
         // Verify reconstructed chunks after missing data
         for (i, chunk) in reconstructed_chunks.iter().enumerate() {
             assert_eq!(chunk.index, i as u16);
-            assert_eq!(chunk.hash(), FixedBytes::from_slice(&Keccak256::digest(&chunk.data)));
+            assert_eq!(chunk.hash(), original_chunks[i].hash());
+        }
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::test_runner::Config::with_cases(64))]
+
+        // any data length and any set of at most `parity` missing chunks, data or parity, decodes
+        // back to exactly the original bytes
+        #[test]
+        fn prop_erasure_roundtrip_at_any_length(
+            data in proptest::collection::vec(proptest::num::u8::ANY, DEFAULT_MIN_DATA_SIZE..=100_000),
+            missing in proptest::sample::subsequence((0..TOTAL_SHARDS).collect::<Vec<_>>(), 0..=TOTAL_SHARDS - REQUIRED_SHARDS),
+        ) {
+            let dispenser = Dispenser::new(MockPodaClientTrait::new());
+            let chunks = dispenser.erasure_encode(&data, REQUIRED_SHARDS, TOTAL_SHARDS);
+            let mut partial = chunks.iter().cloned().map(Some).collect::<Vec<_>>();
+            for index in &missing {
+                partial[*index] = None;
+            }
+
+            let (decoded, data_chunks) = dispenser.erasure_decode(partial, REQUIRED_SHARDS, TOTAL_SHARDS, data.len()).unwrap();
+            proptest::prop_assert_eq!(&decoded, &data);
+            for (decoded, original) in data_chunks.iter().zip(&chunks) {
+                proptest::prop_assert_eq!(&decoded.data, &original.data);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submit_data_result() {
+        let url = spawn_stub_provider().await;
+        let providers = create_test_providers_at(&url);
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_eligible_providers().returning(move || Ok(providers.clone()));
+        pod.expect_submit_commitment().returning(|_, _, _, _, _| Ok(()));
+        pod.expect_commitment_exists().returning(|_| Ok(false));
+        pod.expect_wait_for_availability().returning(|_, _| Ok(()));
+        let dispenser = Dispenser::new(pod);
+
+        let data = "Data for a full submission".repeat(100);
+        let result = dispenser.submit_data(data.as_bytes()).await.unwrap();
+
+        let chunks = dispenser.encode_payload(data.as_bytes());
+        assert_eq!(result.commitment, gen_merkle_tree(&chunks).root());
+        assert_eq!(result.total_chunks, TOTAL_SHARDS);
+        assert_eq!(result.required_chunks, REQUIRED_SHARDS);
+        assert_eq!(result.promised_chunks, TOTAL_SHARDS);
+        assert_eq!(result.assignments.values().map(|c| c.len()).sum::<usize>(), TOTAL_SHARDS);
+    }
+
+    #[tokio::test]
+    async fn test_submit_records_commitment_metadata() {
+        let url = spawn_stub_provider().await;
+        let providers = create_test_providers_at(&url);
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_eligible_providers().returning(move || Ok(providers.clone()));
+        pod.expect_submit_commitment().returning(|_, _, _, _, _| Ok(()));
+        pod.expect_commitment_exists().returning(|_| Ok(false));
+        pod.expect_wait_for_availability().returning(|_, _| Ok(()));
+        let store = Arc::new(InMemoryMetadataStore::default());
+        let clock = Arc::new(MockClock::default());
+        let dispenser = Dispenser::new(pod).with_metadata_store(Some(store.clone())).with_clock(clock.clone());
+
+        let data = "Data whose metadata is kept by the dispenser".repeat(100);
+        let result = dispenser.submit_data(data.as_bytes()).await.unwrap();
+
+        let metadata = store.get(result.commitment).await.unwrap().expect("submitted commitment is recorded");
+        let assigned = result.assignments.iter().map(|(provider, chunks)| (*provider, chunks.iter().map(|c| c.index).collect::<Vec<_>>())).collect::<HashMap<_, _>>();
+        assert_eq!(metadata.size, data.len());
+        assert_eq!(metadata.coding, CodingParams::new(REQUIRED_SHARDS, TOTAL_SHARDS));
+        assert_eq!(metadata.assignment, assigned);
+        assert_eq!(metadata.timestamp, clock.now().duration_since(UNIX_EPOCH).unwrap().as_secs());
+    }
+
+    // Metadata store recording every chunk of `commitment` at the provider in `assignment`
+    async fn create_metadata_store(commitment: FixedBytes<32>, size: usize, assignment: HashMap<Address, Vec<u16>>) -> Arc<InMemoryMetadataStore> {
+        let store = Arc::new(InMemoryMetadataStore::default());
+        let metadata = CommitmentMetadata { size, coding: CodingParams::new(REQUIRED_SHARDS, TOTAL_SHARDS), assignment, timestamp: 0 };
+        store.put(commitment, metadata).await.unwrap();
+        store
+    }
+
+    // The chunk indices each /batch-retrieve asked a provider for, in order
+    type RetrieveLog = Arc<Mutex<Vec<(Address, Vec<u16>)>>>;
+
+    // Dispenser whose first two test providers hold `held` chunks each on-chain and serve only those
+    fn create_hinted_retrieval_dispenser(chunks: &[Chunk], held: [Vec<u16>; 2], size: usize, chain_reads: bool) -> (Dispenser<MockPodaClientTrait>, RetrieveLog) {
+        let providers = create_test_providers()[..2].to_vec();
+        let holdings = HashMap::from([(providers[0].addr, held[0].clone()), (providers[1].addr, held[1].clone())]);
+
+        let asked = Arc::new(Mutex::new(Vec::new()));
+        let recorded = asked.clone();
+        let (served, served_holdings) = (chunks.to_vec(), holdings.clone());
+        let mut transport = MockProviderTransport::new();
+        transport.expect_batch_retrieve().returning(move |provider, request| {
+            recorded.lock().unwrap().push((provider.addr, request.indices.clone()));
+            let mut response = serve_chunks(&served, &request.indices);
+            for (index, chunk) in zip(&request.indices, &mut response.chunks) {
+                if !served_holdings[&provider.addr].contains(index) {
+                    *chunk = None;
+                }
+            }
+            Ok(response)
+        });
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(move |_| Ok((recoverable_commitment(size, Bytes::new()), true)));
+        pod.expect_get_providers().returning(move || Ok(providers.clone()));
+        if chain_reads {
+            pod.expect_get_provider_chunks().returning(move |_, provider| Ok(holdings[&provider].clone()));
+        } else {
+            pod.expect_get_provider_chunks().never();
         }
+
+        (Dispenser::new(pod).with_transport(Arc::new(transport)), asked)
+    }
+
+    #[tokio::test]
+    async fn test_accurate_recorded_placement_skips_chain_lookups() {
+        let data = "Data found where the dispenser put it".repeat(50).into_bytes();
+        let chunks = create_test_dispenser().await.encode_payload(&data);
+        let commitment = gen_merkle_tree(&chunks).root();
+        let providers = create_test_providers();
+        let held = [(0..12).collect::<Vec<_>>(), (12..TOTAL_SHARDS as u16).collect()];
+
+        let store = create_metadata_store(commitment, data.len(), HashMap::from([(providers[0].addr, held[0].clone()), (providers[1].addr, held[1].clone())])).await;
+        let (dispenser, _) = create_hinted_retrieval_dispenser(&chunks, held, data.len(), false);
+        let dispenser = dispenser.with_metadata_store(Some(store));
+
+        let report = dispenser.retrieve_data_detailed(commitment, None, None).await.unwrap();
+        assert_eq!(report.data, data);
+        assert!(report.shards_missing.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stale_recorded_placement_is_confirmed_on_chain() {
+        let data = "Data whose chunks moved after it was submitted".repeat(50).into_bytes();
+        let chunks = create_test_dispenser().await.encode_payload(&data);
+        let commitment = gen_merkle_tree(&chunks).root();
+        let providers = create_test_providers();
+
+        // everything was sent to the first provider, but the last chunks were re-attested by the second
+        let store = create_metadata_store(commitment, data.len(), HashMap::from([(providers[0].addr, (0..TOTAL_SHARDS as u16).collect())])).await;
+        let held = [(0..REQUIRED_SHARDS as u16).collect::<Vec<_>>(), (REQUIRED_SHARDS as u16..TOTAL_SHARDS as u16).collect()];
+        let (dispenser, asked) = create_hinted_retrieval_dispenser(&chunks, held.clone(), data.len(), true);
+        let dispenser = dispenser.with_metadata_store(Some(store));
+
+        let report = dispenser.retrieve_data_detailed(commitment, None, None).await.unwrap();
+        assert_eq!(report.data, data);
+        assert!(report.shards_missing.is_empty());
+        assert_eq!(asked.lock().unwrap()[1..], [(providers[1].addr, held[1].clone())]);
+    }
+
+    #[tokio::test]
+    async fn test_submitted_commitments_are_bound_to_the_same_chunks() {
+        let url = spawn_stub_provider().await;
+        let providers = create_test_providers_at(&url);
+
+        let submitted = Arc::new(Mutex::new(None));
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_eligible_providers().returning(move || Ok(providers.clone()));
+        pod.expect_commitment_exists().returning(|_| Ok(false));
+        let captured = submitted.clone();
+        pod.expect_submit_commitment().returning(move |root, _, _, _, kzg_commitment| {
+            *captured.lock().unwrap() = Some((root, kzg_commitment));
+            Ok(())
+        });
+        pod.expect_wait_for_availability().returning(|_, _| Ok(()));
+        let dispenser = Dispenser::new(pod);
+
+        let data = "Data committed to twice, once per scheme".repeat(100);
+        dispenser.submit_data(data.as_bytes()).await.unwrap();
+
+        let (root, kzg_commitment) = submitted.lock().unwrap().take().unwrap();
+        let chunks = dispenser.encode_payload(data.as_bytes());
+        assert!(verify_commitment_binding(&chunks, root, KzgCommitment::try_from(kzg_commitment).unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_commitment_binding_rejects_mismatched_chunks() {
+        let dispenser = create_test_dispenser().await;
+        let chunks = dispenser.encode_payload("The chunks the merkle tree was built from".repeat(50).as_bytes());
+        let other = dispenser.encode_payload("Different chunks a buggy dispenser committed to".repeat(50).as_bytes());
+        let root = gen_merkle_tree(&chunks).root();
+        let (kzg_commitment, _) = kzg_commit(&chunks);
+        let (other_kzg_commitment, _) = kzg_commit(&other);
+
+        assert!(verify_commitment_binding(&chunks, root, kzg_commitment.clone()));
+        assert!(!verify_commitment_binding(&chunks, root, other_kzg_commitment.clone()));
+        assert!(!verify_commitment_binding(&other, root, other_kzg_commitment));
+        assert!(!verify_commitment_binding(&chunks, gen_merkle_tree(&other).root(), kzg_commitment.clone()));
+        assert!(!verify_commitment_binding(&[], root, kzg_commitment));
+    }
+
+    #[tokio::test]
+    async fn test_submit_fails_when_too_few_chunks_are_promised() {
+        let providers = create_test_providers();
+        let rejecting = providers[2].name.clone();
+
+        let mut transport = MockProviderTransport::new();
+        transport.expect_version().returning(|_| Err(anyhow::anyhow!("no version")));
+        transport.expect_capabilities().returning(|_| Err(anyhow::anyhow!("no capabilities")));
+        transport.expect_batch_store().returning(move |provider, _| if provider.name == rejecting {
+            Err(anyhow::anyhow!("provider down"))
+        } else {
+            Ok(None)
+        });
+
+        let eligible = providers.clone();
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_eligible_providers().returning(move || Ok(eligible.clone()));
+        pod.expect_submit_commitment().returning(|_, _, _, _, _| Ok(()));
+        pod.expect_commitment_exists().returning(|_| Ok(false));
+        pod.expect_wait_for_availability().never();
+        let dispenser = Dispenser::new(pod).with_transport(Arc::new(transport));
+
+        let data = "Data most providers turn away".repeat(100);
+        let chunks = dispenser.encode_payload(data.as_bytes());
+        let assignments = dispenser.assign_chunks(&chunks, &providers).unwrap();
+        let accepted = TOTAL_SHARDS - assignments[&providers[2].addr].len();
+        assert!(accepted < REQUIRED_SHARDS);
+
+        let err = dispenser.submit_data(data.as_bytes()).await.unwrap_err();
+        assert!(matches!(err, DispenserError::NotEnoughChunks { available, required } if available == accepted && required == REQUIRED_SHARDS));
+    }
+
+    #[tokio::test]
+    async fn test_resubmitting_the_same_data_short_circuits() {
+        let providers = create_test_providers();
+        let stores = Arc::new(Mutex::new(0));
+        let mut transport = MockProviderTransport::new();
+        transport.expect_version().returning(|_| Err(anyhow::anyhow!("no version")));
+        transport.expect_capabilities().returning(|_| Err(anyhow::anyhow!("no capabilities")));
+        let counted = stores.clone();
+        transport.expect_batch_store().returning(move |_, _| {
+            *counted.lock().unwrap() += 1;
+            Ok(None)
+        });
+
+        let submitted = Arc::new(AtomicBool::new(false));
+        let exists = submitted.clone();
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_eligible_providers().returning(move || Ok(providers.clone()));
+        pod.expect_commitment_exists().returning(move |_| Ok(exists.load(Ordering::SeqCst)));
+        // the KZG commitment only feeds submit_commitment, so a single call means it was computed once
+        pod.expect_submit_commitment().times(1).returning(move |_, _, _, _, _| {
+            submitted.store(true, Ordering::SeqCst);
+            Ok(())
+        });
+        pod.expect_wait_for_availability().returning(|_, _| Ok(()));
+        let dispenser = Dispenser::new(pod).with_transport(Arc::new(transport));
+
+        let data = "Data submitted twice".repeat(100);
+        let first = dispenser.submit_data(data.as_bytes()).await.unwrap();
+        let stores_after_first = *stores.lock().unwrap();
+
+        let err = dispenser.submit_data(data.as_bytes()).await.unwrap_err();
+        assert!(matches!(err, DispenserError::AlreadySubmitted(commitment) if commitment == first.commitment));
+        assert_eq!(err.http_status(), warp::http::StatusCode::CONFLICT);
+        assert_eq!(*stores.lock().unwrap(), stores_after_first);
+    }
+
+    #[tokio::test]
+    async fn test_inactive_provider_gets_no_chunks() {
+        let url = spawn_stub_provider().await;
+        let mut providers = create_test_providers_at(&url);
+        providers[2].active = false;
+        let eligible = providers.iter().filter(|p| p.active).cloned().collect::<Vec<_>>();
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_providers().never();
+        pod.expect_get_eligible_providers().returning(move || Ok(eligible.clone()));
+        pod.expect_submit_commitment().returning(|_, _, _, _, _| Ok(()));
+        pod.expect_commitment_exists().returning(|_| Ok(false));
+        pod.expect_wait_for_availability().returning(|_, _| Ok(()));
+        let dispenser = Dispenser::new(pod);
+
+        let result = dispenser.submit_data("Data kept away from inactive providers".repeat(100).as_bytes()).await.unwrap();
+
+        assert!(!result.assignments.contains_key(&providers[2].addr));
+        assert_eq!(result.assignments.values().map(|c| c.len()).sum::<usize>(), TOTAL_SHARDS);
+    }
+
+    #[tokio::test]
+    async fn test_submit_without_staked_providers() {
+        let zero_stake = create_test_providers().into_iter().map(|mut p| {
+            p.stakedAmount = U256::ZERO;
+            p
+        }).collect::<Vec<_>>();
+
+        for providers in [vec![], zero_stake] {
+            let mut pod = MockPodaClientTrait::new();
+            pod.expect_get_eligible_providers().returning(move || Ok(providers.clone()));
+            pod.expect_submit_commitment().never();
+            let dispenser = Dispenser::new(pod);
+
+            let err = dispenser.submit_data("Data with nowhere to go".repeat(10).as_bytes()).await.unwrap_err();
+            assert!(matches!(err, DispenserError::NoEligibleProviders));
+            assert_eq!(err.to_string(), "No eligible providers with positive stake");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resubmit_after_provider_outage() {
+        let data = "Data that outlives a provider outage".repeat(100);
+        let providers = create_test_providers()[..2].to_vec();
+        let chunks = create_test_dispenser().await.encode_payload(data.as_bytes());
+        let assignments = create_test_dispenser().await.assign_chunks(&chunks, &providers).unwrap();
+
+        // take down whichever provider holds the majority so the first submission cannot succeed
+        let down = if assignments[&providers[0].addr].len() > assignments[&providers[1].addr].len() { 0 } else { 1 };
+        let mut stub_providers = vec![];
+        let mut up = vec![];
+        let mut received = vec![];
+        for (i, mut provider) in providers.into_iter().enumerate() {
+            let is_up = Arc::new(AtomicBool::new(i != down));
+            let got = Arc::new(Mutex::new(vec![]));
+            provider.url = spawn_recording_provider(is_up.clone(), got.clone()).await;
+            stub_providers.push(provider);
+            up.push(is_up);
+            received.push(got);
+        }
+
+        let mut pod = MockPodaClientTrait::new();
+        let returned_providers = stub_providers.clone();
+        pod.expect_get_eligible_providers().returning(move || Ok(returned_providers.clone()));
+        let submitted = Arc::new(AtomicBool::new(false));
+        let exists = submitted.clone();
+        pod.expect_submit_commitment().times(1).returning(move |_, _, _, _, _| {
+            submitted.store(true, Ordering::SeqCst);
+            Ok(())
+        });
+        pod.expect_commitment_exists().returning(move |_| Ok(exists.load(Ordering::SeqCst)));
+        let reported = received.clone();
+        pod.expect_get_provider_chunks().returning(move |_, addr| {
+            let i = (addr.0[0] - 1) as usize;
+            Ok(reported[i].lock().unwrap().clone())
+        });
+        pod.expect_wait_for_availability().returning(|_, _| Ok(()));
+        let dispenser = Dispenser::new(pod);
+
+        let err = dispenser.submit_data(data.as_bytes()).await.unwrap_err();
+        assert!(err.to_string().contains("Not enough chunks"));
+        let healthy_before = received[1 - down].lock().unwrap().len();
+        assert!(received[down].lock().unwrap().is_empty());
+
+        up[down].store(true, Ordering::SeqCst);
+        let commitment = gen_merkle_tree(&chunks).root();
+        let result = dispenser.resubmit_data(commitment, data.as_bytes()).await.unwrap();
+
+        assert_eq!(result.commitment, commitment);
+        assert_eq!(result.promised_chunks, TOTAL_SHARDS);
+        assert_eq!(received[1 - down].lock().unwrap().len(), healthy_before);
+        assert_eq!(received[down].lock().unwrap().len(), assignments[&stub_providers[down].addr].len());
+    }
+
+    #[tokio::test]
+    async fn test_proof_format_follows_provider_capabilities() {
+        let data = "Data sent in whichever proof format a provider supports".repeat(100);
+        let chunks = create_test_dispenser().await.encode_payload(data.as_bytes());
+        let root = gen_merkle_tree(&chunks).root();
+
+        let mut providers = create_test_providers()[..2].to_vec();
+        let per_chunk = Arc::new(Mutex::new(vec![]));
+        let multi = Arc::new(Mutex::new(vec![]));
+        providers[0].url = spawn_proof_checking_provider(vec![ProofFormat::PerChunk], root, per_chunk.clone()).await;
+        providers[1].url = spawn_proof_checking_provider(vec![ProofFormat::PerChunk, ProofFormat::MultiProof], root, multi.clone()).await;
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_eligible_providers().returning(move || Ok(providers.clone()));
+        pod.expect_submit_commitment().returning(|_, _, _, _, _| Ok(()));
+        pod.expect_commitment_exists().returning(|_| Ok(false));
+        pod.expect_wait_for_availability().returning(|_, _| Ok(()));
+        let dispenser = Dispenser::new(pod).with_multiproof(true);
+
+        let result = dispenser.submit_data(data.as_bytes()).await.unwrap();
+
+        assert_eq!(result.promised_chunks, TOTAL_SHARDS);
+        assert_eq!(*per_chunk.lock().unwrap(), vec![ProofFormat::PerChunk]);
+        assert_eq!(*multi.lock().unwrap(), vec![ProofFormat::MultiProof]);
+    }
+
+    #[tokio::test]
+    async fn test_proof_format_follows_provider_version() {
+        let dispenser = create_test_dispenser().await;
+        let chunks = dispenser.encode_payload("Data sent in the format a provider's version allows".repeat(100).as_bytes());
+        let (_, polynomial) = kzg_commit(&chunks);
+        let providers = create_test_providers()[..2].to_vec();
+
+        let multiproof_provider = providers[0].addr;
+        let mut transport = MockProviderTransport::new();
+        transport.expect_version().returning(move |provider| Ok(VersionResponse {
+            crate_version: "0.1.0".to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            features: if provider.addr == multiproof_provider { vec![ProtocolFeature::Multiproof] } else { vec![ProtocolFeature::PossessionProofs] },
+        }));
+        transport.expect_capabilities().never();
+        let received = Arc::new(Mutex::new(HashMap::new()));
+        let recorded = received.clone();
+        transport.expect_batch_store().returning(move |provider, request| {
+            recorded.lock().unwrap().insert(provider.addr, request.merkle_multi_proof.is_some());
+            Ok(None)
+        });
+        let dispenser = dispenser.with_transport(Arc::new(transport)).with_multiproof(true);
+
+        let assignments = ChunkAssignment::from([
+            (providers[0].addr, chunks[..12].to_vec()),
+            (providers[1].addr, chunks[12..].to_vec()),
+        ]);
+        let (promised, _) = dispenser.distribute_chunks(&chunks, &polynomial, &assignments, &providers, &HashMap::new()).await;
+
+        assert_eq!(promised, TOTAL_SHARDS);
+        assert_eq!(*received.lock().unwrap(), HashMap::from([(providers[0].addr, true), (providers[1].addr, false)]));
+    }
+
+    #[tokio::test]
+    async fn test_per_chunk_proofs_are_sent_unless_multiproof_is_enabled() {
+        let dispenser = create_test_dispenser().await;
+        let chunks = dispenser.encode_payload("Data whose chunks must stay challengeable".repeat(100).as_bytes());
+        let (_, polynomial) = kzg_commit(&chunks);
+        let providers = create_test_providers()[..1].to_vec();
+
+        // the provider would accept a multiproof, but is never asked
+        let mut transport = MockProviderTransport::new();
+        transport.expect_version().never();
+        transport.expect_capabilities().never();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let recorded = received.clone();
+        transport.expect_batch_store().returning(move |_, request| {
+            recorded.lock().unwrap().push((request.merkle_multi_proof.is_some(), request.merkle_proofs.len()));
+            Ok(None)
+        });
+        let dispenser = dispenser.with_transport(Arc::new(transport));
+
+        let assignments = ChunkAssignment::from([(providers[0].addr, chunks.clone())]);
+        let (promised, _) = dispenser.distribute_chunks(&chunks, &polynomial, &assignments, &providers, &HashMap::new()).await;
+
+        assert_eq!(promised, TOTAL_SHARDS);
+        assert_eq!(*received.lock().unwrap(), vec![(false, TOTAL_SHARDS)]);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_retrievals_overlap() {
+        let data = "Data decoded while another retrieval is in flight".repeat(2_000).into_bytes();
+        let chunks = create_test_dispenser().await.encode_payload(&data);
+        let commitment = gen_merkle_tree(&chunks).root();
+        let provider = create_test_providers().remove(0);
+
+        // the mocks answer without yielding, so a retrieval only gives way while it decodes
+        let mut transport = MockProviderTransport::new();
+        transport.expect_batch_retrieve().returning(move |_, request| Ok(serve_chunks(&chunks, &request.indices)));
+        let size = data.len();
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(move |_| Ok((recoverable_commitment(size, Bytes::new()), true)));
+        pod.expect_get_providers().returning(move || Ok(vec![provider.clone()]));
+        pod.expect_get_provider_chunks().returning(|_, _| Ok((0..REQUIRED_SHARDS as u16).collect()));
+        let dispenser = Dispenser::new(pod).with_transport(Arc::new(transport));
+
+        let timed_retrieval = || async {
+            let started = std::time::Instant::now();
+            let retrieved = dispenser.retrieve_data(commitment, None, None).await.unwrap();
+            (started, std::time::Instant::now(), retrieved)
+        };
+        let ((first_start, first_end, first), (second_start, second_end, second)) = tokio::join!(timed_retrieval(), timed_retrieval());
+
+        assert_eq!(first, data);
+        assert_eq!(second, data);
+        assert!(second_start < first_end && first_start < second_end, "retrievals ran one after the other");
+    }
+
+    #[tokio::test]
+    async fn test_preferred_providers_are_asked_first_and_stop_early() {
+        let data = "Data read from the closest providers".repeat(100).into_bytes();
+        let chunks = create_test_dispenser().await.encode_payload(&data);
+        let commitment = gen_merkle_tree(&chunks).root();
+        let providers = create_test_providers()[..2].to_vec();
+        let (slow, preferred) = (providers[0].addr, providers[1].addr);
+
+        let mut transport = MockProviderTransport::new();
+        transport.expect_batch_retrieve().withf(move |provider, _| provider.addr == slow).never();
+        transport.expect_batch_retrieve().withf(move |provider, _| provider.addr == preferred).times(1).returning(move |_, request| Ok(serve_chunks(&chunks, &request.indices)));
+        let size = data.len();
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(move |_| Ok((recoverable_commitment(size, Bytes::new()), true)));
+        pod.expect_get_providers().returning(move || Ok(providers.clone()));
+        // the slow provider is registered first and holds the parity chunks
+        pod.expect_get_provider_chunks().returning(move |_, provider| Ok(if provider == preferred {
+            (0..REQUIRED_SHARDS as u16).collect()
+        } else {
+            (REQUIRED_SHARDS as u16..TOTAL_SHARDS as u16).collect()
+        }));
+        let dispenser = Dispenser::new(pod).with_transport(Arc::new(transport));
+
+        let strategy = RetrievalStrategy { preferred: vec![preferred], stop_early: true };
+        let report = dispenser.retrieve_data_detailed(commitment, None, Some(&strategy)).await.unwrap();
+
+        assert_eq!(report.data, data);
+        assert_eq!(report.providers_responded, vec![create_test_providers()[1].name.clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_reports_why_providers_failed() {
+        let data = "Data held partly by providers that cannot serve it".repeat(100).into_bytes();
+        let chunks = create_test_dispenser().await.encode_payload(&data);
+        let commitment = gen_merkle_tree(&chunks).root();
+
+        let missing = warp::path("batch-retrieve").map(|| warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "All chunks not found"})),
+            warp::http::StatusCode::NOT_FOUND,
+        ));
+        let (missing_addr, server) = warp::serve(missing).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        let closed_addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+
+        let mut providers = create_test_providers();
+        providers[0].url = spawn_retrieval_provider(chunks).await;
+        providers[1].url = format!("http://{}", missing_addr);
+        providers[2].url = format!("http://{}", closed_addr);
+        let names = providers.iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+        let (serving, missing) = (providers[0].addr, providers[1].addr);
+
+        let size = data.len();
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(move |_| Ok((recoverable_commitment(size, Bytes::new()), true)));
+        pod.expect_get_providers().returning(move || Ok(providers.clone()));
+        pod.expect_get_provider_chunks().returning(move |_, provider| Ok(if provider == serving {
+            (0..REQUIRED_SHARDS as u16).collect()
+        } else if provider == missing {
+            (REQUIRED_SHARDS as u16..20).collect()
+        } else {
+            (20..TOTAL_SHARDS as u16).collect()
+        }));
+        let dispenser = Dispenser::new(pod);
+
+        let report = dispenser.retrieve_data_detailed(commitment, None, None).await.unwrap();
+
+        assert_eq!(report.data, data);
+        assert_eq!(report.providers_responded, vec![names[0].clone()]);
+        assert_eq!(report.providers_failed.len(), 2);
+        assert_eq!(report.providers_failed[0], (names[1].clone(), RetrieveError::NotFound));
+        assert_eq!(report.providers_failed[1].0, names[2]);
+        assert!(matches!(report.providers_failed[1].1, RetrieveError::Unreachable(_)));
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_with_expected_hash() {
+        let data = "Data with a known hash".repeat(50);
+        let (dispenser, commitment) = create_retrieval_dispenser(data.as_bytes()).await;
+
+        let retrieved = dispenser.retrieve_data(commitment, Some(keccak256(data.as_bytes())), None).await.unwrap();
+        assert_eq!(retrieved, data.as_bytes());
+
+        let wrong = keccak256(b"some other data");
+        let err = dispenser.retrieve_data(commitment, Some(wrong), None).await.unwrap_err();
+        assert!(matches!(err, DispenserError::HashMismatch { expected, .. } if expected == wrong));
+        assert!(err.to_string().contains(&format!("expected {}", wrong)));
+    }
+
+    #[tokio::test]
+    async fn test_retrieval_report_with_provider_down() {
+        let data = "Data retrieved around an outage".repeat(50);
+        let chunks = create_test_dispenser().await.encode_payload(data.as_bytes());
+        let commitment = gen_merkle_tree(&chunks).root();
+
+        let mut providers = create_test_providers();
+        providers.truncate(2);
+        providers[0].addr = Address::with_last_byte(1);
+        providers[0].url = spawn_retrieval_provider(chunks.clone()).await;
+        providers[1].addr = Address::with_last_byte(2);
+        providers[1].url = spawn_recording_provider(Arc::new(AtomicBool::new(false)), Arc::default()).await;
+
+        let size = data.len();
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(move |_| Ok((recoverable_commitment(size, Bytes::new()), true)));
+        let registered = providers.clone();
+        pod.expect_get_providers().returning(move || Ok(registered.clone()));
+        // the first provider holds the data shards, the second one the parity shards
+        pod.expect_get_provider_chunks().returning(|_, addr| Ok(if addr == Address::with_last_byte(1) {
+            (0..REQUIRED_SHARDS as u16).collect()
+        } else {
+            (REQUIRED_SHARDS as u16..TOTAL_SHARDS as u16).collect()
+        }));
+        let dispenser = Dispenser::new(pod);
+
+        let report = dispenser.retrieve_data_detailed(commitment, None, None).await.unwrap();
+        assert_eq!(report.data, data.as_bytes());
+        assert_eq!(report.bytes, data.len());
+        assert_eq!((report.total_shards, report.required_shards), (TOTAL_SHARDS, REQUIRED_SHARDS));
+        assert_eq!(report.shards_used, REQUIRED_SHARDS);
+        assert_eq!(report.shards_missing, (REQUIRED_SHARDS as u16..TOTAL_SHARDS as u16).collect::<Vec<_>>());
+        assert_eq!(report.providers_responded, vec![providers[0].name.clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_parity_chunk_is_caught_after_decode() {
+        let data = "Parity that decodes into the wrong data".repeat(20);
+        // the parity shard was corrupted before committing, so it still proves into the commitment
+        let mut chunks = create_test_dispenser().await.encode_payload(data.as_bytes());
+        chunks[REQUIRED_SHARDS].data[0] ^= 0xff;
+        let commitment = gen_merkle_tree(&chunks).root();
+
+        // half of the data shards are missing, so the corrupted parity shard feeds the decode
+        let held = (REQUIRED_SHARDS as u16 / 2..TOTAL_SHARDS as u16).collect::<Vec<_>>();
+        let mut provider = create_test_providers().remove(0);
+        provider.url = spawn_retrieval_provider(chunks.clone()).await;
+
+        let size = data.len();
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(move |_| Ok((recoverable_commitment(size, Bytes::new()), true)));
+        pod.expect_get_providers().returning(move || Ok(vec![provider.clone()]));
+        let provider_chunks = held.clone();
+        pod.expect_get_provider_chunks().returning(move |_, _| Ok(provider_chunks.clone()));
+        let dispenser = Dispenser::new(pod);
+
+        let partial = chunks.into_iter().map(|c| held.contains(&c.index).then_some(c)).collect::<Vec<_>>();
+        let (decoded, _) = dispenser.erasure_decode(partial, REQUIRED_SHARDS, TOTAL_SHARDS, data.len()).unwrap();
+        assert_ne!(decoded, data.as_bytes());
+
+        let err = dispenser.retrieve_data(commitment, None, None).await.unwrap_err();
+        assert!(matches!(err, DispenserError::ReconstructionMismatch(c) if c == commitment));
+        assert_eq!(err.http_status(), warp::http::StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_served_chunks_are_skipped() {
+        let data = "Data served partly by a dishonest provider".repeat(50).into_bytes();
+        let chunks = create_test_dispenser().await.encode_payload(&data);
+        let commitment = gen_merkle_tree(&chunks).root();
+        let providers = create_test_providers()[..2].to_vec();
+        let dishonest = providers[0].addr;
+
+        // chunk 0 is tampered with and chunk 1 is swapped for chunk 2, both with genuine proofs
+        let mut transport = MockProviderTransport::new();
+        let served = chunks.clone();
+        transport.expect_batch_retrieve().returning(move |provider, request| {
+            let mut response = serve_chunks(&served, &request.indices);
+            if provider.addr == dishonest {
+                response.chunks[0].as_mut().unwrap().data[0] ^= 0xff;
+                response.chunks[1] = Some(Chunk { index: 1, data: served[2].data.clone() });
+            }
+            Ok(response)
+        });
+        let size = data.len();
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(move |_| Ok((recoverable_commitment(size, Bytes::new()), true)));
+        pod.expect_get_providers().returning(move || Ok(providers.clone()));
+        pod.expect_get_provider_chunks().returning(move |_, provider| Ok(if provider == dishonest {
+            (0..4).collect()
+        } else {
+            (4..TOTAL_SHARDS as u16).collect()
+        }));
+        let dispenser = Dispenser::new(pod).with_transport(Arc::new(transport));
+
+        let report = dispenser.retrieve_data_detailed(commitment, None, None).await.unwrap();
+
+        let names = create_test_providers().into_iter().map(|p| p.name).collect::<Vec<_>>();
+        assert_eq!(report.data, data);
+        assert_eq!(report.shards_missing, vec![0, 1]);
+        assert_eq!(report.providers_responded, vec![names[0].clone(), names[1].clone()]);
+        assert_eq!(report.providers_failed, vec![(names[0].clone(), RetrieveError::InvalidChunks(vec![0, 1]))]);
+    }
+
+    #[tokio::test]
+    async fn test_verify_commitment_against_local_data() {
+        let data = "Data checked against its commitment".repeat(20).into_bytes();
+        let chunks = create_test_dispenser().await.encode_payload(&data);
+        let commitment = gen_merkle_tree(&chunks).root();
+        let (kzg_commitment, _) = kzg_commit(&chunks);
+        let kzg_bytes: Bytes = kzg_commitment.into();
+
+        let size = data.len();
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(move |_| Ok((recoverable_commitment(size, kzg_bytes.clone()), true)));
+        let dispenser = Dispenser::new(pod);
+
+        assert!(dispenser.verify_commitment(commitment, &data).await.unwrap());
+
+        let mut flipped = data.clone();
+        flipped[7] ^= 1;
+        assert!(!dispenser.verify_commitment(commitment, &flipped).await.unwrap());
+        assert!(!dispenser.verify_commitment(commitment, &data[1..]).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_reconstruct_from_shuffled_subset() {
+        let dispenser = create_test_dispenser().await;
+        let data = "Chunks gathered from several providers".repeat(30);
+        let chunks = dispenser.encode_payload(data.as_bytes());
+
+        // all parity chunks and the tail of the data chunks, out of order
+        let mut subset = chunks[TOTAL_SHARDS - REQUIRED_SHARDS..].to_vec();
+        subset.reverse();
+        subset.swap(1, 9);
+        let decoded = dispenser.reconstruct_from(&subset, TOTAL_SHARDS, REQUIRED_SHARDS, data.len()).unwrap();
+        assert_eq!(decoded, data.as_bytes());
+
+        let err = dispenser.reconstruct_from(&subset[1..], TOTAL_SHARDS, REQUIRED_SHARDS, data.len()).unwrap_err();
+        assert!(err.to_string().contains("Not enough chunks"));
+        let out_of_range = Chunk { index: TOTAL_SHARDS as u16, data: vec![0; 32] };
+        assert!(dispenser.reconstruct_from(&[out_of_range], TOTAL_SHARDS, REQUIRED_SHARDS, data.len()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_erasure_decode_fails_gracefully() {
+        let dispenser = create_test_dispenser().await;
+        let data = "Shards that do not line up".repeat(30);
+        let chunks = dispenser.encode_payload(data.as_bytes());
+
+        let mut mismatched = chunks.iter().cloned().map(Some).collect::<Vec<_>>();
+        mismatched[3].as_mut().unwrap().data.pop();
+        let err = dispenser.erasure_decode(mismatched, REQUIRED_SHARDS, TOTAL_SHARDS, data.len()).unwrap_err();
+        assert!(err.to_string().contains("inconsistent lengths"));
+
+        let mut too_few = chunks.into_iter().map(Some).collect::<Vec<_>>();
+        for chunk in too_few.iter_mut().take(TOTAL_SHARDS - REQUIRED_SHARDS + 1) {
+            *chunk = None;
+        }
+        let err = dispenser.erasure_decode(too_few, REQUIRED_SHARDS, TOTAL_SHARDS, data.len()).unwrap_err();
+        assert!(err.to_string().contains("Failed to reconstruct"));
+    }
+
+    #[tokio::test]
+    async fn test_galois16_roundtrip_with_many_shards() {
+        let dispenser = create_test_dispenser().await;
+        let params = CodingParams::new(200, 300);
+        assert_eq!(params.field, ErasureField::Galois16);
+
+        let data = "More shards than GF(2^8) can address".repeat(40);
+        let chunks = dispenser.erasure_encode_with(data.as_bytes(), &params);
+        assert_eq!(chunks.len(), 300);
+
+        let mut partial = chunks.into_iter().map(Some).collect::<Vec<_>>();
+        for chunk in partial.iter_mut().step_by(3) {
+            *chunk = None;
+        }
+        let (decoded, _) = dispenser.erasure_decode_with(partial, &params, data.len()).unwrap();
+        assert_eq!(decoded, data.as_bytes());
+
+        let galois8 = CodingParams::new(200, 300).with_field(ErasureField::Galois8);
+        assert!(dispenser.codecs.encode(&galois8, &mut dispenser.create_shards(data.as_bytes(), 200, 300)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_codecs_are_built_once_per_params() {
+        let dispenser = create_test_dispenser().await;
+        let data = "Encoded over and over by the repair path".repeat(20);
+
+        let first = dispenser.erasure_encode(data.as_bytes(), REQUIRED_SHARDS, TOTAL_SHARDS);
+        for _ in 0..3 {
+            let again = dispenser.erasure_encode(data.as_bytes(), REQUIRED_SHARDS, TOTAL_SHARDS);
+            assert_eq!(gen_merkle_tree(&again).root(), gen_merkle_tree(&first).root());
+        }
+        let shards = first.into_iter().map(Some).collect();
+        dispenser.erasure_decode(shards, REQUIRED_SHARDS, TOTAL_SHARDS, data.len()).unwrap();
+        assert_eq!(dispenser.codecs.built(), 1);
+
+        dispenser.erasure_encode(data.as_bytes(), 4, 6);
+        dispenser.erasure_encode(data.as_bytes(), 4, 6);
+        assert_eq!(dispenser.codecs.built(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_small_payload_is_padded_to_floor() {
+        let dispenser = create_test_dispenser().await;
+        let data = b"sixteen byte msg";
+        assert_eq!(data.len(), DEFAULT_MIN_DATA_SIZE);
+
+        let chunks = dispenser.encode_payload(data);
+        assert_eq!(chunks.len(), TOTAL_SHARDS);
+        for chunk in &chunks {
+            assert_eq!(chunk.data.len(), DEFAULT_MIN_PAYLOAD_SIZE / REQUIRED_SHARDS);
+        }
+
+        let shards = chunks.into_iter().map(Some).collect();
+        let (decoded, _) = dispenser.erasure_decode(shards, REQUIRED_SHARDS, TOTAL_SHARDS, data.len()).unwrap();
+        assert_eq!(decoded, data);
     }
 
     #[tokio::test]
@@ -422,11 +1950,200 @@ mod tests {
         
         // Verify each provider has at least one chunk
         for provider in &providers {
-            assert!(assignments.contains_key(&provider.name));
-            assert!(!assignments[&provider.name].is_empty());
+            assert!(assignments.contains_key(&provider.addr));
+            assert!(!assignments[&provider.addr].is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_estimate_matches_submission_shape() {
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_eligible_providers().returning(|| Ok(create_test_providers()));
+        let dispenser = Dispenser::new(pod);
+
+        for data_len in [dispenser.min_data_size(), 1001, 100_000] {
+            let estimate = dispenser.estimate(data_len).await.unwrap();
+            let chunks = dispenser.encode_payload(&vec![0xab; data_len]);
+
+            assert_eq!(estimate.total_chunks, TOTAL_SHARDS);
+            assert_eq!(estimate.required_chunks, REQUIRED_SHARDS);
+            assert_eq!(estimate.per_provider.len(), create_test_providers().len());
+            assert_eq!(estimate.per_provider.values().sum::<usize>(), estimate.total_chunks);
+            assert_eq!(estimate.total_stored_bytes, chunks.iter().map(|chunk| chunk.data.len()).sum::<usize>());
+        }
+
+        assert!(matches!(dispenser.estimate(1).await, Err(DispenserError::DataTooSmall { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_providers_sharing_a_name_keep_separate_assignments() {
+        let providers = create_test_providers().into_iter().map(|mut p| {
+            p.name = "Same Name".to_string();
+            p
+        }).collect::<Vec<_>>();
+
+        let mut transport = MockProviderTransport::new();
+        transport.expect_version().returning(|_| Err(anyhow::anyhow!("no version")));
+        transport.expect_capabilities().returning(|_| Err(anyhow::anyhow!("no capabilities")));
+        let stored = Arc::new(Mutex::new(HashMap::<Address, usize>::new()));
+        let recorded = stored.clone();
+        transport.expect_batch_store().returning(move |provider, request| {
+            *recorded.lock().unwrap().entry(provider.addr).or_default() += request.chunks.len();
+            Ok(None)
+        });
+
+        let eligible = providers.clone();
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_eligible_providers().returning(move || Ok(eligible.clone()));
+        pod.expect_commitment_exists().returning(|_| Ok(false));
+        pod.expect_submit_commitment().returning(|_, _, _, _, _| Ok(()));
+        pod.expect_wait_for_availability().returning(|_, _| Ok(()));
+        let dispenser = Dispenser::new(pod).with_transport(Arc::new(transport));
+
+        let result = dispenser.submit_data("Data for providers with one name".repeat(100).as_bytes()).await.unwrap();
+
+        assert_eq!(result.assignments.len(), providers.len());
+        assert_eq!(result.assignments.values().map(|c| c.len()).sum::<usize>(), TOTAL_SHARDS);
+        let stored = stored.lock().unwrap();
+        for provider in &providers {
+            assert_eq!(stored.get(&provider.addr).copied().unwrap_or_default(), result.assignments[&provider.addr].len());
         }
     }
 
+    #[tokio::test]
+    async fn test_duplicate_or_out_of_range_indices_are_not_proven() {
+        let dispenser = create_test_dispenser().await;
+        let chunks = dispenser.encode_payload("Data with a tampered assignment".repeat(100).as_bytes());
+        let (_, polynomial) = kzg_commit(&chunks);
+        let providers = create_test_providers();
+
+        let mut transport = MockProviderTransport::new();
+        transport.expect_version().returning(|_| Err(anyhow::anyhow!("no version")));
+        transport.expect_capabilities().returning(|_| Err(anyhow::anyhow!("no capabilities")));
+        let honest = providers[2].addr;
+        transport.expect_batch_store().times(1).withf(move |provider, _| provider.addr == honest).returning(|_, _| Ok(None));
+        let dispenser = dispenser.with_transport(Arc::new(transport));
+
+        let past_the_end = Chunk { index: TOTAL_SHARDS as u16, data: chunks[0].data.clone() };
+        let assignments = ChunkAssignment::from([
+            (providers[0].addr, vec![chunks[3].clone(), chunks[3].clone()]),
+            (providers[1].addr, vec![chunks[4].clone(), past_the_end]),
+            (providers[2].addr, vec![chunks[5].clone()]),
+        ]);
+
+        let (promised, _) = dispenser.distribute_chunks(&chunks, &polynomial, &assignments, &providers, &HashMap::new()).await;
+        assert_eq!(promised, 1);
+    }
+
+    #[tokio::test]
+    async fn test_only_valid_store_receipts_are_kept() {
+        let dispenser = create_test_dispenser().await;
+        let chunks = dispenser.encode_payload("Data stored against a signed receipt".repeat(100).as_bytes());
+        let (_, polynomial) = kzg_commit(&chunks);
+        let (honest, forger) = (PrivateKeySigner::random(), PrivateKeySigner::random());
+        let mut providers = create_test_providers()[..2].to_vec();
+        providers[0].addr = honest.address();
+        providers[1].addr = forger.address();
+
+        // the forger hands back a receipt signed by some other key
+        let mut transport = MockProviderTransport::new();
+        transport.expect_version().returning(|_| Err(anyhow::anyhow!("no version")));
+        transport.expect_capabilities().returning(|_| Err(anyhow::anyhow!("no capabilities")));
+        let honest_addr = honest.address();
+        transport.expect_batch_store().returning(move |provider, request| {
+            let indices = request.chunks.iter().map(|c| c.index).collect();
+            let signer = if provider.addr == honest_addr { &honest } else { &PrivateKeySigner::random() };
+            Ok(Some(StoreReceipt::sign(request.commitment, indices, signer).unwrap()))
+        });
+        let dispenser = dispenser.with_transport(Arc::new(transport));
+
+        let assignments = ChunkAssignment::from([
+            (providers[0].addr, chunks[..12].to_vec()),
+            (providers[1].addr, chunks[12..].to_vec()),
+        ]);
+        let (promised, receipts) = dispenser.distribute_chunks(&chunks, &polynomial, &assignments, &providers, &HashMap::new()).await;
+
+        assert_eq!(promised, TOTAL_SHARDS);
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].provider_address, honest_addr);
+        assert_eq!(receipts[0].indices, (0..12).collect::<Vec<u16>>());
+        assert_eq!(receipts[0].commitment, gen_merkle_tree(&chunks).root());
+    }
+
+    // Dispenser whose pod assigns every chunk to one provider, which serves `served` along with the
+    // honest merkle proofs
+    fn create_audit_dispenser(chunks: &[Chunk], served: Vec<Option<Chunk>>, size: usize) -> (Dispenser<MockPodaClientTrait>, Address) {
+        let merkle_tree = gen_merkle_tree(chunks);
+        let proofs = chunks.iter().map(|c| merkle_tree::gen_proof(&merkle_tree, c.clone()).ok()).collect::<Vec<_>>();
+        let (kzg_commitment, _) = kzg_commit(chunks);
+        let kzg_bytes: Bytes = kzg_commitment.into();
+        let owner = create_test_providers().remove(1);
+        let owner_addr = owner.addr;
+
+        let mut transport = MockProviderTransport::new();
+        transport.expect_batch_retrieve().returning(move |_, request| Ok(BatchRetrieveResponse {
+            chunks: request.indices.iter().map(|i| served[*i as usize].clone()).collect(),
+            proofs: request.indices.iter().map(|i| proofs[*i as usize].clone()).collect(),
+        }));
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_chunk_owner().returning(move |_, _| Ok(owner_addr));
+        let info = owner.clone();
+        pod.expect_get_provider_info().returning(move |_| Ok(info.clone()));
+        pod.expect_get_providers().returning(move || Ok(vec![owner.clone()]));
+        pod.expect_get_provider_chunks().returning(|_, _| Ok((0..TOTAL_SHARDS as u16).collect()));
+        pod.expect_get_commitment_info().returning(move |_| Ok((recoverable_commitment(size, kzg_bytes.clone()), true)));
+
+        (Dispenser::new(pod).with_transport(Arc::new(transport)), owner_addr)
+    }
+
+    #[tokio::test]
+    async fn test_audit_chunk_checks_the_owners_copy() {
+        let data = "Chunk pulled for a dispute".repeat(30).into_bytes();
+        let chunks = create_test_dispenser().await.encode_payload(&data);
+        let commitment = gen_merkle_tree(&chunks).root();
+
+        let (dispenser, owner) = create_audit_dispenser(&chunks, chunks.iter().cloned().map(Some).collect(), data.len());
+        let audit = dispenser.audit_chunk(commitment, 5).await.unwrap();
+        assert_eq!(audit.provider, owner);
+        assert!(audit.retrieved && audit.merkle_valid && audit.kzg_valid);
+        assert!(audit.passed());
+
+        let mut corrupted = chunks.iter().cloned().map(Some).collect::<Vec<_>>();
+        corrupted[5].as_mut().unwrap().data[0] ^= 1;
+        let (dispenser, _) = create_audit_dispenser(&chunks, corrupted, data.len());
+        let audit = dispenser.audit_chunk(commitment, 5).await.unwrap();
+        assert!(audit.retrieved);
+        assert!(!audit.merkle_valid && !audit.kzg_valid);
+        assert!(!audit.passed());
+
+        let mut lost = chunks.iter().cloned().map(Some).collect::<Vec<_>>();
+        lost[5] = None;
+        let (dispenser, _) = create_audit_dispenser(&chunks, lost, data.len());
+        let audit = dispenser.audit_chunk(commitment, 5).await.unwrap();
+        assert!(!audit.retrieved && !audit.passed());
+
+        let err = dispenser.audit_chunk(commitment, TOTAL_SHARDS as u16).await.unwrap_err();
+        assert!(matches!(err, DispenserError::InvalidChunkIndex { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_audit_blames_the_owner_on_chain() {
+        let data = "Chunk whose recorded owner was slashed".repeat(30).into_bytes();
+        let chunks = create_test_dispenser().await.encode_payload(&data);
+        let commitment = gen_merkle_tree(&chunks).root();
+
+        let (dispenser, owner) = create_audit_dispenser(&chunks, chunks.iter().cloned().map(Some).collect(), data.len());
+        let stale_owner = create_test_providers()[0].addr;
+        assert_ne!(stale_owner, owner);
+        let store = create_metadata_store(commitment, data.len(), HashMap::from([(stale_owner, (0..TOTAL_SHARDS as u16).collect())])).await;
+        let dispenser = dispenser.with_metadata_store(Some(store));
+
+        let audit = dispenser.audit_chunk(commitment, 5).await.unwrap();
+        assert_eq!(audit.provider, owner);
+        assert!(audit.passed());
+    }
+
     #[tokio::test]
     async fn test_provider_selection() {
         let dispenser = create_test_dispenser().await;
@@ -462,6 +2179,53 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_provider_selection_is_exactly_stake_weighted() {
+        let dispenser = create_test_dispenser().await;
+        let providers = create_test_providers();
+        let total_stake: U256 = providers.iter().map(|p| p.stakedAmount).sum();
+
+        // every seed modulo the total stake is hit once, so each provider wins exactly its stake
+        let mut selections = HashMap::new();
+        for seed in 0..total_stake.to::<u64>() {
+            let provider = dispenser.select_provider_with_seed(seed, &providers, total_stake).unwrap();
+            *selections.entry(provider.name).or_insert(U256::ZERO) += U256::from(1);
+        }
+
+        for provider in &providers {
+            assert_eq!(selections[&provider.name], provider.stakedAmount);
+        }
+    }
+
+    // Provider (1-based index into create_test_providers) chosen for each chunk index of
+    // keccak256("test_commitment"). Changing assignment must update this table on purpose
+    const EXPECTED_ASSIGNMENTS: [usize; TOTAL_SHARDS] = [2, 1, 1, 3, 3, 3, 3, 3, 3, 3, 2, 2, 3, 2, 1, 3, 3, 2, 3, 1, 1, 3, 2, 2];
+
+    #[test]
+    fn test_selection_seed_scheme() {
+        let commitment = FixedBytes::<32>::from_slice(&Keccak256::digest("test_commitment"));
+        let total_stake = U256::from(600);
+
+        // (chunk index, seed, seed % total stake of create_test_providers)
+        for (chunk_index, seed, target) in [(0, 15953328615970604609u64, 209u64), (1, 7501767217172073695, 95), (23, 9506651337754828428, 228)] {
+            assert_eq!(selection_seed(&commitment, chunk_index), seed);
+            assert_eq!(U256::from(seed) % total_stake, U256::from(target));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_provider_selection_matches_recorded_assignments() {
+        let dispenser = create_test_dispenser().await;
+        let providers = create_test_providers();
+        let total_stake: U256 = providers.iter().map(|p| p.stakedAmount).sum();
+        let commitment = FixedBytes::<32>::from_slice(&Keccak256::digest("test_commitment"));
+
+        for (chunk_index, expected) in EXPECTED_ASSIGNMENTS.iter().enumerate() {
+            let provider = dispenser.select_provider_for_chunk(&commitment, chunk_index as u16, &providers, total_stake).unwrap();
+            assert_eq!(provider.name, providers[expected - 1].name, "chunk {}", chunk_index);
+        }
+    }
+
     #[tokio::test]
     async fn test_create_shards() {
         let dispenser = create_test_dispenser().await;
@@ -0,0 +1,95 @@
+use pod::FixedBytes;
+use warp::http::StatusCode;
+
+#[derive(Debug)]
+pub enum DispenserError {
+    DataTooSmall { size: usize, min: usize },
+    // the data handed to a resubmission does not encode to the given commitment
+    DataMismatch(FixedBytes<32>),
+    // the data encodes to a commitment that is already on-chain
+    AlreadySubmitted(FixedBytes<32>),
+    // no registered provider has stake, so chunks cannot be assigned
+    NoEligibleProviders,
+    NotRecoverable(FixedBytes<32>),
+    // the decoded data re-encodes to a different merkle root, some served chunks were wrong
+    ReconstructionMismatch(FixedBytes<32>),
+    InvalidChunkIndex { index: u16, total: usize },
+    // the chunk has no owner on-chain, so there is no provider to ask for it
+    ChunkNotAssigned { commitment: FixedBytes<32>, index: u16 },
+    // keccak256 of the reconstructed data differs from the hash the caller expected
+    HashMismatch { expected: FixedBytes<32>, actual: FixedBytes<32> },
+    // fewer chunks were stored or retrieved than are needed to recover the data
+    NotEnoughChunks { available: usize, required: usize },
+    Pod(anyhow::Error),
+    Internal(anyhow::Error),
+}
+
+impl DispenserError {
+    pub fn http_status(&self) -> StatusCode {
+        match self {
+            DispenserError::DataTooSmall { .. } | DispenserError::DataMismatch(_) | DispenserError::InvalidChunkIndex { .. } => StatusCode::BAD_REQUEST,
+            DispenserError::AlreadySubmitted(_) => StatusCode::CONFLICT,
+            DispenserError::NoEligibleProviders => StatusCode::SERVICE_UNAVAILABLE,
+            DispenserError::HashMismatch { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            DispenserError::NotRecoverable(_) | DispenserError::ChunkNotAssigned { .. } => StatusCode::NOT_FOUND,
+            DispenserError::ReconstructionMismatch(_) => StatusCode::BAD_GATEWAY,
+            DispenserError::NotEnoughChunks { .. } | DispenserError::Pod(_) | DispenserError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl std::fmt::Display for DispenserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DispenserError::DataTooSmall { size, min } => write!(f, "Data size is too small ({} bytes). Must be at least {} bytes", size, min),
+            DispenserError::DataMismatch(commitment) => write!(f, "Data does not match commitment {:?}", commitment),
+            DispenserError::AlreadySubmitted(commitment) => write!(f, "Data was already submitted under commitment {:?}", commitment),
+            DispenserError::NoEligibleProviders => write!(f, "No eligible providers with positive stake"),
+            DispenserError::NotRecoverable(commitment) => write!(f, "Commitment {:?} is not recoverable", commitment),
+            DispenserError::ReconstructionMismatch(commitment) => write!(f, "Reconstructed data does not match commitment {:?}", commitment),
+            DispenserError::InvalidChunkIndex { index, total } => write!(f, "Chunk index {} out of range (total chunks: {})", index, total),
+            DispenserError::ChunkNotAssigned { commitment, index } => write!(f, "Chunk {} of commitment {:?} is not assigned to any provider", index, commitment),
+            DispenserError::HashMismatch { expected, actual } => write!(f, "Retrieved data hash mismatch: expected {}, got {}", expected, actual),
+            DispenserError::NotEnoughChunks { available, required } => write!(f, "Not enough chunks available: {} of the {} required", available, required),
+            DispenserError::Pod(e) => write!(f, "Pod request failed: {}", e),
+            DispenserError::Internal(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for DispenserError {}
+
+impl From<anyhow::Error> for DispenserError {
+    fn from(e: anyhow::Error) -> Self {
+        DispenserError::Internal(e)
+    }
+}
+
+/// Why a provider did not hand over the chunks it was asked for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RetrieveError {
+    // the request never got an answer: connection refused, timed out or cut off
+    Unreachable(String),
+    // the provider holds none of the requested chunks
+    NotFound,
+    // any other non-success response
+    Status { status: u16, body: String },
+    // the provider answered with a body that is not a batch-retrieve response
+    Deserialization(String),
+    // the chunks served at these indices were not the requested chunk or failed their merkle proof
+    InvalidChunks(Vec<u16>),
+}
+
+impl std::fmt::Display for RetrieveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RetrieveError::Unreachable(e) => write!(f, "Provider unreachable: {}", e),
+            RetrieveError::NotFound => write!(f, "Provider has none of the requested chunks"),
+            RetrieveError::Status { status, body } => write!(f, "Provider answered with status {}: {}", status, body),
+            RetrieveError::Deserialization(e) => write!(f, "Invalid response from provider: {}", e),
+            RetrieveError::InvalidChunks(indices) => write!(f, "Provider served invalid chunks at indices {:?}", indices),
+        }
+    }
+}
+
+impl std::error::Error for RetrieveError {}
@@ -1,118 +1,419 @@
 use std::convert::Infallible;
+use std::str::FromStr;
 use std::sync::Arc;
-use pod::FixedBytes;
+use std::time::Duration;
+use pod::{Address, FixedBytes};
 use warp::Filter;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use anyhow::Result;
-use crate::dispenser::Dispenser;
+use crate::{dispenser::{Dispenser, RetrievalStrategy}, error::DispenserError, metrics::DispenserMetrics};
+use prometheus::Registry;
 use pod::client::PodaClientTrait;
-use common::log::info;
+use common::{constants::{REQUIRED_SHARDS, TOTAL_SHARDS}, http::shared_client, log::{info, warn}};
+use storage_provider::http::{RecoverRequest, StoreReceipt, StoreRequest};
+use warp::Reply;
+use futures::future::join_all;
+
+/// How the `data` string of a `SubmitDataRequest` is encoded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataEncoding {
+    #[default]
+    Base64,
+    Hex,
+    // the string's own UTF-8 bytes
+    Raw,
+}
+
+/// Submitted data, either an encoded string or the original array of byte values
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SubmitData {
+    Encoded(String),
+    Bytes(Vec<u8>),
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SubmitDataRequest {
-    pub data: Vec<u8>,
+    pub data: SubmitData,
+    // ignored when `data` is an array
+    #[serde(default)]
+    pub encoding: DataEncoding,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl SubmitDataRequest {
+    pub fn new(data: &[u8], encoding: DataEncoding) -> Self {
+        let data = match encoding {
+            DataEncoding::Base64 => SubmitData::Encoded(BASE64.encode(data)),
+            DataEncoding::Hex => SubmitData::Encoded(hex::encode(data)),
+            // bytes that are not valid UTF-8 fall back to the array form, which decodes the same way
+            DataEncoding::Raw => match std::str::from_utf8(data) {
+                Ok(data) => SubmitData::Encoded(data.to_string()),
+                Err(_) => SubmitData::Bytes(data.to_vec()),
+            },
+        };
+        Self { data, encoding }
+    }
+
+    pub fn decode(&self) -> Result<Vec<u8>> {
+        match (&self.data, self.encoding) {
+            (SubmitData::Bytes(bytes), _) => Ok(bytes.clone()),
+            (SubmitData::Encoded(data), DataEncoding::Base64) => Ok(BASE64.decode(data)?),
+            (SubmitData::Encoded(data), DataEncoding::Hex) => Ok(hex::decode(data.strip_prefix("0x").unwrap_or(data))?),
+            (SubmitData::Encoded(data), DataEncoding::Raw) => Ok(data.as_bytes().to_vec()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct SubmitDataResponse {
     pub success: bool,
     pub message: String,
     pub commitment: FixedBytes<32>,
-    pub assignments: std::collections::HashMap<String, Vec<u16>>,
+    // chunk indices per provider address
+    pub assignments: std::collections::HashMap<Address, Vec<u16>>,
+    pub total_chunks: usize,
+    pub required_chunks: usize,
+    pub promised_chunks: usize,
+    // receipts signed by the providers for the chunks they stored
+    #[serde(default)]
+    pub receipts: Vec<StoreReceipt>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RetrieveDataRequest {
     pub commitment: FixedBytes<32>,
+    // keccak256 of the original data, checked against the reconstructed payload when set
+    #[serde(default)]
+    pub expected_hash: Option<FixedBytes<32>>,
+    // which providers to ask first and whether to stop once enough chunks are in; all of them otherwise
+    #[serde(default)]
+    pub strategy: Option<RetrievalStrategy>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct RetrieveDataResponse {
     pub success: bool,
     pub message: String,
     pub data: Option<Vec<u8>>,
+    // retrieval details, left at their defaults when the retrieval failed
+    #[serde(default)]
+    pub bytes: usize,
+    #[serde(default)]
+    pub total_shards: usize,
+    #[serde(default)]
+    pub required_shards: usize,
+    #[serde(default)]
+    pub shards_used: usize,
+    #[serde(default)]
+    pub shards_missing: Vec<u16>,
+    #[serde(default)]
+    pub providers_responded: Vec<String>,
+    #[serde(default)]
+    pub providers_failed: Vec<ProviderFailure>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderFailure {
+    pub provider: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EstimateRequest {
+    // length in bytes of the data that would be submitted
+    pub size: usize,
+}
+
+/// What a submission of `EstimateRequest::size` bytes would produce, see `Dispenser::estimate`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EstimateResponse {
+    pub total_chunks: usize,
+    pub required_chunks: usize,
+    pub per_provider: std::collections::HashMap<Address, usize>,
+    pub total_stored_bytes: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditRequest {
+    pub commitment: FixedBytes<32>,
+    pub chunk_id: u16,
+}
+
+/// Result of `Dispenser::audit_chunk` for the provider holding the chunk
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditResponse {
+    pub commitment: FixedBytes<32>,
+    pub chunk_id: u16,
+    pub provider: Address,
+    pub retrieved: bool,
+    pub merkle_valid: bool,
+    pub kzg_valid: bool,
+    pub passed: bool,
+}
+
+/// Limits and coding parameters clients can check a submission against before sending it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigResponse {
+    pub min_data_size: usize,
+    // cap on the JSON body of /submit, whose data is base64 encoded by default
+    pub max_body_bytes: u64,
+    // smaller payloads are zero-padded up to this size before erasure coding
+    pub min_payload_size: usize,
+    pub total_shards: usize,
+    pub required_shards: usize,
+}
+
+/// On-chain state of a commitment, read without fetching any chunks
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatusResponse {
+    pub exists: bool,
+    pub recoverable: bool,
+    pub available_chunks: u16,
+    pub total_chunks: u16,
+    pub required_chunks: u16,
+    pub size: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HealthResponse {
     status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
 }
 
+// How long /ready waits on the providers' /health, which it probes concurrently
+const PROVIDER_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Default cap on /submit bodies, which carry the payload itself
+pub const DEFAULT_MAX_BODY_BYTES: u64 = 256 * 1024 * 1024;
+// Cap on bodies of the control endpoints, which only carry commitments and hashes
+pub const CONTROL_MAX_BODY_BYTES: u64 = 16 * 1024;
+
 pub async fn start_server<T: PodaClientTrait + Send + Sync + 'static>(
     dispenser: Arc<Dispenser<T>>,
     port: u16,
+    max_body_bytes: u64,
+    registry: Registry,
 ) {
+    info!("🦀 Rust Dispenser API starting on port {}", port);
+    warp::serve(routes(dispenser, max_body_bytes, registry)).run(([0, 0, 0, 0], port)).await;
+}
+
+// JSON body that is rejected with 413 Payload Too Large past `limit` bytes
+fn json_body<B: DeserializeOwned + Send>(limit: u64) -> impl Filter<Extract = (B,), Error = warp::Rejection> + Clone {
+    warp::body::content_length_limit(limit).and(warp::body::json())
+}
+
+fn routes<T: PodaClientTrait + Send + Sync + 'static>(
+    dispenser: Arc<Dispenser<T>>,
+    max_body_bytes: u64,
+    registry: Registry,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let metrics = Arc::new(DispenserMetrics::register(registry).expect("dispenser metrics are registered once per registry"));
+    let request_metrics = metrics.clone();
     let dispenser_filter = warp::any().map(move || dispenser.clone());
+    let control_body_bytes = CONTROL_MAX_BODY_BYTES.min(max_body_bytes);
 
     // POST /submit - Submit data for storage
     let submit = warp::path("submit")
         .and(warp::post())
-        .and(warp::body::json())
+        .and(json_body(max_body_bytes))
         .and(dispenser_filter.clone())
         .and_then(handle_submit_data);
 
     // POST /retrieve - Retrieve data
     let retrieve = warp::path("retrieve")
         .and(warp::post())
-        .and(warp::body::json())
+        .and(json_body(control_body_bytes))
         .and(dispenser_filter.clone())
         .and_then(handle_retrieve_data);
 
+    // POST /reconstruct - Rebuild a single chunk with its proofs for a provider that lost it
+    let reconstruct = warp::path("reconstruct")
+        .and(warp::post())
+        .and(json_body(control_body_bytes))
+        .and(dispenser_filter.clone())
+        .and_then(handle_reconstruct);
+
+    // POST /estimate - Chunk count, placement and stored size of a submission, without submitting
+    let estimate = warp::path("estimate")
+        .and(warp::post())
+        .and(json_body(control_body_bytes))
+        .and(dispenser_filter.clone())
+        .and_then(handle_estimate);
+
+    // POST /audit - Fetch a chunk from the provider holding it and check it against its commitment
+    let audit = warp::path("audit")
+        .and(warp::post())
+        .and(json_body(control_body_bytes))
+        .and(dispenser_filter.clone())
+        .and_then(handle_audit);
+
+    // GET /status/{commitment} - Whether a commitment is recoverable yet, without retrieving it
+    let status = warp::path!("status" / String)
+        .and(warp::get())
+        .and(dispenser_filter.clone())
+        .and_then(handle_status);
+
+    // GET /placement/{commitment} - Chunk ids each provider holds for a commitment, keyed by address
+    let placement = warp::path!("placement" / String)
+        .and(warp::get())
+        .and(dispenser_filter.clone())
+        .and_then(handle_placement);
+
+    // GET /assignment/{commitment} - Chunk ids this dispenser assigned to each provider, keyed by address
+    let assignment = warp::path!("assignment" / String)
+        .and(warp::get())
+        .and(dispenser_filter.clone())
+        .and_then(handle_assignment);
+
+    // GET /config - Size limits and coding parameters
+    let config = warp::path("config")
+        .and(warp::get())
+        .and(dispenser_filter.clone())
+        .map(move |dispenser: Arc<Dispenser<T>>| warp::reply::json(&ConfigResponse {
+            min_data_size: dispenser.min_data_size(),
+            max_body_bytes,
+            min_payload_size: dispenser.min_payload_size(),
+            total_shards: TOTAL_SHARDS,
+            required_shards: REQUIRED_SHARDS,
+        }));
+
     // GET /health - Health check
     let health_check = warp::path("health")
         .and(warp::get())
         .and_then(handle_health_check);
 
-    let routes = submit
+    // GET /ready - Readiness check that probes the RPC node and the storage providers
+    let ready = warp::path("ready")
+        .and(warp::get())
+        .and(dispenser_filter.clone())
+        .and_then(handle_ready);
+
+    // GET /metrics - Prometheus text format counters
+    let metrics = warp::path("metrics")
+        .and(warp::get())
+        .map(move || warp::reply::with_header(metrics.encode(), "content-type", prometheus::TEXT_FORMAT));
+
+    submit
         .or(retrieve)
+        .or(reconstruct)
+        .or(estimate)
+        .or(audit)
+        .or(status)
+        .or(placement)
+        .or(assignment)
+        .or(config)
         .or(health_check)
-        .with(warp::cors().allow_any_origin());
-
-    info!("🦀 Rust Dispenser API starting on port {}", port);
-    warp::serve(routes).run(([0, 0, 0, 0], port)).await;
+        .or(ready)
+        .or(metrics)
+        .with(warp::log::custom(move |info| request_metrics.observe_request(info.path(), info.status().as_u16(), info.elapsed())))
+        .with(warp::cors().allow_any_origin())
 }
 
 async fn handle_health_check() -> Result<impl warp::Reply, Infallible> {
     Ok(warp::reply::with_status(
         warp::reply::json(&HealthResponse {
             status: "ok".to_string(),
+            reason: None,
         }),
         warp::http::StatusCode::OK,
     ))
 }
 
+async fn handle_ready<T: PodaClientTrait>(dispenser: Arc<Dispenser<T>>) -> Result<impl warp::Reply, Infallible> {
+    let reason = match unready_reason(&dispenser).await {
+        None => return Ok(warp::reply::with_status(
+            warp::reply::json(&HealthResponse { status: "ok".to_string(), reason: None }),
+            warp::http::StatusCode::OK,
+        )),
+        Some(reason) => reason,
+    };
+
+    warn!(reason, "Dispenser is not ready");
+    Ok(warp::reply::with_status(
+        warp::reply::json(&HealthResponse { status: "unavailable".to_string(), reason: Some(reason) }),
+        warp::http::StatusCode::SERVICE_UNAVAILABLE,
+    ))
+}
+
+// Ready once the RPC node answers and at least one eligible provider passes its health check
+async fn unready_reason<T: PodaClientTrait>(dispenser: &Dispenser<T>) -> Option<String> {
+    let providers = match dispenser.pod.get_eligible_providers().await {
+        Ok(providers) => providers,
+        Err(e) => return Some(format!("Pod RPC is unreachable: {}", e)),
+    };
+    if providers.is_empty() {
+        return Some("No eligible providers".to_string());
+    }
+
+    let client = shared_client();
+    let probes = providers.iter().map(|provider| async {
+        let response = client.get(format!("{}/health", provider.url)).timeout(PROVIDER_PROBE_TIMEOUT).send().await;
+        response.is_ok_and(|response| response.status().is_success())
+    });
+    if join_all(probes).await.into_iter().any(|healthy| healthy) {
+        return None;
+    }
+
+    Some(format!("None of the {} eligible providers is reachable", providers.len()))
+}
+
 async fn handle_submit_data<T: PodaClientTrait>(
     request: SubmitDataRequest,
     dispenser: Arc<Dispenser<T>>,
 ) -> Result<impl warp::Reply, Infallible> {
-    match dispenser.submit_data(&request.data).await {
-        Ok((commitment, assignments)) => {
+    let data = match request.decode() {
+        Ok(data) => data,
+        Err(e) => return Ok(warp::reply::with_status(
+            warp::reply::json(&SubmitDataResponse {
+                success: false,
+                message: format!("Failed to decode {:?} data: {}", request.encoding, e),
+                ..Default::default()
+            }),
+            warp::http::StatusCode::BAD_REQUEST,
+        )),
+    };
+
+    match dispenser.submit_data(&data).await {
+        Ok(result) => {
             // Convert assignments to a simpler format for JSON serialization
             let mut assignments_json = std::collections::HashMap::new();
-            for (provider_name, chunks) in assignments {
+            for (provider_addr, chunks) in result.assignments {
                 let indices: Vec<u16> = chunks.iter().map(|c| c.index).collect();
-                assignments_json.insert(provider_name, indices);
+                assignments_json.insert(provider_addr, indices);
             }
 
             Ok(warp::reply::with_status(
                 warp::reply::json(&SubmitDataResponse {
                     success: true,
                     message: "Data submitted successfully".to_string(),
-                    commitment,
+                    commitment: result.commitment,
                     assignments: assignments_json,
+                    total_chunks: result.total_chunks,
+                    required_chunks: result.required_chunks,
+                    promised_chunks: result.promised_chunks,
+                    receipts: result.receipts,
                 }),
                 warp::http::StatusCode::OK,
             ))
         }
         Err(e) => {
+            // a resubmission still tells the client where its data lives
+            let commitment = match e {
+                DispenserError::AlreadySubmitted(commitment) => commitment,
+                _ => FixedBytes::ZERO,
+            };
             Ok(warp::reply::with_status(
                 warp::reply::json(&SubmitDataResponse {
                     success: false,
-                    message: format!("Failed to submit data: {:?}", e),
-                    commitment: FixedBytes::default(),
-                    assignments: std::collections::HashMap::new(),
+                    message: format!("Failed to submit data: {}", e),
+                    commitment,
+                    ..Default::default()
                 }),
-                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+                e.http_status(),
             ))
         }
     }
@@ -122,13 +423,22 @@ async fn handle_retrieve_data<T: PodaClientTrait>(
     request: RetrieveDataRequest,
     dispenser: Arc<Dispenser<T>>,
 ) -> Result<impl warp::Reply, Infallible> {
-    match dispenser.retrieve_data(request.commitment).await {
-        Ok(data) => {
+    match dispenser.retrieve_data_detailed(request.commitment, request.expected_hash, request.strategy.as_ref()).await {
+        Ok(report) => {
             Ok(warp::reply::with_status(
                 warp::reply::json(&RetrieveDataResponse {
                     success: true,
                     message: "Data retrieved successfully".to_string(),
-                    data: Some(data),
+                    data: Some(report.data),
+                    bytes: report.bytes,
+                    total_shards: report.total_shards,
+                    required_shards: report.required_shards,
+                    shards_used: report.shards_used,
+                    shards_missing: report.shards_missing,
+                    providers_responded: report.providers_responded,
+                    providers_failed: report.providers_failed.into_iter()
+                        .map(|(provider, reason)| ProviderFailure { provider, reason: reason.to_string() })
+                        .collect(),
                 }),
                 warp::http::StatusCode::OK,
             ))
@@ -137,11 +447,477 @@ async fn handle_retrieve_data<T: PodaClientTrait>(
             Ok(warp::reply::with_status(
                 warp::reply::json(&RetrieveDataResponse {
                     success: false,
-                    message: format!("Failed to retrieve data: {:?}", e),
-                    data: None,
+                    message: format!("Failed to retrieve data: {}", e),
+                    ..Default::default()
                 }),
-                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+                e.http_status(),
             ))
         }
     }
 }
+
+async fn handle_reconstruct<T: PodaClientTrait>(
+    request: RecoverRequest,
+    dispenser: Arc<Dispenser<T>>,
+) -> Result<warp::reply::Response, Infallible> {
+    match dispenser.reconstruct_chunk(request.commitment, request.index).await {
+        Ok((chunk, merkle_proof, kzg_proof)) => Ok(warp::reply::json(&StoreRequest {
+            commitment: request.commitment,
+            chunk,
+            kzg_proof,
+            merkle_proof,
+        }).into_response()),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"success": false, "message": format!("Failed to reconstruct chunk: {}", e)})),
+            e.http_status(),
+        ).into_response()),
+    }
+}
+
+async fn handle_estimate<T: PodaClientTrait>(
+    request: EstimateRequest,
+    dispenser: Arc<Dispenser<T>>,
+) -> Result<warp::reply::Response, Infallible> {
+    match dispenser.estimate(request.size).await {
+        Ok(estimate) => Ok(warp::reply::json(&EstimateResponse {
+            total_chunks: estimate.total_chunks,
+            required_chunks: estimate.required_chunks,
+            per_provider: estimate.per_provider,
+            total_stored_bytes: estimate.total_stored_bytes,
+        }).into_response()),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"success": false, "message": format!("Failed to estimate submission: {}", e)})),
+            e.http_status(),
+        ).into_response()),
+    }
+}
+
+async fn handle_audit<T: PodaClientTrait>(
+    request: AuditRequest,
+    dispenser: Arc<Dispenser<T>>,
+) -> Result<warp::reply::Response, Infallible> {
+    match dispenser.audit_chunk(request.commitment, request.chunk_id).await {
+        Ok(audit) => Ok(warp::reply::json(&AuditResponse {
+            commitment: audit.commitment,
+            chunk_id: audit.chunk_id,
+            provider: audit.provider,
+            retrieved: audit.retrieved,
+            merkle_valid: audit.merkle_valid,
+            kzg_valid: audit.kzg_valid,
+            passed: audit.passed(),
+        }).into_response()),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"success": false, "message": format!("Failed to audit chunk: {}", e)})),
+            e.http_status(),
+        ).into_response()),
+    }
+}
+
+async fn handle_status<T: PodaClientTrait>(
+    commitment: String,
+    dispenser: Arc<Dispenser<T>>,
+) -> Result<warp::reply::Response, Infallible> {
+    let Ok(commitment) = FixedBytes::<32>::from_str(&commitment) else {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"success": false, "message": "Invalid commitment format"})),
+            warp::http::StatusCode::BAD_REQUEST,
+        ).into_response());
+    };
+
+    match dispenser.pod.get_commitment_info(commitment).await {
+        Ok((info, recoverable)) => Ok(warp::reply::json(&StatusResponse {
+            // unknown commitments come back zeroed
+            exists: info.timestamp != 0,
+            recoverable,
+            available_chunks: info.availableChunks,
+            total_chunks: info.totalChunks,
+            required_chunks: info.requiredChunks,
+            size: info.size,
+        }).into_response()),
+        Err(e) => {
+            let e = DispenserError::Pod(e);
+            Ok(warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({"success": false, "message": format!("Failed to get commitment status: {}", e)})),
+                e.http_status(),
+            ).into_response())
+        }
+    }
+}
+
+async fn handle_placement<T: PodaClientTrait>(
+    commitment: String,
+    dispenser: Arc<Dispenser<T>>,
+) -> Result<warp::reply::Response, Infallible> {
+    let Ok(commitment) = FixedBytes::<32>::from_str(&commitment) else {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"success": false, "message": "Invalid commitment format"})),
+            warp::http::StatusCode::BAD_REQUEST,
+        ).into_response());
+    };
+
+    match dispenser.placement(commitment).await {
+        Ok(placement) => {
+            let placement = placement.into_iter().map(|(provider, chunk_ids)| (provider.addr, chunk_ids)).collect::<std::collections::HashMap<_, _>>();
+            Ok(warp::reply::json(&placement).into_response())
+        }
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"success": false, "message": format!("Failed to get chunk placement: {}", e)})),
+            e.http_status(),
+        ).into_response()),
+    }
+}
+
+async fn handle_assignment<T: PodaClientTrait>(
+    commitment: String,
+    dispenser: Arc<Dispenser<T>>,
+) -> Result<warp::reply::Response, Infallible> {
+    let Ok(commitment) = FixedBytes::<32>::from_str(&commitment) else {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"success": false, "message": "Invalid commitment format"})),
+            warp::http::StatusCode::BAD_REQUEST,
+        ).into_response());
+    };
+
+    match dispenser.recorded_assignment(commitment).await {
+        Some(assignment) => Ok(warp::reply::json(&assignment).into_response()),
+        None => Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"success": false, "message": "No assignment recorded for commitment"})),
+            warp::http::StatusCode::NOT_FOUND,
+        ).into_response()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pod::{client::{Commitment, MockPodaClientTrait, ProviderInfo}, Bytes};
+
+    #[tokio::test]
+    async fn test_submit_undersized_data_is_bad_request() {
+        let dispenser = Arc::new(Dispenser::new(MockPodaClientTrait::new()));
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/submit")
+            .json(&SubmitDataRequest::new(&[1, 2, 3], DataEncoding::Base64))
+            .reply(&routes(dispenser, DEFAULT_MAX_BODY_BYTES, Registry::new()))
+            .await;
+
+        assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+        let body: SubmitDataResponse = serde_json::from_slice(response.body()).unwrap();
+        assert!(!body.success);
+        assert!(body.message.contains("too small"));
+    }
+
+    #[tokio::test]
+    async fn test_configured_min_data_size() {
+        let dispenser = Arc::new(Dispenser::new(MockPodaClientTrait::new()).with_min_data_size(32));
+        let routes = routes(dispenser, DEFAULT_MAX_BODY_BYTES, Registry::new());
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/submit")
+            .json(&SubmitDataRequest::new(&[7; 24], DataEncoding::Base64))
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+        let body: SubmitDataResponse = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body.message, "Failed to submit data: Data size is too small (24 bytes). Must be at least 32 bytes");
+
+        let response = warp::test::request().method("GET").path("/config").reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+        let config: ConfigResponse = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(config.min_data_size, 32);
+        assert_eq!(config.max_body_bytes, DEFAULT_MAX_BODY_BYTES);
+        assert_eq!((config.total_shards, config.required_shards), (TOTAL_SHARDS, REQUIRED_SHARDS));
+    }
+
+    #[tokio::test]
+    async fn test_base64_submit_round_trip() {
+        // arbitrary binary bytes, including ones that are not valid UTF-8
+        let blob = (0..4096u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8).collect::<Vec<_>>();
+        let expected_root = merkle_tree::gen_merkle_tree(&Dispenser::new(MockPodaClientTrait::new()).encode_payload(&blob)).root();
+
+        let base64 = serde_json::to_vec(&SubmitDataRequest::new(&blob, DataEncoding::Base64)).unwrap();
+        let array = serde_json::to_vec(&SubmitDataRequest { data: SubmitData::Bytes(blob.clone()), encoding: DataEncoding::default() }).unwrap();
+        assert!(base64.len() < array.len() / 2, "base64 body {} vs array body {}", base64.len(), array.len());
+        let hex = SubmitDataRequest::new(&blob, DataEncoding::Hex);
+        assert_eq!(hex.decode().unwrap(), blob);
+        let raw = SubmitDataRequest::new(b"plain text", DataEncoding::Raw);
+        assert!(matches!(&raw.data, SubmitData::Encoded(text) if text == "plain text"));
+        assert_eq!(raw.decode().unwrap(), b"plain text");
+        assert_eq!(SubmitDataRequest::new(&blob, DataEncoding::Raw).decode().unwrap(), blob);
+
+        let provider = ProviderInfo {
+            name: "Provider".to_string(),
+            url: "http://127.0.0.1:1".to_string(),
+            addr: pod::Address::with_last_byte(1),
+            registeredAt: 0,
+            challengeCount: 0,
+            challengeSuccessCount: 0,
+            active: true,
+            stakedAmount: pod::U256::from(100),
+        };
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_eligible_providers().returning(move || Ok(vec![provider.clone()]));
+        // the commitment is the merkle root of the decoded data, so matching it means the bytes survived
+        let size = blob.len() as u32;
+        pod.expect_submit_commitment()
+            .withf(move |commitment, data_size, _, _, _| *commitment == expected_root && *data_size == size)
+            .times(1)
+            .returning(|_, _, _, _, _| Err(anyhow::anyhow!("stop after the commitment")));
+        pod.expect_commitment_exists().returning(|_| Ok(false));
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/submit")
+            .body(base64)
+            .header("content-type", "application/json")
+            .reply(&routes(Arc::new(Dispenser::new(pod)), DEFAULT_MAX_BODY_BYTES, Registry::new()))
+            .await;
+        // a failed on-chain submission is a server-side error, not a conflict
+        assert_eq!(response.status(), warp::http::StatusCode::INTERNAL_SERVER_ERROR);
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/submit")
+            .json(&serde_json::json!({ "data": "not base64!", "encoding": "base64" }))
+            .reply(&routes(Arc::new(Dispenser::new(MockPodaClientTrait::new())), DEFAULT_MAX_BODY_BYTES, Registry::new()))
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_unrecoverable_commitment_is_not_found() {
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(|_| Ok((Commitment {
+            size: 0,
+            timestamp: 0,
+            totalChunks: 0,
+            requiredChunks: 0,
+            availableChunks: 0,
+            kzgCommitment: Bytes::new(),
+        }, false)));
+        let dispenser = Arc::new(Dispenser::new(pod));
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/retrieve")
+            .json(&RetrieveDataRequest { commitment: FixedBytes::from([7u8; 32]), expected_hash: None, strategy: None })
+            .reply(&routes(dispenser, DEFAULT_MAX_BODY_BYTES, Registry::new()))
+            .await;
+
+        assert_eq!(response.status(), warp::http::StatusCode::NOT_FOUND);
+        let body: RetrieveDataResponse = serde_json::from_slice(response.body()).unwrap();
+        assert!(body.message.contains("not recoverable"));
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_commitment_info() {
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(|_| Ok((Commitment {
+            size: 4096,
+            timestamp: 1_700_000_000,
+            totalChunks: TOTAL_SHARDS as u16,
+            requiredChunks: REQUIRED_SHARDS as u16,
+            availableChunks: 20,
+            kzgCommitment: Bytes::new(),
+        }, true)));
+        let routes = routes(Arc::new(Dispenser::new(pod)), DEFAULT_MAX_BODY_BYTES, Registry::new());
+
+        let response = warp::test::request()
+            .method("GET")
+            .path(&format!("/status/{}", FixedBytes::from([7u8; 32])))
+            .reply(&routes)
+            .await;
+
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body, serde_json::json!({
+            "exists": true,
+            "recoverable": true,
+            "available_chunks": 20,
+            "total_chunks": TOTAL_SHARDS,
+            "required_chunks": REQUIRED_SHARDS,
+            "size": 4096,
+        }));
+
+        let response = warp::test::request().method("GET").path("/status/not-a-commitment").reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_estimate_does_not_submit() {
+        let provider = ProviderInfo {
+            name: "Provider".to_string(),
+            url: "http://127.0.0.1:1".to_string(),
+            addr: pod::Address::with_last_byte(1),
+            registeredAt: 0,
+            challengeCount: 0,
+            challengeSuccessCount: 0,
+            active: true,
+            stakedAmount: pod::U256::from(100),
+        };
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_eligible_providers().returning(move || Ok(vec![provider.clone()]));
+        pod.expect_submit_commitment().never();
+        let routes = routes(Arc::new(Dispenser::new(pod)), DEFAULT_MAX_BODY_BYTES, Registry::new());
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/estimate")
+            .json(&EstimateRequest { size: 4096 })
+            .reply(&routes)
+            .await;
+
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+        let body: EstimateResponse = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body.total_chunks, TOTAL_SHARDS);
+        assert_eq!(body.per_provider[&pod::Address::with_last_byte(1)], TOTAL_SHARDS);
+        assert_eq!(body.total_stored_bytes, 4096 / REQUIRED_SHARDS * TOTAL_SHARDS);
+
+        let response = warp::test::request().method("POST").path("/estimate").json(&EstimateRequest { size: 1 }).reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_placement_matches_the_stored_assignment() {
+        let providers = (1..=3u8).map(|byte| ProviderInfo {
+            name: format!("Provider {}", byte),
+            url: format!("http://127.0.0.1:{}", byte),
+            addr: pod::Address::with_last_byte(byte),
+            registeredAt: 0,
+            challengeCount: 0,
+            challengeSuccessCount: 0,
+            active: true,
+            stakedAmount: pod::U256::from(100 * byte as u64),
+        }).collect::<Vec<_>>();
+
+        let stored = Arc::new(std::sync::Mutex::new(std::collections::HashMap::<Address, Vec<u16>>::new()));
+        let recorded = stored.clone();
+        let mut transport = crate::transport::MockProviderTransport::new();
+        transport.expect_version().returning(|_| Err(anyhow::anyhow!("no version")));
+        transport.expect_capabilities().returning(|_| Err(anyhow::anyhow!("no capabilities")));
+        transport.expect_batch_store().returning(move |provider, request| {
+            recorded.lock().unwrap().entry(provider.addr).or_default().extend(request.chunks.iter().map(|c| c.index));
+            Ok(None)
+        });
+
+        let mut pod = MockPodaClientTrait::new();
+        let eligible = providers.clone();
+        pod.expect_get_eligible_providers().returning(move || Ok(eligible.clone()));
+        // a registered provider that was assigned nothing is left out of the placement
+        let mut registered = providers.clone();
+        registered.push(ProviderInfo { addr: pod::Address::with_last_byte(4), stakedAmount: pod::U256::ZERO, ..providers[0].clone() });
+        pod.expect_get_providers().returning(move || Ok(registered.clone()));
+        pod.expect_commitment_exists().returning(|_| Ok(false));
+        pod.expect_submit_commitment().returning(|_, _, _, _, _| Ok(()));
+        pod.expect_wait_for_availability().returning(|_, _| Ok(()));
+        let held = stored.clone();
+        pod.expect_get_provider_chunks().returning(move |_, addr| Ok(held.lock().unwrap().get(&addr).cloned().unwrap_or_default()));
+        let metadata = Arc::new(crate::metadata::InMemoryMetadataStore::default());
+        let dispenser = Arc::new(Dispenser::new(pod).with_transport(Arc::new(transport)).with_metadata_store(Some(metadata)));
+
+        let result = dispenser.submit_data("Data whose chunks are looked up by provider".repeat(100).as_bytes()).await.unwrap();
+        let routes = routes(dispenser, DEFAULT_MAX_BODY_BYTES, Registry::new());
+
+        let response = warp::test::request().path(&format!("/placement/{}", result.commitment)).reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+        let placement: std::collections::HashMap<Address, Vec<u16>> = serde_json::from_slice(response.body()).unwrap();
+        let assigned = result.assignments.iter()
+            .map(|(addr, chunks)| (*addr, chunks.iter().map(|c| c.index).collect::<Vec<_>>()))
+            .collect::<std::collections::HashMap<_, _>>();
+        assert_eq!(placement, assigned);
+
+        // the recorded assignment agrees while nothing has moved, and is unknown for other commitments
+        let response = warp::test::request().path(&format!("/assignment/{}", result.commitment)).reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+        let recorded: std::collections::HashMap<Address, Vec<u16>> = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(recorded, assigned);
+        let response = warp::test::request().path(&format!("/assignment/{}", FixedBytes::<32>::from([7u8; 32]))).reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::NOT_FOUND);
+
+        let response = warp::test::request().path("/placement/not-a-commitment").reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_reconstruct_out_of_range_chunk_is_bad_request() {
+        let dispenser = Arc::new(Dispenser::new(MockPodaClientTrait::new()));
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/reconstruct")
+            .json(&RecoverRequest { commitment: FixedBytes::from([7u8; 32]), index: TOTAL_SHARDS as u16 })
+            .reply(&routes(dispenser, DEFAULT_MAX_BODY_BYTES, Registry::new()))
+            .await;
+
+        assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert!(body["message"].as_str().unwrap().contains("out of range"));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_count_operations() {
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(|_| Err(anyhow::anyhow!("rpc unavailable")));
+        let routes = routes(Arc::new(Dispenser::new(pod)), DEFAULT_MAX_BODY_BYTES, Registry::new());
+
+        for _ in 0..2 {
+            let response = warp::test::request()
+                .method("POST")
+                .path("/submit")
+                .json(&SubmitDataRequest::new(&[1, 2, 3], DataEncoding::Base64))
+                .reply(&routes)
+                .await;
+            assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+        }
+        let response = warp::test::request()
+            .method("POST")
+            .path("/retrieve")
+            .json(&RetrieveDataRequest { commitment: FixedBytes::from([7u8; 32]), expected_hash: None, strategy: None })
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::INTERNAL_SERVER_ERROR);
+
+        let response = warp::test::request().method("GET").path("/metrics").reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+        let body = String::from_utf8(response.body().to_vec()).unwrap();
+        for line in [
+            "poda_dispenser_requests_total{operation=\"submit\"} 2",
+            "poda_dispenser_request_failures_total{operation=\"submit\"} 2",
+            "poda_dispenser_requests_total{operation=\"retrieve\"} 1",
+            "poda_dispenser_request_failures_total{operation=\"retrieve\"} 1",
+            "poda_dispenser_request_duration_seconds_count{operation=\"submit\"} 2",
+            "poda_dispenser_request_duration_seconds_count{operation=\"retrieve\"} 1",
+        ] {
+            assert!(body.lines().any(|l| l == line), "missing {:?} in:\n{}", line, body);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ready_requires_a_reachable_provider() {
+        let unreachable = ProviderInfo {
+            name: "Unreachable".to_string(),
+            url: "http://127.0.0.1:1".to_string(),
+            addr: pod::Address::with_last_byte(1),
+            registeredAt: 0,
+            challengeCount: 0,
+            challengeSuccessCount: 0,
+            active: true,
+            stakedAmount: pod::U256::from(100),
+        };
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_eligible_providers().returning(move || Ok(vec![unreachable.clone()]));
+
+        let response = warp::test::request()
+            .method("GET")
+            .path("/ready")
+            .reply(&routes(Arc::new(Dispenser::new(pod)), DEFAULT_MAX_BODY_BYTES, Registry::new()))
+            .await;
+
+        assert_eq!(response.status(), warp::http::StatusCode::SERVICE_UNAVAILABLE);
+        let body: HealthResponse = serde_json::from_slice(response.body()).unwrap();
+        assert!(body.reason.unwrap().contains("reachable"));
+    }
+}
@@ -1,2 +1,8 @@
 pub mod http;
-pub mod dispenser;
\ No newline at end of file
+pub mod dispenser;
+pub mod bundle;
+pub mod coding;
+pub mod error;
+pub mod metadata;
+pub mod metrics;
+pub mod transport;
\ No newline at end of file
@@ -1,14 +1,28 @@
-mod dispenser;
-mod http;
 use std::{str::FromStr, sync::Arc};
 
-use http::start_server;
-use dispenser::Dispenser;
-use pod::{client::{PodaClient}, Address, PrivateKeySigner};
+use dispencer::http::{start_server, DEFAULT_MAX_BODY_BYTES};
+use dispencer::dispenser::{Dispenser, DEFAULT_MIN_DATA_SIZE, DEFAULT_MIN_PAYLOAD_SIZE};
+use dispencer::metadata::{InMemoryMetadataStore, MetadataStore};
+use pod::{client::{PodaClient, DEFAULT_AVAILABILITY_CONFIRMATIONS}, Address, PrivateKeySigner};
 use dotenv::dotenv;
-use common::log::{init_logging, info};
+use prometheus::Registry;
+use common::{constants::TOTAL_SHARDS, http::{init_shared_client, HttpTimeouts}, log::{init_logging, info}};
 
-fn load_config() -> (String, Address, u16, String) {
+struct Config {
+    rpc_url: String,
+    poda_address: Address,
+    port: u16,
+    private_key: String,
+    min_data_size: usize,
+    min_payload_size: usize,
+    max_body_bytes: u64,
+    availability_confirmations: u64,
+    send_multiproof: bool,
+    record_metadata: bool,
+    kzg_ceremony_path: Option<String>,
+}
+
+fn load_config() -> Config {
     dotenv().ok();
     init_logging();
 
@@ -16,20 +30,45 @@ fn load_config() -> (String, Address, u16, String) {
     let private_key = std::env::var("DISPENCER_PRIVATE_KEY").unwrap();
     let rpc_url = std::env::var("POD_RPC_URL").unwrap();
     let poda_address = std::env::var("PODA_ADDRESS").unwrap().parse::<Address>().unwrap();
+    let min_data_size = std::env::var("DISPENCER_MIN_DATA_SIZE")
+        .map(|size| size.parse::<usize>().unwrap())
+        .unwrap_or(DEFAULT_MIN_DATA_SIZE);
+    let min_payload_size = std::env::var("DISPENCER_MIN_PAYLOAD_SIZE")
+        .map(|size| size.parse::<usize>().unwrap())
+        .unwrap_or(DEFAULT_MIN_PAYLOAD_SIZE);
+    let max_body_bytes = std::env::var("DISPENCER_MAX_BODY_BYTES")
+        .map(|size| size.parse::<u64>().unwrap())
+        .unwrap_or(DEFAULT_MAX_BODY_BYTES);
+    let availability_confirmations = std::env::var("DISPENCER_AVAILABILITY_CONFIRMATIONS")
+        .map(|confirmations| confirmations.parse::<u64>().unwrap())
+        .unwrap_or(DEFAULT_AVAILABILITY_CONFIRMATIONS);
+    let send_multiproof = std::env::var("DISPENCER_SEND_MULTIPROOF").map(|v| v == "true").unwrap_or(false);
+    let record_metadata = std::env::var("DISPENCER_RECORD_METADATA").map(|v| v == "true").unwrap_or(false);
+    let kzg_ceremony_path = std::env::var("KZG_CEREMONY_PATH").ok();
+    init_shared_client(HttpTimeouts::from_env());
 
     info!("Loading config");
 
-    (rpc_url, poda_address, port, private_key)
+    Config { rpc_url, poda_address, port, private_key, min_data_size, min_payload_size, max_body_bytes, availability_confirmations, send_multiproof, record_metadata, kzg_ceremony_path }
 }
 
-#[tokio::main(flavor = "current_thread")]
+#[tokio::main]
 async fn main() {
-    let (rpc_url, poda_address, port, private_key) = load_config();
+    let Config { rpc_url, poda_address, port, private_key, min_data_size, min_payload_size, max_body_bytes, availability_confirmations, send_multiproof, record_metadata, kzg_ceremony_path } = load_config();
+
+    if let Some(path) = kzg_ceremony_path {
+        kzg::init_from_path(&path, TOTAL_SHARDS).unwrap_or_else(|e| panic!("Failed to load KZG ceremony from {}: {}", path, e));
+    }
 
     let signer = PrivateKeySigner::from_str(&private_key).unwrap();
-    let poda_client = PodaClient::new(signer, rpc_url.clone(), poda_address).await;
+    let poda_client = PodaClient::new(signer, rpc_url.clone(), poda_address).await
+        .with_availability_confirmations(availability_confirmations);
 
-    let dispenser = Arc::new(Dispenser::new(poda_client));
+    let dispenser = Arc::new(Dispenser::new(poda_client)
+        .with_min_data_size(min_data_size)
+        .with_min_payload_size(min_payload_size)
+        .with_multiproof(send_multiproof)
+        .with_metadata_store(record_metadata.then(|| Arc::new(InMemoryMetadataStore::default()) as Arc<dyn MetadataStore>)));
 
-    start_server(dispenser, port).await;
+    start_server(dispenser, port, max_body_bytes, Registry::new()).await;
 }
\ No newline at end of file
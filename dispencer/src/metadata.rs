@@ -0,0 +1,46 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use anyhow::Result;
+use async_trait::async_trait;
+use pod::{Address, FixedBytes};
+use serde::{Deserialize, Serialize};
+use crate::coding::CodingParams;
+
+/// What the dispenser recorded about a commitment when it submitted it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitmentMetadata {
+    // length of the original data
+    pub size: usize,
+    pub coding: CodingParams,
+    // chunk indices sent to each provider, moved along when chunks are repaired
+    pub assignment: HashMap<Address, Vec<u16>>,
+    // unix seconds of the submission
+    pub timestamp: u64,
+}
+
+/// Keeps the metadata of submitted commitments so retrievals need fewer chain reads. Chain state
+/// stays authoritative: a recorded assignment is only a hint, and chunks it does not yield are
+/// looked up on-chain.
+#[async_trait]
+pub trait MetadataStore: Send + Sync {
+    async fn put(&self, commitment: FixedBytes<32>, metadata: CommitmentMetadata) -> Result<()>;
+    async fn get(&self, commitment: FixedBytes<32>) -> Result<Option<CommitmentMetadata>>;
+}
+
+/// Metadata kept for the lifetime of the process
+#[derive(Debug, Default)]
+pub struct InMemoryMetadataStore {
+    entries: RwLock<HashMap<FixedBytes<32>, CommitmentMetadata>>,
+}
+
+#[async_trait]
+impl MetadataStore for InMemoryMetadataStore {
+    async fn put(&self, commitment: FixedBytes<32>, metadata: CommitmentMetadata) -> Result<()> {
+        self.entries.write().unwrap().insert(commitment, metadata);
+        Ok(())
+    }
+
+    async fn get(&self, commitment: FixedBytes<32>) -> Result<Option<CommitmentMetadata>> {
+        Ok(self.entries.read().unwrap().get(&commitment).cloned())
+    }
+}
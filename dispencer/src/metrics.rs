@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder};
+
+/// Prometheus counters and latency histograms exposed on the dispenser's /metrics route
+pub struct DispenserMetrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    failures: IntCounterVec,
+    latency: HistogramVec,
+}
+
+impl DispenserMetrics {
+    pub fn register(registry: Registry) -> prometheus::Result<Self> {
+        let requests = IntCounterVec::new(Opts::new("poda_dispenser_requests_total", "Requests handled, by operation"), &["operation"])?;
+        let failures = IntCounterVec::new(Opts::new("poda_dispenser_request_failures_total", "Requests answered with an error status, by operation"), &["operation"])?;
+        // submissions wait for on-chain availability, so the buckets reach well past the default 10s
+        let buckets = vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+        let latency = HistogramVec::new(HistogramOpts::new("poda_dispenser_request_duration_seconds", "Request latency, by operation").buckets(buckets), &["operation"])?;
+
+        registry.register(Box::new(requests.clone()))?;
+        registry.register(Box::new(failures.clone()))?;
+        registry.register(Box::new(latency.clone()))?;
+
+        Ok(Self { registry, requests, failures, latency })
+    }
+
+    /// Counts and times a finished request against its operation, derived from the request path
+    pub fn observe_request(&self, path: &str, status: u16, elapsed: Duration) {
+        let operation = match path {
+            "/submit" => "submit",
+            "/retrieve" => "retrieve",
+            _ => return,
+        };
+
+        self.requests.with_label_values(&[operation]).inc();
+        if status >= 400 {
+            self.failures.with_label_values(&[operation]).inc();
+        }
+        self.latency.with_label_values(&[operation]).observe(elapsed.as_secs_f64());
+    }
+
+    pub fn encode(&self) -> String {
+        let mut buffer = String::new();
+        if let Err(e) = TextEncoder::new().encode_utf8(&self.registry.gather(), &mut buffer) {
+            common::log::error!("Failed to encode metrics: {:?}", e);
+        }
+        buffer
+    }
+}
@@ -0,0 +1,118 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use common::http::shared_client;
+use pod::client::ProviderInfo;
+use storage_provider::http::{BatchRetrieveRequest, BatchRetrieveResponse, BatchStoreRequest, BatchStoreResponse, CapabilitiesResponse, StoreReceipt, VersionResponse};
+use crate::error::RetrieveError;
+
+/// The calls the dispenser makes to storage providers, so that distribution and retrieval can be
+/// exercised without running provider servers
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait ProviderTransport: Send + Sync {
+    // the receipt the provider signed for the stored chunks, if it signs receipts
+    async fn batch_store(&self, provider: &ProviderInfo, request: &BatchStoreRequest) -> Result<Option<StoreReceipt>>;
+    async fn batch_retrieve(&self, provider: &ProviderInfo, request: &BatchRetrieveRequest) -> Result<BatchRetrieveResponse, RetrieveError>;
+    async fn capabilities(&self, provider: &ProviderInfo) -> Result<CapabilitiesResponse>;
+    async fn version(&self, provider: &ProviderInfo) -> Result<VersionResponse>;
+}
+
+/// Talks to providers over their HTTP API
+pub struct HttpTransport {
+    client: reqwest::Client,
+}
+
+impl HttpTransport {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+impl Default for HttpTransport {
+    fn default() -> Self {
+        Self::new(shared_client())
+    }
+}
+
+#[async_trait]
+impl ProviderTransport for HttpTransport {
+    async fn batch_store(&self, provider: &ProviderInfo, request: &BatchStoreRequest) -> Result<Option<StoreReceipt>> {
+        let url = format!("{}/batch-store", provider.url);
+        let response = self.client.post(url).json(request).send().await?;
+
+        if !response.status().is_success() {
+            let error: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+            return Err(anyhow::anyhow!("Failed to submit chunks: {:?}", error["message"]));
+        }
+
+        Ok(response.json::<BatchStoreResponse>().await?.receipt)
+    }
+
+    async fn batch_retrieve(&self, provider: &ProviderInfo, request: &BatchRetrieveRequest) -> Result<BatchRetrieveResponse, RetrieveError> {
+        let url = format!("{}/batch-retrieve", provider.url);
+        let unreachable = |e: reqwest::Error| RetrieveError::Unreachable(e.to_string());
+        let response = self.client.post(url).json(request).send().await.map_err(unreachable)?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(RetrieveError::NotFound);
+        }
+        if !status.is_success() {
+            let body = response.text().await.map_err(unreachable)?;
+            return Err(RetrieveError::Status { status: status.as_u16(), body });
+        }
+
+        // read the body first so a dropped connection is not reported as a malformed response
+        let body = response.bytes().await.map_err(unreachable)?;
+        serde_json::from_slice(&body).map_err(|e| RetrieveError::Deserialization(e.to_string()))
+    }
+
+    async fn capabilities(&self, provider: &ProviderInfo) -> Result<CapabilitiesResponse> {
+        let url = format!("{}/capabilities", provider.url);
+        let response = self.client.get(url).send().await?.error_for_status()?;
+        Ok(response.json().await?)
+    }
+
+    async fn version(&self, provider: &ProviderInfo) -> Result<VersionResponse> {
+        let url = format!("{}/version", provider.url);
+        let response = self.client.get(url).send().await?.error_for_status()?;
+        Ok(response.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::TcpListener, time::{Duration, Instant}};
+    use common::http::{build_client, HttpTimeouts};
+    use pod::{Address, FixedBytes, U256};
+
+    #[tokio::test]
+    async fn test_unresponsive_provider_times_out() {
+        // accepts connections and holds them open without ever answering
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let _held = listener.incoming().collect::<Vec<_>>();
+        });
+
+        let provider = ProviderInfo {
+            name: "Hung provider".to_string(),
+            url: format!("http://{}", addr),
+            addr: Address::repeat_byte(1),
+            registeredAt: 0,
+            challengeCount: 0,
+            challengeSuccessCount: 0,
+            active: true,
+            stakedAmount: U256::from(100),
+        };
+        let timeout = Duration::from_millis(200);
+        let transport = HttpTransport::new(build_client(HttpTimeouts { connect: timeout, request: timeout }));
+        let request = BatchRetrieveRequest { commitment: FixedBytes::from([1; 32]), indices: vec![0] };
+
+        let started = Instant::now();
+        let result = tokio::time::timeout(Duration::from_secs(10), transport.batch_retrieve(&provider, &request)).await;
+
+        assert!(result.expect("the call hung past its timeout").is_err());
+        assert!(started.elapsed() >= timeout);
+    }
+}
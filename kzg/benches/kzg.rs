@@ -0,0 +1,54 @@
+//! Baseline cost of the KZG operations the dispenser runs on every submission: committing to
+//! all chunks, and opening at one point or at the batch of points a provider is sent.
+//!
+//! Batch openings are measured both recomputing the polynomial from the chunks and reusing the
+//! one `kzg_commit` already returned. The dispenser proves once per provider, so the difference
+//! is paid for every provider.
+//!
+//! Openings up to `TOTAL_SHARDS` points run against the production polynomial. A 32 point
+//! opening needs more points than it has, so it runs against a 48 chunk polynomial instead.
+//!
+//! `cargo bench -p kzg --bench kzg` to run.
+
+use common::{constants::TOTAL_SHARDS, types::Chunk};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use kzg::{kzg_commit, kzg_multi_prove, kzg_multi_prove_with_polynomial, kzg_prove_with_polynomial};
+
+const OPENING_SIZES: [usize; 5] = [1, 4, 8, 16, 32];
+
+fn chunks(count: usize) -> Vec<Chunk> {
+    (0..count as u16).map(|index| Chunk { index, data: vec![index as u8; 1024] }).collect()
+}
+
+fn commit(c: &mut Criterion) {
+    let chunks = chunks(TOTAL_SHARDS);
+    c.bench_function("kzg_commit", |b| b.iter(|| kzg_commit(&chunks)));
+}
+
+fn open(c: &mut Criterion) {
+    let (_, polynomial) = kzg_commit(&chunks(TOTAL_SHARDS));
+    c.bench_function("kzg_open", |b| b.iter(|| kzg_prove_with_polynomial(&polynomial, TOTAL_SHARDS - 1)));
+}
+
+fn multi_open(c: &mut Criterion) {
+    let (production_chunks, wide_chunks) = (chunks(TOTAL_SHARDS), chunks(2 * TOTAL_SHARDS));
+    let (_, production) = kzg_commit(&production_chunks);
+    let (_, wide) = kzg_commit(&wide_chunks);
+
+    let mut group = c.benchmark_group("kzg_multi_open");
+    for points in OPENING_SIZES {
+        let (chunks, polynomial) = if points <= TOTAL_SHARDS { (&production_chunks, &production) } else { (&wide_chunks, &wide) };
+        let indices = (0..points).collect::<Vec<_>>();
+        group.bench_with_input(BenchmarkId::new("recompute", points), &indices, |b, indices| {
+            b.iter(|| kzg_multi_prove(chunks, indices));
+        });
+        group.bench_with_input(BenchmarkId::new("precomputed", points), &indices, |b, indices| {
+            b.iter(|| kzg_multi_prove_with_polynomial(polynomial, indices));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, commit, open, multi_open);
+criterion_main!(benches);
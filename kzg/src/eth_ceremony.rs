@@ -2,6 +2,7 @@ use ark_bls12_381::G1Projective as G1;
 use ark_bls12_381::G2Projective as G2;
 use ark_serialize::CanonicalDeserialize;
 use serde::{Deserialize, Serialize};
+use crate::kzg::MIN_G2_POWERS;
 
 pub static ETH_CEREMONY: &str = r#"{
     "contributions": [
@@ -4206,43 +4207,57 @@ struct PowersOfTau {
     g2_powers: Vec<String>,
 }
 
-/// Load Ethereum's trusted setup ceremony data and extract CRS powers
-pub fn load_ethereum_ceremony(degree: usize) -> Result<(Vec<G1>, Vec<G2>), Box<dyn std::error::Error>> {
-    let ceremony: EthereumCeremony = serde_json::from_str(ETH_CEREMONY)?;
-    
+/// Why a ceremony could not be turned into CRS powers
+#[derive(Debug, PartialEq, Eq)]
+pub enum KzgError {
+    // fewer G1 powers than a polynomial of the requested degree needs
+    CeremonyTooSmall { have: usize, need: usize },
+    InvalidCeremony(String),
+    // the CRS is fixed once the first KZG instance has been built from it
+    AlreadyInitialized,
+}
+
+impl std::fmt::Display for KzgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KzgError::CeremonyTooSmall { have, need } => write!(f, "Ceremony only has {} G1 powers, but {} are needed", have, need),
+            KzgError::InvalidCeremony(reason) => write!(f, "Invalid ceremony data: {}", reason),
+            KzgError::AlreadyInitialized => write!(f, "The KZG trusted setup is already in use and cannot be replaced"),
+        }
+    }
+}
+
+impl std::error::Error for KzgError {}
+
+fn decode_point<P: CanonicalDeserialize>(hex_str: &str) -> Result<P, KzgError> {
+    let bytes = hex::decode(hex_str.trim_start_matches("0x")).map_err(|e| KzgError::InvalidCeremony(e.to_string()))?;
+    P::deserialize_compressed(&bytes[..]).map_err(|e| KzgError::InvalidCeremony(e.to_string()))
+}
+
+/// Extract the CRS powers for `degree` from ceremony JSON in the format of Ethereum's
+/// trusted setup, such as `ETH_CEREMONY`
+pub fn load_ceremony(json: &str, degree: usize) -> Result<(Vec<G1>, Vec<G2>), KzgError> {
+    let ceremony: EthereumCeremony = serde_json::from_str(json).map_err(|e| KzgError::InvalidCeremony(e.to_string()))?;
+
     // Use the last contribution (most recent/final)
     let final_contribution = ceremony.contributions.last()
-        .ok_or("No contributions found in ceremony file")?;
-    
+        .ok_or_else(|| KzgError::InvalidCeremony("No contributions found in ceremony file".to_string()))?;
+
     // Extract G1 powers (we need degree + 1 powers)
-    let mut crs_g1 = Vec::new();
-    for i in 0..=degree {
-        if i >= final_contribution.powers_of_tau.g1_powers.len() {
-            return Err(format!("Ceremony only has {} G1 powers, but {} are needed", 
-                final_contribution.powers_of_tau.g1_powers.len(), degree + 1).into());
-        }
-        
-        let hex_str = &final_contribution.powers_of_tau.g1_powers[i];
-        let clean_hex = hex_str.trim_start_matches("0x");
-        let bytes = hex::decode(clean_hex)?;
-        let point = G1::deserialize_compressed(&bytes[..])?;
-        crs_g1.push(point);
+    let g1_powers = &final_contribution.powers_of_tau.g1_powers;
+    if g1_powers.len() < degree + 1 {
+        return Err(KzgError::CeremonyTooSmall { have: g1_powers.len(), need: degree + 1 });
     }
-    
-    // Extract G2 powers (we need degree + 1 powers)
-    let mut crs_g2 = Vec::new();
-    for i in 0..=degree {
-        if i >= final_contribution.powers_of_tau.g2_powers.len() {
-            return Err(format!("Ceremony only has {} G2 powers, but {} are needed", 
-                final_contribution.powers_of_tau.g2_powers.len(), degree + 1).into());
-        }
-        
-        let hex_str = &final_contribution.powers_of_tau.g2_powers[i];
-        let clean_hex = hex_str.trim_start_matches("0x");
-        let bytes = hex::decode(clean_hex)?;
-        let point = G2::deserialize_compressed(&bytes[..])?;
-        crs_g2.push(point);
+    let crs_g1 = g1_powers.iter().take(degree + 1).map(|hex_str| decode_point(hex_str)).collect::<Result<Vec<G1>, _>>()?;
+
+    // Extract G2 powers. Ceremonies ship far fewer G2 than G1 powers, so take as many as
+    // are needed to multi-verify every evaluation point (degree + 2) and settle for less
+    let g2_powers = &final_contribution.powers_of_tau.g2_powers;
+    if g2_powers.len() < MIN_G2_POWERS {
+        return Err(KzgError::InvalidCeremony(format!("Ceremony only has {} G2 powers, but at least {} are needed",
+            g2_powers.len(), MIN_G2_POWERS)));
     }
-    
+    let crs_g2 = g2_powers.iter().take(degree + 2).map(|hex_str| decode_point(hex_str)).collect::<Result<Vec<G2>, _>>()?;
+
     Ok((crs_g1, crs_g2))
-}
\ No newline at end of file
+}
@@ -1,6 +1,7 @@
 use std::ops::Mul;
 use ark_ff::Field;
 use ark_ec::pairing::Pairing;
+use ark_std::Zero;
 use crate::utils::{div, mul, evaluate, interpolate};
 
 #[allow(clippy::upper_case_acronyms)]
@@ -14,6 +15,12 @@ pub struct KZG<E: Pairing> {
 }
 
 
+// g2 and g2_tau, enough for single point verification
+pub const MIN_G2_POWERS: usize = 2;
+
+// (points, values, commitment, pi) of one `verify_multi` opening
+pub type MultiOpening<E> = (Vec<<E as Pairing>::ScalarField>, Vec<<E as Pairing>::ScalarField>, <E as Pairing>::G1, <E as Pairing>::G1);
+
 impl <E:Pairing> KZG<E> {
     #[allow(dead_code)]
     pub fn new(g1: E::G1, g2: E::G2, degree: usize) -> Self {
@@ -27,7 +34,12 @@ impl <E:Pairing> KZG<E> {
         }
     }
 
-    /// Create KZG instance with pre-computed CRS from trusted setup ceremony
+    /// Create KZG instance with pre-computed CRS from trusted setup ceremony.
+    ///
+    /// Committing and opening only use G1 powers, so `crs_g1` must cover `degree`.
+    /// G2 powers are only needed for verification:
+    /// - `verify` uses `g2` and `g2_tau`, i.e. `MIN_G2_POWERS`
+    /// - `verify_multi` over `n` points commits to a degree `n` zero polynomial, i.e. `n + 1`
     pub fn from_trusted_setup(
         g1: E::G1, 
         g2: E::G2, 
@@ -38,17 +50,13 @@ impl <E:Pairing> KZG<E> {
         if crs_g1.len() < degree + 1 {
             return Err("CRS G1 powers insufficient for degree");
         }
-        if crs_g2.len() < degree + 1 {
-            return Err("CRS G2 powers insufficient for degree");
+        if crs_g2.len() < MIN_G2_POWERS {
+            return Err("CRS G2 needs at least 2 powers for g2_tau");
         }
-        
+
         // g2_tau is the first power of tau in G2 (index 1)
-        let g2_tau = if crs_g2.len() > 1 {
-            crs_g2[1]
-        } else {
-            return Err("CRS G2 needs at least 2 powers for g2_tau");
-        };
-        
+        let g2_tau = crs_g2[1];
+
         Ok(Self {
             g1,
             g2,
@@ -59,6 +67,11 @@ impl <E:Pairing> KZG<E> {
         })
     }
 
+    /// Largest number of points `verify_multi` can check with the loaded G2 powers
+    pub fn max_multi_points(&self) -> usize {
+        self.crs_g2.len().saturating_sub(1)
+    }
+
     #[allow(dead_code)]
     pub fn setup(&mut self, secret: E::ScalarField) {
         for i in 0..self.degree+1 {
@@ -155,29 +168,180 @@ impl <E:Pairing> KZG<E> {
         commitment: E::G1,
         pi: E::G1
     ) -> bool {
-        // compute the zero polynomial
+        if !self.is_valid_opening(points, values) {
+            return false;
+        }
+
+        let lhs = E::pairing(pi, self.zero_commitment(points));
+        let rhs = E::pairing(commitment - self.lagrange_commitment(points, values), self.g2);
+        lhs == rhs
+    }
+
+    /// Checks every `(points, values, commitment, pi)` opening at once, each weighted by the
+    /// matching entry of `weights`, with one multi-pairing. The weights have to be unpredictable
+    /// to whoever made the proofs, or a wrong opening can be cancelled out by another.
+    pub fn verify_multi_batch(
+        &self,
+        openings: &[MultiOpening<E>],
+        weights: &[E::ScalarField],
+    ) -> bool {
+        if openings.is_empty() || openings.len() != weights.len() {
+            return false;
+        }
+        if !openings.iter().all(|(points, values, _, _)| self.is_valid_opening(points, values)) {
+            return false;
+        }
+
+        // prod e(r_i * pi_i, Z_i) == e(sum r_i * (C_i - I_i), g2)
+        let mut g1_terms = Vec::with_capacity(openings.len() + 1);
+        let mut g2_terms = Vec::with_capacity(openings.len() + 1);
+        let mut combined = self.g1.mul(E::ScalarField::default());
+        for ((points, values, commitment, pi), weight) in openings.iter().zip(weights) {
+            g1_terms.push(pi.mul(*weight));
+            g2_terms.push(self.zero_commitment(points));
+            combined += (*commitment - self.lagrange_commitment(points, values)).mul(*weight);
+        }
+        g1_terms.push(-combined);
+        g2_terms.push(self.g2);
+
+        E::multi_pairing(g1_terms, g2_terms).is_zero()
+    }
+
+    // an empty opening proves nothing, and every point needs exactly one value
+    fn is_valid_opening(&self, points: &[E::ScalarField], values: &[E::ScalarField]) -> bool {
+        !points.is_empty() && points.len() == values.len() && points.len() <= self.max_multi_points()
+    }
+
+    // commitment in G2 to the polynomial whose roots are `points`
+    fn zero_commitment(&self, points: &[E::ScalarField]) -> E::G2 {
         let mut zero_poly = vec![-points[0], E::ScalarField::ONE];
         for point in points.iter().skip(1) {
             zero_poly = mul(&zero_poly, &[-*point, E::ScalarField::ONE]);
         }
 
-        // compute commitment of zero polynomial in regards to crs_g2
         let mut zero_commitment = self.g2.mul(E::ScalarField::default());
-        for (i, coeff) in zero_poly.iter().enumerate().take(self.crs_g2.len()) {
+        for (i, coeff) in zero_poly.iter().enumerate() {
             zero_commitment += self.crs_g2[i] * coeff;
         }
+        zero_commitment
+    }
 
-        // compute lagrange polynomial
+    // commitment in G1 to the lowest degree polynomial through `points` and `values`
+    fn lagrange_commitment(&self, points: &[E::ScalarField], values: &[E::ScalarField]) -> E::G1 {
         let lagrange_poly = interpolate(points, values).unwrap();
 
-        // compute commitment of lagrange polynomial in regards to crs_g1
         let mut lagrange_commitment = self.g1.mul(E::ScalarField::default());
         for (i, coeff) in lagrange_poly.iter().enumerate().take(std::cmp::min(lagrange_poly.len(), self.crs_g1.len())) {
             lagrange_commitment += self.crs_g1[i] * coeff;
         }
+        lagrange_commitment
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Bls12_381, Fr, G1Projective as G1, G2Projective as G2};
+    use ark_ec::PrimeGroup;
 
-        let lhs = E::pairing(pi, zero_commitment);
-        let rhs = E::pairing(commitment - lagrange_commitment, self.g2);
-        lhs == rhs
+    const DEGREE: usize = 4;
+
+    fn crs(g2_powers: usize) -> (Vec<G1>, Vec<G2>) {
+        let tau = Fr::from(7u64);
+        let crs_g1 = (0..=DEGREE).map(|i| G1::generator().mul(tau.pow([i as u64]))).collect();
+        let crs_g2 = (0..g2_powers).map(|i| G2::generator().mul(tau.pow([i as u64]))).collect();
+        (crs_g1, crs_g2)
+    }
+
+    fn setup(g2_powers: usize) -> Result<KZG<Bls12_381>, &'static str> {
+        let (crs_g1, crs_g2) = crs(g2_powers);
+        KZG::<Bls12_381>::from_trusted_setup(G1::generator(), G2::generator(), DEGREE, crs_g1, crs_g2)
+    }
+
+    fn poly() -> Vec<Fr> {
+        (1..=DEGREE as u64 + 1).map(Fr::from).collect()
+    }
+
+    #[test]
+    fn test_single_g2_power_is_rejected() {
+        assert!(setup(MIN_G2_POWERS - 1).is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_min_g2_powers_supports_single_point_verify() {
+        let kzg = setup(MIN_G2_POWERS).unwrap();
+        let poly = poly();
+        let commitment = kzg.commit(&poly);
+        let point = Fr::from(3u64);
+
+        let pi = kzg.open(&poly, point);
+        assert!(kzg.verify(point, evaluate(&poly, point), commitment, pi));
+
+        assert_eq!(kzg.max_multi_points(), 1);
+        let pi = kzg.multi_open(&poly, &[point]);
+        assert!(kzg.verify_multi(&[point], &[evaluate(&poly, point)], commitment, pi));
+    }
+
+    #[test]
+    fn test_verify_multi_needs_points_plus_one_g2_powers() {
+        let poly = poly();
+        let points = [Fr::from(1u64), Fr::from(2u64)];
+        let values = points.iter().map(|p| evaluate(&poly, *p)).collect::<Vec<_>>();
+
+        let kzg = setup(points.len()).unwrap();
+        let commitment = kzg.commit(&poly);
+        let pi = kzg.multi_open(&poly, &points);
+        assert!(!kzg.verify_multi(&points, &values, commitment, pi));
+
+        let kzg = setup(points.len() + 1).unwrap();
+        assert!(kzg.verify_multi(&points, &values, commitment, pi));
+    }
+
+    #[test]
+    fn test_verify_multi_rejects_empty_and_mismatched_input() {
+        let kzg = setup(DEGREE + 1).unwrap();
+        let poly = poly();
+        let commitment = kzg.commit(&poly);
+        let points = [Fr::from(1u64), Fr::from(2u64)];
+        let values = points.iter().map(|p| evaluate(&poly, *p)).collect::<Vec<_>>();
+        let pi = kzg.multi_open(&poly, &points);
+
+        assert!(!kzg.verify_multi(&[], &[], commitment, pi));
+        assert!(!kzg.verify_multi(&points, &values[..1], commitment, pi));
+        assert!(!kzg.verify_multi(&points[..1], &values, commitment, pi));
+        assert!(kzg.verify_multi(&points, &values, commitment, pi));
+    }
+
+    #[test]
+    fn test_verify_multi_batch_across_commitments() {
+        let kzg = setup(DEGREE + 1).unwrap();
+        let polys = [poly(), (1..=DEGREE as u64 + 1).map(|x| Fr::from(x * x + 3)).collect::<Vec<_>>()];
+        let opening = |poly: &[Fr], points: Vec<Fr>| {
+            let values = points.iter().map(|p| evaluate(poly, *p)).collect::<Vec<_>>();
+            let pi = kzg.multi_open(poly, &points);
+            (points, values, kzg.commit(poly), pi)
+        };
+        let openings = vec![
+            opening(&polys[0], vec![Fr::from(0u64), Fr::from(2u64)]),
+            opening(&polys[1], vec![Fr::from(1u64), Fr::from(3u64), Fr::from(4u64)]),
+            opening(&polys[0], vec![Fr::from(4u64)]),
+        ];
+        let weights = [Fr::from(11u64), Fr::from(13u64), Fr::from(17u64)];
+        assert!(kzg.verify_multi_batch(&openings, &weights));
+        assert!(!kzg.verify_multi_batch(&openings, &weights[..2]));
+
+        // any single wrong value, commitment or proof fails the batch
+        for i in 0..openings.len() {
+            let mut wrong_value = openings.clone();
+            wrong_value[i].1[0] += Fr::from(1u64);
+            assert!(!kzg.verify_multi_batch(&wrong_value, &weights), "wrong value in opening {}", i);
+
+            let mut wrong_commitment = openings.clone();
+            wrong_commitment[i].2 = kzg.commit(&polys[(i + 1) % 2]);
+            assert!(!kzg.verify_multi_batch(&wrong_commitment, &weights), "wrong commitment in opening {}", i);
+
+            let mut wrong_proof = openings.clone();
+            wrong_proof[i].3 = openings[(i + 1) % openings.len()].3;
+            assert!(!kzg.verify_multi_batch(&wrong_proof, &weights), "wrong proof in opening {}", i);
+        }
+    }
+}
@@ -4,29 +4,52 @@ pub mod types;
 mod eth_ceremony;
 
 use ark_bls12_381::{Bls12_381, Fr, FrConfig, G1Projective as G1, G2Projective as G2};
-use ark_ec::PrimeGroup;
+use ark_ec::{CurveGroup, PrimeGroup};
+use ark_std::Zero;
 use ark_ff::AdditiveGroup;
-use ark_ff::{Fp, MontBackend};
-use common::{types::Chunk, constants::TOTAL_SHARDS};
+use ark_ff::{Fp, MontBackend, PrimeField};
+use common::types::{keccak256, Chunk};
 use types::{KzgCommitment, KzgProof};
 use kzg::KZG;
 use utils::interpolate;
 use std::cmp::Ordering;
-use std::sync::OnceLock;
-use std::sync::Arc;
-use eth_ceremony::load_ethereum_ceremony;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use eth_ceremony::{load_ceremony, ETH_CEREMONY};
+
+pub use eth_ceremony::KzgError;
 
 pub type KZGPolynomial = Vec<ark_ff::Fp<ark_ff::MontBackend<ark_bls12_381::FrConfig, 4>, 4>>;
 
-static KZG_INSTANCE: OnceLock<Arc<KZG<Bls12_381>>> = OnceLock::new();
+// One instance per degree, loaded from the ceremony the first time it is asked for
+static KZG_INSTANCES: OnceLock<Mutex<HashMap<usize, Arc<KZG<Bls12_381>>>>> = OnceLock::new();
+// Ceremony set by `init_from_path`, the embedded Ethereum ceremony is used when unset
+static CEREMONY: OnceLock<String> = OnceLock::new();
+
+/// Uses the ceremony file at `path` as the trusted setup instead of the embedded Ethereum
+/// ceremony. It must hold enough powers to commit to `max_chunks` chunks, and has to be loaded
+/// before the first commitment, proof or verification.
+pub fn init_from_path(path: impl AsRef<Path>, max_chunks: usize) -> Result<(), KzgError> {
+    let json = std::fs::read_to_string(path).map_err(|e| KzgError::InvalidCeremony(e.to_string()))?;
+    load_ceremony(&json, commit_degree(max_chunks))?;
+
+    let instances = KZG_INSTANCES.get_or_init(Default::default).lock().unwrap();
+    if !instances.is_empty() {
+        return Err(KzgError::AlreadyInitialized);
+    }
+    CEREMONY.set(json).map_err(|_| KzgError::AlreadyInitialized)
+}
 
-fn get_kzg_instance() -> Arc<KZG<Bls12_381>> {
-    KZG_INSTANCE.get_or_init(|| {
-        let kzg = match load_ethereum_ceremony(TOTAL_SHARDS - 1) {
+fn get_kzg_instance_for_degree(degree: usize) -> Arc<KZG<Bls12_381>> {
+    let mut instances = KZG_INSTANCES.get_or_init(Default::default).lock().unwrap();
+    instances.entry(degree).or_insert_with(|| {
+        let ceremony = CEREMONY.get().map(String::as_str).unwrap_or(ETH_CEREMONY);
+        let kzg = match load_ceremony(ceremony, degree) {
             Ok((crs_g1, crs_g2)) => {
                 let g1 = G1::generator();
                 let g2 = G2::generator();
-                KZG::<Bls12_381>::from_trusted_setup(g1, g2, TOTAL_SHARDS - 1, crs_g1, crs_g2)
+                KZG::<Bls12_381>::from_trusted_setup(g1, g2, degree, crs_g1, crs_g2)
                     .expect("Failed to create KZG from Ethereum ceremony data")
             },
             Err(e) => {
@@ -37,7 +60,18 @@ fn get_kzg_instance() -> Arc<KZG<Bls12_381>> {
     }).clone()
 }
 
-pub fn kzg_commit(chunks: &Vec<Chunk>) -> (KzgCommitment, KZGPolynomial) {
+// The polynomial interpolates one value per chunk, so committing and opening need degree n - 1
+fn commit_degree(chunk_count: usize) -> usize {
+    chunk_count.saturating_sub(1)
+}
+
+// Verifying n points needs n G1 and n + 1 G2 powers, which a degree n - 1 instance loads.
+// Single point verification still needs g2_tau, so never go below degree 1.
+fn verify_degree(point_count: usize) -> usize {
+    point_count.saturating_sub(1).max(1)
+}
+
+pub fn kzg_commit(chunks: &[Chunk]) -> (KzgCommitment, KZGPolynomial) {
     // Convert all chunks to field elements (one field element per chunk)
     let mut all_field_elements = Vec::new();
     
@@ -46,45 +80,131 @@ pub fn kzg_commit(chunks: &Vec<Chunk>) -> (KzgCommitment, KZGPolynomial) {
         all_field_elements.extend(field_elements);
     }
 
-    let polynomial = gen_polynomial(chunks, get_kzg_instance().degree);
-    let commitment = get_kzg_instance().commit(&polynomial);
+    let kzg = get_kzg_instance_for_degree(commit_degree(chunks.len()));
+    let polynomial = gen_polynomial(chunks, kzg.degree);
+    let commitment = kzg.commit(&polynomial);
 
     (KzgCommitment::new(commitment), polynomial)
 }
 
-pub fn kzg_prove(chunks: &Vec<Chunk>, chunk_index: usize) -> KzgProof {
+pub fn kzg_prove(chunks: &[Chunk], chunk_index: usize) -> KzgProof {
     let (_, polynomial) = kzg_commit(chunks);
+    kzg_prove_with_polynomial(&polynomial, chunk_index)
+}
 
+/// Like `kzg_prove`, but opens the polynomial returned by an earlier `kzg_commit` instead of
+/// interpolating it again
+pub fn kzg_prove_with_polynomial(polynomial: &KZGPolynomial, chunk_index: usize) -> KzgProof {
     let proof_point = Fr::from(chunk_index as u64);
-    let proof = get_kzg_instance().open(&polynomial, proof_point);
+    let proof = get_kzg_instance_for_degree(commit_degree(polynomial.len())).open(polynomial, proof_point);
 
     KzgProof::new(proof)
 }
 
-pub fn kzg_multi_prove(chunks: &Vec<Chunk>, chunk_indices: &[usize]) -> KzgProof {
+pub fn kzg_multi_prove(chunks: &[Chunk], chunk_indices: &[usize]) -> KzgProof {
     let (_, polynomial) = kzg_commit(chunks);
+    kzg_multi_prove_with_polynomial(&polynomial, chunk_indices)
+}
 
+/// Like `kzg_multi_prove`, but opens the polynomial returned by an earlier `kzg_commit`
+/// instead of interpolating it again
+pub fn kzg_multi_prove_with_polynomial(polynomial: &KZGPolynomial, chunk_indices: &[usize]) -> KzgProof {
     let points: Vec<Fr> = chunk_indices.iter().map(|i| Fr::from(*i as u64)).collect();
-    let proof = get_kzg_instance().multi_open(&polynomial, &points);
+    let proof = get_kzg_instance_for_degree(commit_degree(polynomial.len())).multi_open(polynomial, &points);
 
     KzgProof::new(proof)
 }
 
+/// Verifies that `proof` opens `commitment` to the chunks at `chunk_indices`. Returns false
+/// without pairing if there is not exactly one index per chunk, if either point fails
+/// `is_valid_point` or if the commitment is the identity.
 pub fn kzg_multi_verify(chunks: &[Chunk], chunk_indices: &[usize], commitment: KzgCommitment, proof: KzgProof) -> bool {
+    if chunks.is_empty() || chunks.len() != chunk_indices.len() {
+        return false;
+    }
+    if !is_valid_commitment(&commitment) || !is_valid_point(proof.as_inner()) {
+        return false;
+    }
+
     let points: Vec<Fr> = chunk_indices.iter().map(|i| Fr::from(*i as u64)).collect();
     let values: Vec<Fp<MontBackend<FrConfig, 4>, 4>> = chunks.iter().map(chunk_to_field_elements).map(|v| v[0]).collect();
 
-    get_kzg_instance().verify_multi(&points, &values, commitment.into_inner(), proof.into_inner())
+    get_kzg_instance_for_degree(verify_degree(points.len())).verify_multi(&points, &values, commitment.into_inner(), proof.into_inner())
+}
+
+/// Verifies many `(commitment, chunk_indices, chunks, proof)` multi-openings, of any mix of
+/// commitments, with one multi-pairing instead of two pairings per item. Each item gets the checks
+/// of `kzg_multi_verify`, and a single invalid item fails the whole batch.
+pub fn kzg_batch_verify_multi(items: &[(KzgCommitment, Vec<usize>, Vec<Chunk>, KzgProof)]) -> bool {
+    if items.is_empty() {
+        return false;
+    }
+
+    let mut openings = Vec::with_capacity(items.len());
+    for (commitment, chunk_indices, chunks, proof) in items {
+        if chunks.is_empty() || chunks.len() != chunk_indices.len() {
+            return false;
+        }
+        if !is_valid_commitment(commitment) || !is_valid_point(proof.as_inner()) {
+            return false;
+        }
+
+        let points: Vec<Fr> = chunk_indices.iter().map(|i| Fr::from(*i as u64)).collect();
+        let values: Vec<Fr> = chunks.iter().map(chunk_to_field_elements).map(|v| v[0]).collect();
+        openings.push((points, values, *commitment.as_inner(), *proof.as_inner()));
+    }
+
+    let max_points = openings.iter().map(|(points, _, _, _)| points.len()).max().unwrap_or_default();
+    get_kzg_instance_for_degree(verify_degree(max_points)).verify_multi_batch(&openings, &batch_weights(items))
+}
+
+// Fiat-Shamir weights: every item is hashed into the transcript before any weight is drawn, so
+// the prover cannot pick proofs that cancel out under weights it knows in advance
+fn batch_weights(items: &[(KzgCommitment, Vec<usize>, Vec<Chunk>, KzgProof)]) -> Vec<Fr> {
+    let mut transcript = Vec::new();
+    for (commitment, chunk_indices, chunks, proof) in items {
+        transcript.extend_from_slice(&commitment.to_bytes());
+        transcript.extend_from_slice(&proof.to_bytes());
+        transcript.extend_from_slice(&(chunk_indices.len() as u64).to_be_bytes());
+        for (index, chunk) in chunk_indices.iter().zip(chunks) {
+            transcript.extend_from_slice(&(*index as u64).to_be_bytes());
+            transcript.extend_from_slice(chunk.hash().as_slice());
+        }
+    }
+    let seed = keccak256(&transcript);
+
+    (0..items.len() as u64)
+        .map(|i| Fr::from_le_bytes_mod_order(keccak256([seed.as_slice(), &i.to_be_bytes()].concat()).as_slice()))
+        .collect()
 }
 
+/// Verifies that `proof` opens `commitment` to `chunk` at `chunk_index`, with the same point
+/// checks as `kzg_multi_verify`.
 pub fn kzg_verify(chunk: &Chunk, chunk_index: usize, commitment: KzgCommitment, proof: KzgProof) -> bool {
+    if !is_valid_commitment(&commitment) || !is_valid_point(proof.as_inner()) {
+        return false;
+    }
+
     // Convert the chunk to field elements
     let field_elements = chunk_to_field_elements(chunk);
     let fr_value = field_elements[0];
     
-    // Use the same KZG instance that was used for commitment and proof generation
+    // Verification only reads g2 and g2_tau, so any instance checks proofs of any degree
     let point = Fr::from(chunk_index as u64);
-    get_kzg_instance().verify(point, fr_value, commitment.into_inner(), proof.into_inner())
+    get_kzg_instance_for_degree(verify_degree(1)).verify(point, fr_value, commitment.into_inner(), proof.into_inner())
+}
+
+// A point is usable in a pairing check if it is on the curve and in the prime order subgroup.
+// Points parsed with `from_bytes` already are, but ones built with `new` are not checked.
+fn is_valid_point(point: &G1) -> bool {
+    let affine = point.into_affine();
+    affine.is_on_curve() && affine.is_in_correct_subgroup_assuming_on_curve()
+}
+
+// The identity commits to the zero polynomial, which no chunk data encodes, so it is never a
+// real commitment. An identity proof stays allowed since constant polynomials open to it.
+fn is_valid_commitment(commitment: &KzgCommitment) -> bool {
+    !commitment.as_inner().is_zero() && is_valid_point(commitment.as_inner())
 }
 
 /// Convert a chunk to field elements for interpolation
@@ -97,7 +217,7 @@ fn chunk_to_field_elements(chunk: &Chunk) -> Vec<Fr> {
     vec![Fr::from(combined)]
 }
 
-fn gen_polynomial(data: &Vec<Chunk>, degree: usize) -> Vec<Fp<MontBackend<FrConfig, 4>, 4>> {
+fn gen_polynomial(data: &[Chunk], degree: usize) -> Vec<Fp<MontBackend<FrConfig, 4>, 4>> {
     // Convert all chunks to field elements to reconstruct the polynomial
     let mut all_field_elements = Vec::new();
     
@@ -226,6 +346,122 @@ mod tests {
         assert!(is_valid, "Multi-proof verification should succeed for subset of chunks");
     }
 
+    #[test]
+    fn test_identity_commitment_is_rejected() {
+        let chunks = get_sample_chunks();
+        let identity = KzgCommitment::new(G1::zero());
+        assert_eq!(identity.to_bytes()[0], 0xc0, "compressed identity has only the infinity flag set");
+
+        assert!(!kzg_verify(&chunks[0], 0, identity.clone(), kzg_prove(&chunks, 0)));
+        assert!(!kzg_verify(&chunks[0], 0, identity.clone(), KzgProof::new(G1::zero())));
+        assert!(!kzg_multi_verify(&chunks[..2], &[0, 1], identity, kzg_multi_prove(&chunks, &[0, 1])));
+
+        // the same proofs still verify against the real commitment
+        let (commitment, _) = kzg_commit(&chunks);
+        assert!(kzg_verify(&chunks[0], 0, commitment, kzg_prove(&chunks, 0)));
+    }
+
+    #[test]
+    fn test_multi_open_proof_is_one_compressed_point() {
+        use ark_serialize::CanonicalSerialize;
+
+        let chunks = get_sample_chunks();
+        let (commitment, polynomial) = kzg_commit(&chunks);
+
+        for count in [1, 4, 8, 16, chunks.len()] {
+            let indices = (0..count).collect::<Vec<_>>();
+            let proof = kzg_multi_prove_with_polynomial(&polynomial, &indices);
+            let mut encoded = Vec::new();
+            proof.as_inner().serialize_compressed(&mut encoded).unwrap();
+            assert_eq!(encoded.len(), types::G1_COMPRESSED_SIZE, "{} points", count);
+            assert!(kzg_multi_verify(&chunks[..count], &indices, commitment.clone(), proof));
+        }
+    }
+
+    #[test]
+    fn test_multi_verify_rejects_empty_and_mismatched_input() {
+        let chunks = get_sample_chunks();
+        let (commitment, _) = kzg_commit(&chunks);
+        let proof = kzg_multi_prove(&chunks, &[0, 1]);
+
+        assert!(!kzg_multi_verify(&[], &[], commitment.clone(), proof.clone()));
+        assert!(!kzg_multi_verify(&chunks[..2], &[0], commitment.clone(), proof.clone()));
+        assert!(!kzg_multi_verify(&chunks[..1], &[0, 1], commitment.clone(), proof.clone()));
+        assert!(kzg_multi_verify(&chunks[..2], &[0, 1], commitment, proof));
+    }
+
+    #[test]
+    fn test_batch_verify_multi_across_commitments() {
+        // the placeholder ceremony has tau = 1, where the polynomial takes chunk 1's value, so the two
+        // sets differ in that chunk and share everything else
+        let first = get_sample_chunks();
+        let mut second = first.clone();
+        second[1].data.iter_mut().for_each(|byte| *byte = byte.wrapping_add(1));
+        let (first_commitment, first_polynomial) = kzg_commit(&first);
+        let (second_commitment, second_polynomial) = kzg_commit(&second);
+        assert_ne!(first_commitment.as_inner(), second_commitment.as_inner());
+        let item = |commitment: &KzgCommitment, polynomial: &KZGPolynomial, chunks: &[Chunk], indices: Vec<usize>| {
+            let proof = kzg_multi_prove_with_polynomial(polynomial, &indices);
+            (commitment.clone(), indices.clone(), indices.iter().map(|i| chunks[*i].clone()).collect::<Vec<_>>(), proof)
+        };
+        let items = vec![
+            item(&first_commitment, &first_polynomial, &first, vec![0, 1]),
+            item(&second_commitment, &second_polynomial, &second, vec![3, 5, 7]),
+            item(&first_commitment, &first_polynomial, &first, vec![9]),
+        ];
+        assert!(kzg_batch_verify_multi(&items));
+
+        // the per-item checks of kzg_multi_verify apply to every item
+        assert!(!kzg_batch_verify_multi(&[]));
+        let mut mismatched = items.clone();
+        mismatched[1].1.pop();
+        assert!(!kzg_batch_verify_multi(&mismatched));
+        let mut identity = items.clone();
+        identity[2].0 = KzgCommitment::new(G1::zero());
+        assert!(!kzg_batch_verify_multi(&identity));
+
+        // a proof opened against the other commitment fails, even over identical chunks
+        let mut crossed = items.clone();
+        crossed[1].3 = kzg_multi_prove_with_polynomial(&first_polynomial, &[3, 5, 7]);
+        assert!(!kzg_batch_verify_multi(&crossed));
+    }
+
+    #[test]
+    fn test_precomputed_polynomial_proofs_match() {
+        let chunks = get_sample_chunks();
+        let (commitment, polynomial) = kzg_commit(&chunks);
+
+        let proof = kzg_prove_with_polynomial(&polynomial, 3);
+        assert_eq!(proof.as_inner(), kzg_prove(&chunks, 3).as_inner());
+        assert!(kzg_verify(&chunks[3], 3, commitment.clone(), proof));
+
+        let indices = [0, 4, 9, 17];
+        let selected = indices.iter().map(|&i| chunks[i].clone()).collect::<Vec<_>>();
+        let proof = kzg_multi_prove_with_polynomial(&polynomial, &indices);
+        assert_eq!(proof.as_inner(), kzg_multi_prove(&chunks, &indices).as_inner());
+        assert!(kzg_multi_verify(&selected, &indices, commitment, proof));
+    }
+
+    #[test]
+    fn test_kzg_commit_with_different_degrees() {
+        let chunks = get_sample_chunks();
+
+        for count in [8, chunks.len()] {
+            let chunks = chunks[..count].to_vec();
+            let (commitment, polynomial) = kzg_commit(&chunks);
+            assert_eq!(polynomial.len(), count);
+
+            let proof = kzg_prove(&chunks, count - 1);
+            assert!(kzg_verify(&chunks[count - 1], count - 1, commitment.clone(), proof));
+
+            let indices = (0..count).collect::<Vec<_>>();
+            let proof = kzg_multi_prove(&chunks, &indices);
+            assert!(kzg_multi_verify(&chunks, &indices, commitment, proof));
+        }
+
+        assert!(Arc::ptr_eq(&get_kzg_instance_for_degree(7), &get_kzg_instance_for_degree(7)));
+    }
+
     #[test]
     fn test_kzg_manager_with_total_shards() {
         use common::constants::TOTAL_SHARDS;
@@ -267,4 +503,24 @@ mod tests {
         let is_valid = kzg_multi_verify(&subset_chunks, &subset_indices, commitment, proof);
         assert!(is_valid, "Multi-proof verification should succeed for subset of chunks");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_truncated_ceremony_is_too_small() {
+        let mut ceremony: serde_json::Value = serde_json::from_str(ETH_CEREMONY).unwrap();
+        let g1_powers = ceremony["contributions"][0]["powersOfTau"]["G1Powers"].as_array_mut().unwrap();
+        g1_powers.truncate(8);
+
+        let path = std::env::temp_dir().join(format!("poda-truncated-ceremony-{}.json", std::process::id()));
+        std::fs::write(&path, ceremony.to_string()).unwrap();
+        let result = init_from_path(&path, 24);
+        std::fs::remove_file(&path).unwrap();
+
+        let err = result.unwrap_err();
+        assert_eq!(err, KzgError::CeremonyTooSmall { have: 8, need: 24 });
+        assert_eq!(err.to_string(), "Ceremony only has 8 G1 powers, but 24 are needed");
+        assert!(CEREMONY.get().is_none());
+
+        assert!(matches!(init_from_path(std::env::temp_dir().join("poda-missing-ceremony.json"), 24), Err(KzgError::InvalidCeremony(_))));
+        assert!(load_ceremony(ETH_CEREMONY, 23).is_ok());
+    }
+}
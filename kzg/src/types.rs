@@ -1,4 +1,4 @@
-use std::io::Cursor;
+use anyhow::{anyhow, bail, Result};
 use ark_bls12_381::G1Projective as G1;
 use ark_serialize::{CanonicalSerialize, CanonicalDeserialize};
 use serde::{Serialize, Deserialize};
@@ -18,16 +18,16 @@ pub const G1_COMPRESSED_SIZE: usize = 48;
 /// 
 /// // KzgProof example
 /// let proof = KzgProof::new(g1_point);
-/// let proof_bytes: [u8; G1_COMPRESSED_SIZE] = proof.to_bytes().unwrap();
+/// let proof_bytes: [u8; G1_COMPRESSED_SIZE] = proof.to_bytes();
 /// assert_eq!(proof_bytes.len(), 48);
-/// let reconstructed_proof = KzgProof::from_bytes(proof_bytes).unwrap();
+/// let reconstructed_proof = KzgProof::from_bytes(&proof_bytes).unwrap();
 /// assert_eq!(proof.into_inner(), reconstructed_proof.into_inner());
 /// 
 /// // KzgCommitment example
 /// let commitment = KzgCommitment::new(g1_point);
-/// let commitment_bytes: [u8; G1_COMPRESSED_SIZE] = commitment.to_bytes().unwrap();
+/// let commitment_bytes: [u8; G1_COMPRESSED_SIZE] = commitment.to_bytes();
 /// assert_eq!(commitment_bytes.len(), 48);
-/// let reconstructed_commitment = KzgCommitment::from_bytes(commitment_bytes).unwrap();
+/// let reconstructed_commitment = KzgCommitment::from_bytes(&commitment_bytes).unwrap();
 /// assert_eq!(commitment.into_inner(), reconstructed_commitment.into_inner());
 /// ```
 // Generic G1 serialization helper
@@ -36,7 +36,7 @@ mod g1_serde {
     use serde::{Serializer, Deserializer};
     use ark_std::io::Cursor;
 
-    pub fn serialize<S>(g1: &G1, serializer: S) -> Result<S::Ok, S::Error>
+    pub fn serialize<S>(g1: &G1, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
@@ -59,7 +59,7 @@ mod g1_serde {
         serializer.serialize_bytes(&fixed_bytes)
     }
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<G1, D::Error>
+    pub fn deserialize<'de, D>(deserializer: D) -> std::result::Result<G1, D::Error>
     where
         D: Deserializer<'de>,
     {
@@ -78,39 +78,35 @@ mod g1_serde {
     }
 }
 
-// Generic helper trait for G1-based types
-trait G1Bytes {
-    fn g1_to_bytes(g1: &G1) -> Result<[u8; G1_COMPRESSED_SIZE], String> {
-        let mut bytes = Vec::new();
-        g1.serialize_compressed(&mut bytes)
-            .map_err(|e| format!("Failed to serialize G1: {}", e))?;
-        
-        if bytes.len() != G1_COMPRESSED_SIZE {
-            return Err(format!(
-                "G1 serialization size {} does not match expected size {}",
-                bytes.len(),
-                G1_COMPRESSED_SIZE
-            ));
-        }
-        
-        let mut fixed_bytes = [0u8; G1_COMPRESSED_SIZE];
-        fixed_bytes.copy_from_slice(&bytes);
-        Ok(fixed_bytes)
-    }
-    
-    fn g1_from_bytes(bytes: [u8; G1_COMPRESSED_SIZE]) -> Result<G1, String> {
-        G1::deserialize_compressed(&bytes[..])
-            .map_err(|e| format!("Failed to deserialize G1: {}", e))
+fn g1_to_bytes(g1: &G1) -> [u8; G1_COMPRESSED_SIZE] {
+    let mut bytes = [0u8; G1_COMPRESSED_SIZE];
+    // a compressed BLS12-381 G1 point is always exactly G1_COMPRESSED_SIZE bytes
+    g1.serialize_compressed(&mut bytes[..]).expect("G1 point fits its compressed size");
+    bytes
+}
+
+// Deserialization checks the point is on the curve and in the prime order subgroup
+fn g1_from_bytes(bytes: &[u8]) -> Result<G1> {
+    if bytes.len() != G1_COMPRESSED_SIZE {
+        bail!("Expected {} bytes for a compressed G1 point, got {}", G1_COMPRESSED_SIZE, bytes.len());
     }
+    G1::deserialize_compressed(bytes).map_err(|e| anyhow!("Failed to deserialize G1: {}", e))
+}
+
+// Bytes of the compressed encoding shown by the Debug impls, the full points are too long for logs
+const DEBUG_PREFIX_SIZE: usize = 8;
+
+fn fmt_g1_prefix(name: &str, g1: &G1, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}(0x{}…)", name, hex::encode(&g1_to_bytes(g1)[..DEBUG_PREFIX_SIZE]))
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct KzgProof {
     #[serde(with = "g1_serde")]
     pub proof: G1,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct KzgCommitment {
     #[serde(with = "g1_serde")]
     pub commitment: G1,
@@ -129,15 +125,14 @@ impl KzgProof {
         &self.proof
     }
     
-    /// Returns the proof as a fixed-size byte array
-    pub fn to_bytes(&self) -> Result<[u8; G1_COMPRESSED_SIZE], String> {
-        Self::g1_to_bytes(&self.proof)
+    /// Returns the proof as a compressed G1 point
+    pub fn to_bytes(&self) -> [u8; G1_COMPRESSED_SIZE] {
+        g1_to_bytes(&self.proof)
     }
     
-    /// Creates a KzgProof from a fixed-size byte array
-    pub fn from_bytes(bytes: [u8; G1_COMPRESSED_SIZE]) -> Result<Self, String> {
-        let proof = Self::g1_from_bytes(bytes)?;
-        Ok(Self::new(proof))
+    /// Parses a compressed G1 point, rejecting wrong lengths and invalid points
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Self::new(g1_from_bytes(bytes)?))
     }
 }
 
@@ -154,21 +149,57 @@ impl KzgCommitment {
         &self.commitment
     }
     
-    /// Returns the commitment as a fixed-size byte array
-    pub fn to_bytes(&self) -> Result<[u8; G1_COMPRESSED_SIZE], String> {
-        Self::g1_to_bytes(&self.commitment)
+    /// Returns the commitment as a compressed G1 point, the form stored on-chain
+    pub fn to_bytes(&self) -> [u8; G1_COMPRESSED_SIZE] {
+        g1_to_bytes(&self.commitment)
     }
     
-    /// Creates a KzgCommitment from a fixed-size byte array
-    pub fn from_bytes(bytes: [u8; G1_COMPRESSED_SIZE]) -> Result<Self, String> {
-        let commitment = Self::g1_from_bytes(bytes)?;
-        Ok(Self::new(commitment))
+    /// Parses a compressed G1 point, rejecting wrong lengths and invalid points
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Self::new(g1_from_bytes(bytes)?))
+    }
+}
+
+impl std::fmt::Debug for KzgProof {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_g1_prefix("KzgProof", &self.proof, f)
+    }
+}
+
+impl std::fmt::Debug for KzgCommitment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_g1_prefix("KzgCommitment", &self.commitment, f)
+    }
+}
+
+impl PartialEq for KzgProof {
+    fn eq(&self, other: &Self) -> bool {
+        self.proof == other.proof
+    }
+}
+
+impl Eq for KzgProof {}
+
+// hashes the compressed encoding, which is equal exactly when the points are
+impl std::hash::Hash for KzgProof {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state);
+    }
+}
+
+impl PartialEq for KzgCommitment {
+    fn eq(&self, other: &Self) -> bool {
+        self.commitment == other.commitment
     }
 }
 
-// Implement the helper trait for both types
-impl G1Bytes for KzgProof {}
-impl G1Bytes for KzgCommitment {}
+impl Eq for KzgCommitment {}
+
+impl std::hash::Hash for KzgCommitment {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state);
+    }
+}
 
 impl From<G1> for KzgProof {
     fn from(proof: G1) -> Self {
@@ -194,25 +225,48 @@ impl From<KzgCommitment> for G1 {
     }
 }
 
+impl TryFrom<&[u8]> for KzgCommitment {
+    type Error = anyhow::Error;
+    fn try_from(bytes: &[u8]) -> Result<Self> {
+        Self::from_bytes(bytes)
+    }
+}
+
 impl TryFrom<Bytes> for KzgCommitment {
-    type Error = String;
-    fn try_from(bytes: Bytes) -> Result<Self, Self::Error> {
-        let bytes = bytes.into_iter().collect::<Vec<u8>>();
-        let commitment = G1::deserialize_compressed(&mut Cursor::new(bytes)).map_err(|e| format!("Failed to deserialize G1: {}", e))?;
-        Ok(Self::new(commitment))
+    type Error = anyhow::Error;
+    fn try_from(bytes: Bytes) -> Result<Self> {
+        Self::from_bytes(&bytes)
+    }
+}
+
+impl From<KzgCommitment> for Bytes {
+    fn from(commitment: KzgCommitment) -> Self {
+        Bytes::from(commitment.to_bytes())
     }
 }
 
-impl TryInto<Bytes> for KzgCommitment {
-    type Error = String;
-    fn try_into(self) -> Result<Bytes, Self::Error> {
-        let bytes = self.to_bytes().map_err(|e| format!("Failed to convert KzgCommitment to Bytes: {}", e))?;
-        Ok(Bytes::from(bytes))
+impl TryFrom<&[u8]> for KzgProof {
+    type Error = anyhow::Error;
+    fn try_from(bytes: &[u8]) -> Result<Self> {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl TryFrom<Bytes> for KzgProof {
+    type Error = anyhow::Error;
+    fn try_from(bytes: Bytes) -> Result<Self> {
+        Self::from_bytes(&bytes)
+    }
+}
+
+impl From<KzgProof> for Bytes {
+    fn from(proof: KzgProof) -> Self {
+        Bytes::from(proof.to_bytes())
     }
 }
 
 /// Validates that a G1 point serializes to the expected compressed size
-pub fn validate_g1_size(g1: &G1) -> Result<(), String> {
+pub fn validate_g1_size(g1: &G1) -> std::result::Result<(), String> {
     let mut bytes = Vec::new();
     g1.serialize_compressed(&mut bytes)
         .map_err(|e| format!("Failed to serialize G1: {}", e))?;
@@ -277,11 +331,11 @@ mod tests {
         let kzg_proof = KzgProof::new(g1_point);
         
         // Test conversion to fixed-size array
-        let bytes = kzg_proof.to_bytes().expect("Should convert to bytes successfully");
+        let bytes = kzg_proof.to_bytes();
         assert_eq!(bytes.len(), G1_COMPRESSED_SIZE, "Should be exactly 48 bytes");
         
         // Test conversion back from fixed-size array
-        let reconstructed = KzgProof::from_bytes(bytes).expect("Should reconstruct from bytes successfully");
+        let reconstructed = KzgProof::from_bytes(&bytes).expect("Should reconstruct from bytes successfully");
         assert_eq!(g1_point, reconstructed.into_inner(), "Reconstructed proof should match original");
     }
 
@@ -292,8 +346,8 @@ mod tests {
         let original_proof = KzgProof::new(g1_point);
         
         // Round trip: proof -> bytes -> proof
-        let bytes = original_proof.to_bytes().expect("Should convert to bytes");
-        let reconstructed_proof = KzgProof::from_bytes(bytes).expect("Should reconstruct from bytes");
+        let bytes = original_proof.to_bytes();
+        let reconstructed_proof = KzgProof::from_bytes(&bytes).expect("Should reconstruct from bytes");
         
         assert_eq!(original_proof.into_inner(), reconstructed_proof.into_inner(), 
                    "Round trip should preserve the G1 point");
@@ -319,11 +373,11 @@ mod tests {
         let kzg_commitment = KzgCommitment::new(g1_point);
         
         // Test conversion to fixed-size array
-        let bytes = kzg_commitment.to_bytes().expect("Should convert to bytes successfully");
+        let bytes = kzg_commitment.to_bytes();
         assert_eq!(bytes.len(), G1_COMPRESSED_SIZE, "Should be exactly 48 bytes");
         
         // Test conversion back from fixed-size array
-        let reconstructed = KzgCommitment::from_bytes(bytes).expect("Should reconstruct from bytes successfully");
+        let reconstructed = KzgCommitment::from_bytes(&bytes).expect("Should reconstruct from bytes successfully");
         assert_eq!(g1_point, reconstructed.into_inner(), "Reconstructed commitment should match original");
     }
 
@@ -334,8 +388,8 @@ mod tests {
         let original_commitment = KzgCommitment::new(g1_point);
         
         // Round trip: commitment -> bytes -> commitment
-        let bytes = original_commitment.to_bytes().expect("Should convert to bytes");
-        let reconstructed_commitment = KzgCommitment::from_bytes(bytes).expect("Should reconstruct from bytes");
+        let bytes = original_commitment.to_bytes();
+        let reconstructed_commitment = KzgCommitment::from_bytes(&bytes).expect("Should reconstruct from bytes");
         
         assert_eq!(original_commitment.into_inner(), reconstructed_commitment.into_inner(), 
                    "Round trip should preserve the G1 point");
@@ -364,7 +418,7 @@ mod tests {
         let original_commitment = KzgCommitment::new(g1_point);
         
         // Convert KzgCommitment to Bytes (clone to avoid ownership issues)
-        let bytes: Bytes = original_commitment.clone().try_into().expect("Should convert to bytes");
+        let bytes: Bytes = original_commitment.clone().into();
         
         // Convert Bytes back to KzgCommitment
         let reconstructed_commitment: KzgCommitment = bytes.try_into().expect("Should convert from bytes");
@@ -373,4 +427,47 @@ mod tests {
         assert_eq!(original_commitment.into_inner(), reconstructed_commitment.into_inner(), 
                    "Round trip conversion should preserve the G1 point");
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_kzg_proof_bytes_roundtrip_via_try_from() {
+        let mut rng = ark_std::test_rng();
+        let proof = KzgProof::new(G1::rand(&mut rng));
+
+        let bytes: Bytes = proof.clone().into();
+        let reconstructed = KzgProof::try_from(bytes).expect("Should convert from bytes");
+        assert_eq!(proof.into_inner(), reconstructed.into_inner());
+    }
+
+    #[test]
+    fn test_malformed_bytes_are_rejected() {
+        let mut rng = ark_std::test_rng();
+        let bytes = KzgCommitment::new(G1::rand(&mut rng)).to_bytes();
+
+        assert!(KzgCommitment::from_bytes(&bytes[..G1_COMPRESSED_SIZE - 1]).is_err());
+        assert!(KzgCommitment::from_bytes(&[bytes.as_slice(), &[0]].concat()).is_err());
+        assert!(KzgProof::try_from(&[][..]).is_err());
+
+        // right length, but not a point on the curve
+        assert!(KzgCommitment::from_bytes(&[0xffu8; G1_COMPRESSED_SIZE]).is_err());
+        assert!(KzgProof::try_from(Bytes::from([0x13u8; G1_COMPRESSED_SIZE])).is_err());
+    }
+
+    #[test]
+    fn test_debug_is_short_and_equality_follows_the_point() {
+        let mut rng = ark_std::test_rng();
+        let g1_point = G1::rand(&mut rng);
+        let commitment = KzgCommitment::new(g1_point);
+
+        let debug = format!("{:?}", commitment);
+        let prefix = hex::encode(&commitment.to_bytes()[..DEBUG_PREFIX_SIZE]);
+        assert_eq!(debug, format!("KzgCommitment(0x{}…)", prefix));
+        assert!(format!("{:?}", KzgProof::new(g1_point)).starts_with("KzgProof(0x"));
+
+        // the same point in different projective coordinates still compares and hashes equal
+        let same = KzgCommitment::from_bytes(&commitment.to_bytes()).unwrap();
+        assert_eq!(commitment, same);
+        assert_ne!(commitment, KzgCommitment::new(G1::rand(&mut rng)));
+        let set = std::collections::HashSet::from([commitment, same]);
+        assert_eq!(set.len(), 1);
+    }
+}
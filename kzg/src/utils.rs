@@ -101,21 +101,14 @@ pub fn interpolate<E:Field>(points: &[E], values: &[E]) -> Result<Vec<E>, &'stat
     Ok(result)
 }
 
-// helper function to get the roots of unity of a polynomial
+// helper function to get the primitive `domain_size`-th root of unity, for a power of two
+// `domain_size` no larger than the field's two-adic subgroup
 #[allow(dead_code)]
-pub fn get_omega<E:PrimeField>(coefficients: &[E]) -> E {
-    let mut coefficients = coefficients.to_vec();
-    let n = coefficients.len() - 1;
-    if !n.is_power_of_two() {
-        let num_coeffs = coefficients.len().checked_next_power_of_two().unwrap();
-        // pad the coefficients with zeros to the nearest power of two
-        for i in coefficients.len()..num_coeffs {
-            coefficients[i] = E::ZERO;
-        }
-    }
+pub fn get_omega<E:PrimeField>(domain_size: usize) -> E {
+    assert!(domain_size.is_power_of_two(), "Domain size must be a power of two");
+    let exp = log2(domain_size);
+    assert!(exp <= E::TWO_ADICITY, "Domain size exceeds the field's two-adicity");
 
-    let m = coefficients.len();
-    let exp = log2(m);
     let mut omega = E::TWO_ADIC_ROOT_OF_UNITY;
     for _ in exp..E::TWO_ADICITY {
         omega.square_in_place();
@@ -131,4 +124,27 @@ pub fn scalar_mul<E:Field>(poly: &[E], scalar: E) -> Vec<E> {
         result.push(*coeff * scalar);
     }
     result    
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+
+    #[test]
+    fn test_get_omega_is_primitive_root_of_domain() {
+        for domain_size in [2usize, 4, 8, 16, 32, 1024] {
+            let omega = get_omega::<Fr>(domain_size);
+            assert_eq!(omega.pow([domain_size as u64]), Fr::ONE);
+            assert_ne!(omega.pow([(domain_size / 2) as u64]), Fr::ONE);
+        }
+
+        assert_eq!(get_omega::<Fr>(1), Fr::ONE);
+    }
+
+    #[test]
+    #[should_panic(expected = "power of two")]
+    fn test_get_omega_rejects_non_power_of_two() {
+        get_omega::<Fr>(24);
+    }
+}
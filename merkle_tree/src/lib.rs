@@ -3,21 +3,36 @@ mod tree;
 
 use common::types::{Chunk, FixedBytes};
 use anyhow::Result;
-pub use crate::tree::{MerkleProof, MerkleTree, StandardMerkleTree};
+pub use crate::tree::{MerkleMultiProof, MerkleProof, MerkleTree, StandardMerkleTree};
 
 pub fn gen_merkle_tree(chunks: &[Chunk]) -> StandardMerkleTree {
-    let leaves = chunks.iter().map(|chunk| chunk.hash()).collect::<Vec<_>>();
-    StandardMerkleTree::new(leaves)
+    StandardMerkleTree::from_leaves(chunks.iter().map(Chunk::hash))
 }
 
 pub fn gen_proof(merkle_tree: &StandardMerkleTree, leaf: Chunk) -> Result<MerkleProof> {
     merkle_tree.generate_proof(leaf.hash())
 }
 
+// `Chunk::hash` covers the chunk index, so a proof for one chunk never verifies another chunk's
+// data or index
 pub fn verify_proof(root: FixedBytes<32>, leaf: &Chunk, proof: MerkleProof) -> bool {
     MerkleTree::verify_proof(root, leaf.hash(), proof)
 }
 
+pub fn gen_multi_proof(merkle_tree: &StandardMerkleTree, leaves: &[Chunk]) -> Result<MerkleMultiProof> {
+    let hashes = leaves.iter().map(|chunk| chunk.hash()).collect::<Vec<_>>();
+    merkle_tree.generate_multi_proof(&hashes).ok_or_else(|| anyhow::anyhow!("leaf not found"))
+}
+
+pub fn verify_multi_proof(root: FixedBytes<32>, leaves: &[Chunk], proof: MerkleMultiProof) -> bool {
+    if leaves.is_empty() {
+        return false;
+    }
+
+    let hashes = leaves.iter().map(|chunk| chunk.hash()).collect::<Vec<_>>();
+    MerkleTree::verify_multi_proof(root, &hashes, proof).unwrap_or(false)
+}
+
 #[cfg(test)]
 mod tests {
     use common::types::{keccak256, SolValue};
@@ -60,4 +75,30 @@ mod tests {
         let proof = gen_proof(&merkle_tree, chunks[0].clone()).unwrap();
         assert!(!verify_proof(merkle_tree.root(), &chunks[1], proof));
     }
+
+    #[test]
+    fn test_merkle_proof_is_bound_to_chunk_index() {
+        let chunks = (0..8).map(|i| Chunk { index: i, data: vec![i as u8; 4] }).collect::<Vec<_>>();
+        let merkle_tree = gen_merkle_tree(&chunks);
+        let proof = gen_proof(&merkle_tree, chunks[2].clone()).unwrap();
+        assert!(verify_proof(merkle_tree.root(), &chunks[2], proof.clone()));
+
+        // the same data claimed at another index, including a leaf at the same depth
+        for index in [3, 5] {
+            let relabeled = Chunk { index, ..chunks[2].clone() };
+            assert!(!verify_proof(merkle_tree.root(), &relabeled, proof.clone()));
+        }
+    }
+
+    #[test]
+    fn test_merkle_tree_multi_proof() {
+        let chunks = get_sample_chunks();
+        let merkle_tree = gen_merkle_tree(&chunks);
+
+        let subset = vec![chunks[3].clone(), chunks[1].clone()];
+        let proof = gen_multi_proof(&merkle_tree, &subset).unwrap();
+        assert!(verify_multi_proof(merkle_tree.root(), &subset, proof.clone()));
+        assert!(!verify_multi_proof(merkle_tree.root(), &[chunks[3].clone(), chunks[2].clone()], proof.clone()));
+        assert!(!verify_multi_proof(merkle_tree.root(), &chunks[..1], proof));
+    }
 }
\ No newline at end of file
@@ -30,6 +30,9 @@ impl Deref for StandardMerkleTree {
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 #[derive(Default)]
 pub struct MerkleProof {
+    /// Sibling hashes from the leaf up. Pairs are hashed in sorted order to match the on-chain
+    /// verifier, so the path does not say where the leaf sits; a chunk is bound to its index
+    /// because `Chunk::hash` covers it.
     pub path: Vec<Hash>,
 }
 
@@ -59,6 +62,13 @@ fn commutative_hash_pair(left: Hash, right: Hash) -> Hash {
     }
 }
 
+// Fills every node above the leaf level, bottom up
+fn hash_inner_nodes(tree: &mut [Hash], leaves_len: usize) {
+    for i in (0..tree.len() - leaves_len).rev() {
+        tree[i] = commutative_hash_pair(tree[left_child_index(i)], tree[right_child_index(i)]);
+    }
+}
+
 fn left_child_index(index: usize) -> usize {
     2 * index + 1
 }
@@ -72,7 +82,7 @@ fn parent_index(index: usize) -> usize {
 }
 
 fn sibling_index(index: usize) -> usize {
-    if index % 2 == 0 { index - 1 } else { index + 1 }
+    if index.is_multiple_of(2) { index - 1 } else { index + 1 }
 }
 
 fn is_leaf_index(tree_len: usize, index: usize) -> bool {
@@ -109,6 +119,22 @@ impl StandardMerkleTree {
         Self { tree, indices }
     }
 
+    /// Builds the same tree as `new`, sorting the leaves inside the tree's own storage so the
+    /// caller never has to collect them
+    pub fn from_leaves(leaves: impl IntoIterator<Item = Hash>) -> Self {
+        let mut leaves = leaves.into_iter().collect::<Vec<_>>();
+        leaves.sort();
+        let leaves_len = leaves.len();
+
+        let tree = MerkleTree::from_leaf_vec(leaves);
+        let tree_len = tree.length();
+        let indices = (tree_len - leaves_len..tree_len)
+            .map(|i| (tree.tree[i], i))
+            .collect::<HashMap<Hash, usize>>();
+
+        Self { tree, indices }
+    }
+
     pub fn generate_proof(&self, leaf: Hash) -> Result<MerkleProof> {
         if let Some(&tree_index) = self.indices.get(&leaf) {
             self.tree.generate_proof(tree_index)
@@ -158,13 +184,24 @@ impl MerkleTree {
         for (i, leaf) in leaves.iter().enumerate() {
             tree[tree_len - 1 - i] = *leaf;
         }
+        hash_inner_nodes(&mut tree, leaves_len);
 
-        for i in (0..tree_len - leaves_len).rev() {
-            let left_leaf = tree[left_child_index(i)];
-            let right_leaf = tree[right_child_index(i)];
-            tree[i] = commutative_hash_pair(left_leaf, right_leaf);
+        Self { tree }
+    }
+
+    // Same layout as `new`, but the leaves arrive in their own vector which becomes the tree
+    fn from_leaf_vec(mut tree: Vec<Hash>) -> Self {
+        let leaves_len = tree.len();
+        if leaves_len == 0 {
+            return MerkleTree { tree: vec![Hash::default()] };
         }
 
+        // leaves sit reversed at the end, behind the inner nodes
+        tree.reverse();
+        tree.resize(2 * leaves_len - 1, Hash::default());
+        tree.rotate_right(leaves_len - 1);
+        hash_inner_nodes(&mut tree, leaves_len);
+
         Self { tree }
     }
 
@@ -267,6 +304,10 @@ impl MerkleTree {
         let mut path = proof.path.to_vec();
 
         for flag in proof.flags {
+            if stack.is_empty() || (flag && stack.len() < 2) {
+                bail!("invalid multiproof: flags do not match leaves");
+            }
+
             let a = stack.remove(0);
             let b = if flag {
                 stack.remove(0)
@@ -280,7 +321,7 @@ impl MerkleTree {
         let reconstructed_root = match (stack.len(), path.len()) {
             (1, 0) => stack.remove(0),
             (0, 1) => path.remove(0),
-            _ => panic!("invalid multiproof: invalid total hashes"),
+            _ => bail!("invalid multiproof: invalid total hashes"),
         };
 
         Ok(root == reconstructed_root)
@@ -305,6 +346,22 @@ mod test {
         assert!(MerkleTree::verify_proof(tree.root(), leaf, proof.clone()));
     }
 
+    #[test]
+    pub fn test_tree_from_leaf_iterator_matches_vec() {
+        for count in [0u32, 1, 2, 7, 24] {
+            let leaves = (0..count).map(|i| StandardMerkleTree::hash_leaf(i.to_string(), i.abi_encode().hash_custom())).collect::<Vec<_>>();
+            let from_vec = StandardMerkleTree::new(leaves.clone());
+            let from_iter = StandardMerkleTree::from_leaves(leaves.iter().copied());
+
+            assert_eq!(from_iter.root(), from_vec.root());
+            assert_eq!(from_iter.length(), from_vec.length());
+            for leaf in &leaves {
+                assert_eq!(from_iter.generate_proof(*leaf).unwrap(), from_vec.generate_proof(*leaf).unwrap());
+            }
+            assert_eq!(from_iter.generate_multi_proof(&leaves), from_vec.generate_multi_proof(&leaves));
+        }
+    }
+
     #[test]
     pub fn test_standard_tree_multi_proof() {
         let leaves = vec![
@@ -316,4 +373,24 @@ mod test {
         let proof = tree.generate_multi_proof(&leaves).unwrap();
         assert!(MerkleTree::verify_multi_proof(tree.root(), &leaves, proof.clone()).unwrap());
     }
-}
\ No newline at end of file
+
+    fn sample_tree(count: u32) -> (StandardMerkleTree, Vec<Hash>) {
+        let leaves = (0..count).map(|i| StandardMerkleTree::hash_leaf(i.to_string(), i.abi_encode().hash_custom())).collect::<Vec<_>>();
+        (StandardMerkleTree::new(leaves.clone()), leaves)
+    }
+
+    #[test]
+    pub fn test_proof_serde_round_trip() {
+        let (tree, leaves) = sample_tree(7);
+        for leaf in leaves {
+            let proof = tree.generate_proof(leaf).unwrap();
+            let json = serde_json::to_value(&proof).unwrap();
+            // a bare path, the format providers and dispensers already exchange
+            assert_eq!(json, serde_json::json!({ "path": proof.path }));
+
+            let decoded: MerkleProof = serde_json::from_value(json).unwrap();
+            assert_eq!(decoded, proof);
+            assert!(MerkleTree::verify_proof(tree.root(), leaf, decoded));
+        }
+    }
+}
@@ -1,13 +1,14 @@
 use async_trait::async_trait;
 use mockall::automock;
-use std::{time::Duration};
+use std::{future::Future, sync::Arc, time::Duration};
+use futures::{Stream, StreamExt};
 use alloy::{primitives::FixedBytes, sol};
 use alloy::primitives::U256;
 use anyhow::{Result};
 use pod_sdk::{network::PodNetwork, provider::{PodProvider, PodProviderBuilder}, Address, EthereumWallet, PrivateKeySigner, Provider, Bytes};
 use crate::client::Poda::PodaInstance;
-pub use Poda::{ProviderInfo, Commitment, ChallengeInfo};
-use common::log::info;
+pub use Poda::{ProviderInfo, Commitment, ChallengeInfo, ChunkChallenge};
+use common::{log::{info, warn}, types::Chunk};
 
 sol!(
     #[sol(rpc)]
@@ -20,23 +21,32 @@ sol!(
 #[async_trait]
 pub trait PodaClientTrait {
     async fn register_provider(&self, name: String, url: String, stake: u128) -> Result<()>;
+    // replaces the URL this client's signer registered with
+    async fn update_provider_url(&self, url: String) -> Result<()>;
     async fn submit_commitment(&self, commitment: FixedBytes<32>, size: u32, total_chunks: u16, required_chunks: u16, kzg_commitment: Bytes) -> Result<()>;
     async fn submit_chunk_attestations(&self, commitment: FixedBytes<32>, chunk_ids: Vec<u16>) -> Result<()>;
+    // attests the chunks with possession proofs bound to this client's signer
+    async fn submit_chunk_attestations_with_proof(&self, commitment: FixedBytes<32>, chunks: Vec<Chunk>) -> Result<()>;
+    async fn get_possession_proof(&self, commitment: FixedBytes<32>, chunk_id: u16) -> Result<FixedBytes<32>>;
     async fn get_providers(&self) -> Result<Vec<ProviderInfo>>;
     async fn get_eligible_providers(&self) -> Result<Vec<ProviderInfo>>;
     async fn get_provider_info(&self, provider: Address) -> Result<ProviderInfo>;
+    async fn get_min_stake(&self) -> Result<u128>;
     async fn commitment_exists(&self, commitment: FixedBytes<32>) -> Result<bool>;
     async fn is_commitment_recoverable(&self, commitment: FixedBytes<32>) -> Result<bool>;
     async fn get_commitment_info(&self, commitment: FixedBytes<32>) -> Result<(Commitment, bool)>;
+    // reads the commitment as of the current block once that block has `confirmations` blocks on top
+    async fn get_commitment_info_confirmed(&self, commitment: FixedBytes<32>, confirmations: u64) -> Result<(Commitment, bool)>;
     async fn get_available_chunks(&self, commitment: FixedBytes<32>) -> Result<Vec<u16>>;
     async fn get_provider_chunks(&self, commitment: FixedBytes<32>, provider: Address) -> Result<Vec<u16>>;
     async fn get_chunk_owner(&self, commitment: FixedBytes<32>, chunk_id: u16) -> Result<Address>;
     async fn is_chunk_available(&self, commitment: FixedBytes<32>, chunk_id: u16) -> Result<bool>;
     async fn get_multiple_commitment_status(&self, commitment_list: Vec<FixedBytes<32>>) -> Result<Vec<bool>>;
+    async fn get_chunk_info_batch(&self, commitment: FixedBytes<32>, chunk_ids: Vec<u16>) -> Result<Vec<(Address, bool)>>;
     async fn issue_chunk_challenge(&self, commitment: FixedBytes<32>, chunk_id: u16, provider: Address) -> Result<ChallengeInfo>;
     async fn respond_to_chunk_challenge(&self, commitment: FixedBytes<32>, chunk_id: u16, chunk_data: Bytes, proof: Vec<FixedBytes<32>>) -> Result<()>;
     async fn deploy_poda(provider: PodProvider, owner: Address, min_stake: u128) -> Result<Address>;
-    async fn wait_for_availability(&self, commitment: FixedBytes<32>) -> Result<()>;
+    async fn wait_for_availability(&self, commitment: FixedBytes<32>, timeout: Duration) -> Result<()>;
     async fn verify_chunk_proof(&self, proof: Vec<FixedBytes<32>>, root: FixedBytes<32>, chunk_index: u16, chunk_data: Bytes) -> Result<bool>;
     async fn get_provider_active_challenges(&self, provider: Address) -> Result<Vec<ChallengeInfo>>;
     async fn get_provider_expired_challenges(&self, provider: Address) -> Result<Vec<ChallengeInfo>>;
@@ -44,16 +54,241 @@ pub trait PodaClientTrait {
     async fn get_chunk_challenge(&self, commitment: FixedBytes<32>, chunk_id: u16, provider: Address) -> Result<ChallengeInfo>;
     async fn is_challenge_expired(&self, commitment: FixedBytes<32>, chunk_id: u16, provider: Address) -> Result<bool>;
     async fn slash_expired_challenge(&self, commitment: FixedBytes<32>, chunk_id: u16, provider: Address) -> Result<()>;
+    async fn get_storage_efficiency(&self, commitment: FixedBytes<32>) -> Result<StorageEfficiency>;
+    async fn get_network_storage_stats(&self) -> Result<NetworkStorageStats>;
+}
+
+pub const DEFAULT_AVAILABILITY_TIMEOUT: Duration = Duration::from_secs(60);
+const AVAILABILITY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+// Blocks on top of the one availability is read from. 0 trusts the latest block
+pub const DEFAULT_AVAILABILITY_CONFIRMATIONS: u64 = 0;
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Takes the current block from `block_number`, waits until the chain is `confirmations` blocks
+/// past it and then runs `read_at` against that block, so a reorged read is never trusted
+pub async fn read_confirmed<B, BFut, R, RFut, T>(mut block_number: B, read_at: R, confirmations: u64, interval: Duration) -> Result<T>
+where
+    B: FnMut() -> BFut,
+    BFut: Future<Output = Result<u64>>,
+    R: FnOnce(u64) -> RFut,
+    RFut: Future<Output = Result<T>>,
+{
+    let read_block = block_number().await?;
+    let confirmed_at = read_block + confirmations;
+    loop {
+        let head = block_number().await?;
+        if head >= confirmed_at {
+            break;
+        }
+
+        info!("Waiting for block {} to be confirmed... {}/{} confirmations", read_block, head.saturating_sub(read_block), confirmations);
+        tokio::time::sleep(interval).await;
+    }
+
+    read_at(read_block).await
+}
+
+/// Polls the commitment until it is recoverable, failing once `timeout` has elapsed
+pub async fn poll_until_recoverable<T: PodaClientTrait + ?Sized>(pod: &T, commitment: FixedBytes<32>, timeout: Duration, interval: Duration) -> Result<()> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let (commitment_info, is_recoverable) = pod.get_commitment_info(commitment).await?;
+        if is_recoverable {
+            info!("Commitment is recoverable with {}/{} chunks", commitment_info.availableChunks, commitment_info.totalChunks);
+            return Ok(());
+        }
+
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            return Err(anyhow::anyhow!(
+                "Timed out after {:?} waiting for commitment to be recoverable. {}/{} chunks available",
+                timeout, commitment_info.availableChunks, commitment_info.totalChunks
+            ));
+        }
+
+        info!("Waiting for commitment to be recoverable... {}/{} chunks", commitment_info.availableChunks, commitment_info.totalChunks);
+        tokio::time::sleep(interval.min(deadline - now)).await;
+    }
+}
+
+/// Fails if `stake` is below the minimum stake the contract requires for registration
+pub async fn ensure_min_stake<T: PodaClientTrait + ?Sized>(pod: &T, stake: u128) -> Result<()> {
+    let min_stake = pod.get_min_stake().await?;
+    if stake < min_stake {
+        return Err(anyhow::anyhow!("Stake {} is below the minimum stake of {}", stake, min_stake));
+    }
+    Ok(())
+}
+
+pub const DEFAULT_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(30);
+// Headroom over the estimate for challenge responses
+pub const DEFAULT_GAS_MULTIPLIER: f64 = 2.0;
+const SEND_RETRIES: u32 = 3;
+const SEND_RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+
+/// Runs `send` (a transaction send + receipt fetch) and waits at most `timeout` for it to confirm.
+/// Nonce and underpriced rejections are retried with exponential backoff.
+pub async fn send_and_confirm<F, Fut, R>(mut send: F, timeout: Duration) -> Result<R>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<R>>,
+{
+    let mut delay = SEND_RETRY_BASE_DELAY;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match tokio::time::timeout(timeout, send()).await {
+            Ok(Ok(receipt)) => return Ok(receipt),
+            Ok(Err(e)) if attempt <= SEND_RETRIES && is_retryable_send_error(&e) => {
+                warn!("Transaction rejected ({}), retrying in {:?}", e, delay);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            Ok(Err(e)) => return Err(e),
+            Err(_) => return Err(anyhow::anyhow!("Transaction not confirmed within {:?}", timeout)),
+        }
+    }
+}
+
+fn is_retryable_send_error(e: &anyhow::Error) -> bool {
+    let message = e.to_string().to_lowercase();
+    message.contains("nonce") || message.contains("underpriced")
+}
+
+/// Gas limit for an `estimate` scaled by `multiplier`, never below the estimate itself
+pub fn gas_limit(estimate: u64, multiplier: f64) -> u64 {
+    ((estimate as f64 * multiplier).ceil() as u64).max(estimate)
+}
+
+/// Fails with `PodaClientError::GasPriceAboveCap` when `gas_price` exceeds `cap`
+pub fn check_gas_price(gas_price: u128, cap: Option<u128>) -> Result<()> {
+    match cap {
+        Some(cap) if gas_price > cap => Err(PodaClientError::GasPriceAboveCap { gas_price, cap }.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Hands out sequential nonces for a single signer. Sends hold the lock until the node has
+/// accepted the transaction, so concurrent callers never reuse a nonce.
+#[derive(Debug, Default)]
+pub struct NonceManager {
+    next: tokio::sync::Mutex<Option<u64>>,
+}
+
+impl NonceManager {
+    /// Runs `send` with the next nonce. `fetch` is only awaited when no nonce is cached yet.
+    /// A failed send drops the cached nonce so that the next call resyncs with the node.
+    pub async fn send_with_nonce<N, F, Fut, R>(&self, fetch: N, send: F) -> Result<R>
+    where
+        N: Future<Output = Result<u64>>,
+        F: FnOnce(u64) -> Fut,
+        Fut: Future<Output = Result<R>>,
+    {
+        let mut next = self.next.lock().await;
+        let nonce = match *next {
+            Some(nonce) => nonce,
+            None => fetch.await?,
+        };
+
+        match send(nonce).await {
+            Ok(result) => {
+                *next = Some(nonce + 1);
+                Ok(result)
+            }
+            Err(e) => {
+                *next = None;
+                Err(e)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageEfficiency {
+    pub original_size: u128,
+    pub total_stored_size: u128,
+    // basis points, 10000 = 100%
+    pub redundancy_ratio: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkStorageStats {
+    pub total_commitments: u128,
+    pub total_original_data: u128,
+    pub total_stored_data: u128,
+    // basis points, 10000 = 100%
+    pub average_efficiency: u128,
+}
+
+/// Availability snapshot of a commitment, yielded by `PodaClient::watch_commitment`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitmentUpdate {
+    pub commitment: FixedBytes<32>,
+    pub available_chunks: u16,
+    pub total_chunks: u16,
+    pub is_recoverable: bool,
+}
+
+#[derive(Debug)]
+pub enum PodaClientError {
+    // the client was created with `new_read_only` and cannot send transactions
+    NoSigner,
+    // the network gas price is above the configured cap, the send should be retried later
+    GasPriceAboveCap { gas_price: u128, cap: u128 },
+}
+
+impl std::fmt::Display for PodaClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PodaClientError::NoSigner => write!(f, "No signer configured. This client is read-only"),
+            PodaClientError::GasPriceAboveCap { gas_price, cap } => write!(f, "Gas price {} wei is above the cap of {} wei", gas_price, cap),
+        }
+    }
+}
+
+impl std::error::Error for PodaClientError {}
+
+// Matches the contract's CHALLENGE_PERIOD
+pub const CHALLENGE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+/// Expiry math on a challenge's own `issuedAt`, so callers don't need an RPC call per challenge.
+/// `now` is a unix timestamp in seconds, compared like the contract compares `block.timestamp`.
+pub trait ChallengeTiming {
+    /// Unix timestamp from which the challenge can no longer be answered
+    fn deadline(&self) -> u64;
+    /// `None` once the challenge can no longer be answered
+    fn time_remaining(&self, now: u64) -> Option<Duration>;
+    fn is_expired(&self, now: u64) -> bool;
+}
+
+impl ChallengeTiming for ChallengeInfo {
+    fn deadline(&self) -> u64 {
+        self.challenge.issuedAt as u64 + CHALLENGE_PERIOD.as_secs()
+    }
+
+    fn time_remaining(&self, now: u64) -> Option<Duration> {
+        self.deadline().checked_sub(now).filter(|secs| *secs > 0).map(Duration::from_secs)
+    }
+
+    fn is_expired(&self, now: u64) -> bool {
+        self.time_remaining(now).is_none()
+    }
 }
 
 #[derive(Clone)]
 pub struct PodaClient {
     contract: PodaInstance<(), PodProvider, PodNetwork>,
     provider: PodProvider,
-    pub signer: PrivateKeySigner,
+    pub signer: Option<PrivateKeySigner>,
     pub address: Address,
     #[allow(dead_code)]
     rpc_url: String,
+    confirmation_timeout: Duration,
+    validate_min_stake: bool,
+    nonces: Arc<NonceManager>,
+    gas_multiplier: f64,
+    max_gas_price: Option<u128>,
+    availability_confirmations: u64,
 }
 
 impl PodaClient {
@@ -67,12 +302,156 @@ impl PodaClient {
         let contract: PodaInstance<(), PodProvider, PodNetwork> = Poda::new(address, provider.clone());
 
         Self {
-            signer,
+            signer: Some(signer),
             provider,
             contract,
             rpc_url,
             address,
+            confirmation_timeout: DEFAULT_CONFIRMATION_TIMEOUT,
+            validate_min_stake: true,
+            nonces: Arc::new(NonceManager::default()),
+            gas_multiplier: DEFAULT_GAS_MULTIPLIER,
+            max_gas_price: None,
+            availability_confirmations: DEFAULT_AVAILABILITY_CONFIRMATIONS,
+        }
+    }
+
+    /// Creates a client without a wallet. Only view functions can be used, writes fail with `PodaClientError::NoSigner`
+    pub async fn new_read_only(rpc_url: String, address: Address) -> Self {
+        let provider = PodProviderBuilder::with_recommended_settings()
+            .on_url(rpc_url.clone())
+            .await
+            .expect("Failed to create provider");
+
+        let contract: PodaInstance<(), PodProvider, PodNetwork> = Poda::new(address, provider.clone());
+
+        Self {
+            signer: None,
+            provider,
+            contract,
+            rpc_url,
+            address,
+            confirmation_timeout: DEFAULT_CONFIRMATION_TIMEOUT,
+            validate_min_stake: true,
+            nonces: Arc::new(NonceManager::default()),
+            gas_multiplier: DEFAULT_GAS_MULTIPLIER,
+            max_gas_price: None,
+            availability_confirmations: DEFAULT_AVAILABILITY_CONFIRMATIONS,
+        }
+    }
+
+    /// Streams an update every time a chunk attestation for `commitment` is observed.
+    /// Fails if the RPC does not support log filters.
+    pub async fn watch_commitment(&self, commitment: FixedBytes<32>) -> Result<impl Stream<Item = CommitmentUpdate> + '_> {
+        let mut attestations = self.contract.ChunkAttestation_filter();
+        attestations.filter = attestations.filter.topic1(commitment);
+        let poller = attestations.watch().await?;
+
+        let updates = poller.into_stream().filter_map(move |log| async move {
+            if let Err(e) = log {
+                warn!("Failed to decode chunk attestation: {}", e);
+                return None;
+            }
+
+            let (commitment_info, is_recoverable) = self.get_commitment_info(commitment).await.ok()?;
+            Some(CommitmentUpdate {
+                commitment,
+                available_chunks: commitment_info.availableChunks,
+                total_chunks: commitment_info.totalChunks,
+                is_recoverable,
+            })
+        });
+
+        Ok(updates)
+    }
+
+    pub fn signer_address(&self) -> Option<Address> {
+        self.signer.as_ref().map(|signer| signer.address())
+    }
+
+    fn require_signer(&self) -> Result<&PrivateKeySigner> {
+        self.signer.as_ref().ok_or_else(|| PodaClientError::NoSigner.into())
+    }
+
+    // Sends with the signer's next nonce, serialized with every other send of this client
+    async fn send_sequenced<F, Fut, R>(&self, send: F) -> Result<R>
+    where
+        F: FnOnce(u64) -> Fut,
+        Fut: Future<Output = Result<R>>,
+    {
+        let signer = self.require_signer()?;
+        let fetch = async { Ok(self.provider.get_transaction_count(signer.address()).pending().await?) };
+        self.nonces.send_with_nonce(fetch, send).await
+    }
+
+    pub fn with_confirmation_timeout(mut self, confirmation_timeout: Duration) -> Self {
+        self.confirmation_timeout = confirmation_timeout;
+        self
+    }
+
+    /// Whether `register_provider` checks the stake against the contract minimum before sending
+    pub fn with_min_stake_validation(mut self, validate_min_stake: bool) -> Self {
+        self.validate_min_stake = validate_min_stake;
+        self
+    }
+
+    /// Gas limit of challenge responses as a multiple of their estimate
+    pub fn with_gas_multiplier(mut self, gas_multiplier: f64) -> Self {
+        self.gas_multiplier = gas_multiplier;
+        self
+    }
+
+    /// Challenge responses are deferred while the gas price is above `max_gas_price` wei
+    pub fn with_max_gas_price(mut self, max_gas_price: Option<u128>) -> Self {
+        self.max_gas_price = max_gas_price;
+        self
+    }
+
+    /// Confirmations `wait_for_availability` requires before it trusts a commitment is recoverable
+    pub fn with_availability_confirmations(mut self, confirmations: u64) -> Self {
+        self.availability_confirmations = confirmations;
+        self
+    }
+
+    // Waits for the attestations that make `commitment` recoverable as of the latest block
+    async fn wait_until_recoverable(&self, commitment: FixedBytes<32>, timeout: Duration) -> Result<()> {
+        let updates = match self.watch_commitment(commitment).await {
+            Ok(updates) => updates,
+            Err(e) => {
+                warn!("Event subscription unavailable ({}), falling back to polling", e);
+                return poll_until_recoverable(self, commitment, timeout, AVAILABILITY_POLL_INTERVAL).await;
+            }
+        };
+        let mut updates = std::pin::pin!(updates);
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        // Attestations may have landed before the watcher was installed
+        let (commitment_info, mut is_recoverable) = self.get_commitment_info(commitment).await?;
+        let (mut available_chunks, total_chunks) = (commitment_info.availableChunks, commitment_info.totalChunks);
+
+        while !is_recoverable {
+            info!("Waiting for commitment to be recoverable... {}/{} chunks", available_chunks, total_chunks);
+            match tokio::time::timeout_at(deadline, updates.next()).await {
+                Ok(Some(update)) => {
+                    available_chunks = update.available_chunks;
+                    is_recoverable = update.is_recoverable;
+                }
+                Ok(None) => {
+                    warn!("Attestation stream ended, falling back to polling");
+                    let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+                    return poll_until_recoverable(self, commitment, remaining, AVAILABILITY_POLL_INTERVAL).await;
+                }
+                Err(_) => {
+                    return Err(anyhow::anyhow!(
+                        "Timed out after {:?} waiting for commitment to be recoverable. {}/{} chunks available",
+                        timeout, available_chunks, total_chunks
+                    ));
+                }
+            }
         }
+
+        info!("Commitment is recoverable with {}/{} chunks", available_chunks, total_chunks);
+        Ok(())
     }
 }
 
@@ -83,24 +462,45 @@ impl PodaClientTrait for PodaClient {
     // =============================================================================
 
     async fn register_provider(&self, name: String, url: String, stake: u128) -> Result<()> {
+        let signer = self.require_signer()?;
+        if self.validate_min_stake {
+            ensure_min_stake(self, stake).await?;
+        }
+
         let stake_wei = U256::from(stake);
-        let balance = self.provider.get_balance(self.signer.address()).await?;
+        let balance = self.provider.get_balance(signer.address()).await?;
         if balance < stake_wei {
             return Err(anyhow::anyhow!("Insufficient balance"));
         }
 
-        let register = self.contract.registerProvider(name, url).value(stake_wei).send().await?;
+        let (name, url) = (&name, &url);
+        let receipt = send_and_confirm(move || async move {
+            let register = self.send_sequenced(|nonce| async move {
+                Ok(self.contract.registerProvider(name.clone(), url.clone()).value(stake_wei).nonce(nonce).send().await?)
+            }).await?;
+            Ok(register.get_receipt().await?)
+        }, self.confirmation_timeout).await?;
 
-        match register.get_receipt().await {
-            Ok(receipt) => {
-                if receipt.status() {
-                    Ok(())
-                } else {
-                    Err(anyhow::anyhow!("Submit failed: {:?}", receipt))
-                }
-            }
-            Err(e) => Err(anyhow::anyhow!("Failed to get receipt: {}", e))
+        if !receipt.status() {
+            return Err(anyhow::anyhow!("Submit failed: {:?}", receipt));
+        }
+        Ok(())
+    }
+
+    async fn update_provider_url(&self, url: String) -> Result<()> {
+        self.require_signer()?;
+        let url = &url;
+        let receipt = send_and_confirm(move || async move {
+            let update = self.send_sequenced(|nonce| async move {
+                Ok(self.contract.updateProviderUrl(url.clone()).nonce(nonce).send().await?)
+            }).await?;
+            Ok(update.get_receipt().await?)
+        }, self.confirmation_timeout).await?;
+
+        if !receipt.status() {
+            return Err(anyhow::anyhow!("URL update failed: {:?}", receipt));
         }
+        Ok(())
     }
 
     // =============================================================================
@@ -115,33 +515,52 @@ impl PodaClientTrait for PodaClient {
         required_chunks: u16,
         kzg_commitment: Bytes
     ) -> Result<()> {
-        let submit = self.contract.submitCommitment(commitment, size, total_chunks, required_chunks, kzg_commitment).send().await?;
-        
-        match submit.get_receipt().await {
-            Ok(receipt) => {
-                if receipt.status() {
-                    Ok(())
-                } else {
-                    Err(anyhow::anyhow!("Submit failed: {:?}", receipt))
-                }
-            }
-            Err(e) => Err(anyhow::anyhow!("Failed to get receipt: {}", e))
+        self.require_signer()?;
+        let kzg_commitment = &kzg_commitment;
+        let receipt = send_and_confirm(move || async move {
+            let submit = self.send_sequenced(|nonce| async move {
+                Ok(self.contract.submitCommitment(commitment, size, total_chunks, required_chunks, kzg_commitment.clone()).nonce(nonce).send().await?)
+            }).await?;
+            Ok(submit.get_receipt().await?)
+        }, self.confirmation_timeout).await?;
+
+        if !receipt.status() {
+            return Err(anyhow::anyhow!("Submit failed: {:?}", receipt));
         }
+        Ok(())
     }
 
     async fn submit_chunk_attestations(&self, commitment: FixedBytes<32>, chunk_ids: Vec<u16>) -> Result<()> {
-        let submit = self.contract.submitChunkAttestations(commitment, chunk_ids).send().await?;
-        
-        match submit.get_receipt().await {
-            Ok(receipt) => {
-                if receipt.status() {
-                    Ok(())
-                } else {
-                    Err(anyhow::anyhow!("Submit failed: {:?}", receipt))
-                }
-            }
-            Err(e) => Err(anyhow::anyhow!("Failed to get receipt: {}", e))
+        self.require_signer()?;
+        let chunk_ids = &chunk_ids;
+        let receipt = send_and_confirm(move || async move {
+            let submit = self.send_sequenced(|nonce| async move {
+                Ok(self.contract.submitChunkAttestations(commitment, chunk_ids.clone()).nonce(nonce).send().await?)
+            }).await?;
+            Ok(submit.get_receipt().await?)
+        }, self.confirmation_timeout).await?;
+
+        if !receipt.status() {
+            return Err(anyhow::anyhow!("Submit failed: {:?}", receipt));
         }
+        Ok(())
+    }
+
+    async fn submit_chunk_attestations_with_proof(&self, commitment: FixedBytes<32>, chunks: Vec<Chunk>) -> Result<()> {
+        let provider = self.require_signer()?.address();
+        let chunk_ids = &chunks.iter().map(|c| c.index).collect::<Vec<_>>();
+        let proofs = &chunks.iter().map(|c| c.possession_proof(commitment, provider)).collect::<Vec<_>>();
+        let receipt = send_and_confirm(move || async move {
+            let submit = self.send_sequenced(|nonce| async move {
+                Ok(self.contract.submitChunkAttestationsWithProof(commitment, chunk_ids.clone(), proofs.clone()).nonce(nonce).send().await?)
+            }).await?;
+            Ok(submit.get_receipt().await?)
+        }, self.confirmation_timeout).await?;
+
+        if !receipt.status() {
+            return Err(anyhow::anyhow!("Submit failed: {:?}", receipt));
+        }
+        Ok(())
     }
 
     // =============================================================================
@@ -165,6 +584,11 @@ impl PodaClientTrait for PodaClient {
         Ok(info)
     }
 
+    async fn get_min_stake(&self) -> Result<u128> {
+        let min_stake = self.contract.minStake().call().await?;
+        Ok(min_stake._0.saturating_to())
+    }
+
     async fn commitment_exists(&self, commitment: FixedBytes<32>) -> Result<bool> {
         let exists = self.contract.commitmentExists(commitment).call().await?;
         Ok(exists._0)
@@ -180,6 +604,18 @@ impl PodaClientTrait for PodaClient {
         Ok((info._0, info.isRecoverable))
     }
 
+    async fn get_commitment_info_confirmed(&self, commitment: FixedBytes<32>, confirmations: u64) -> Result<(Commitment, bool)> {
+        read_confirmed(
+            || async { Ok(self.provider.get_block_number().await?) },
+            |block| async move {
+                let info = self.contract.getCommitmentInfo(commitment).call().block(block.into()).await?;
+                Ok((info._0, info.isRecoverable))
+            },
+            confirmations,
+            CONFIRMATION_POLL_INTERVAL,
+        ).await
+    }
+
     async fn get_available_chunks(&self, commitment: FixedBytes<32>) -> Result<Vec<u16>> {
         let chunks = self.contract.getAvailableChunks(commitment).call().await?;
         Ok(chunks._0)
@@ -195,6 +631,11 @@ impl PodaClientTrait for PodaClient {
         Ok(owner._0)
     }
 
+    async fn get_possession_proof(&self, commitment: FixedBytes<32>, chunk_id: u16) -> Result<FixedBytes<32>> {
+        let proof = self.contract.possessionProofs(commitment, chunk_id).call().await?;
+        Ok(proof._0)
+    }
+
     async fn is_chunk_available(&self, commitment: FixedBytes<32>, chunk_id: u16) -> Result<bool> {
         let available = self.contract.isChunkAvailable(commitment, chunk_id).call().await?;
         Ok(available._0)
@@ -205,6 +646,12 @@ impl PodaClientTrait for PodaClient {
         Ok(statuses._0)
     }
 
+    // owner and availability of each chunk, in the order of `chunk_ids`
+    async fn get_chunk_info_batch(&self, commitment: FixedBytes<32>, chunk_ids: Vec<u16>) -> Result<Vec<(Address, bool)>> {
+        let info = self.contract.getChunkInfoBatch(commitment, chunk_ids).call().await?;
+        Ok(info.owners.into_iter().zip(info.available).collect())
+    }
+
     // =============================================================================
     // CHALLENGE SYSTEM
     // =============================================================================
@@ -221,18 +668,18 @@ impl PodaClientTrait for PodaClient {
     }
 
     async fn slash_expired_challenge(&self, commitment: FixedBytes<32>, chunk_id: u16, provider: Address) -> Result<()> {
-        let res = self.contract.slashExpiredChallenge(commitment, chunk_id, provider).send().await?;
-
-        match res.get_receipt().await {
-            Ok(receipt) => {
-                if receipt.status() {
-                    Ok(())
-                } else {
-                    Err(anyhow::anyhow!("Slashing failed: {:?}", receipt))
-                }
-            }
-            Err(e) => Err(anyhow::anyhow!("Failed to get receipt: {}", e))
+        self.require_signer()?;
+        let receipt = send_and_confirm(|| async {
+            let res = self.send_sequenced(|nonce| async move {
+                Ok(self.contract.slashExpiredChallenge(commitment, chunk_id, provider).nonce(nonce).send().await?)
+            }).await?;
+            Ok(res.get_receipt().await?)
+        }, self.confirmation_timeout).await?;
+
+        if !receipt.status() {
+            return Err(anyhow::anyhow!("Slashing failed: {:?}", receipt));
         }
+        Ok(())
     }
 
     async fn get_provider_active_challenges(&self, provider: Address) -> Result<Vec<ChallengeInfo>> {
@@ -252,7 +699,17 @@ impl PodaClientTrait for PodaClient {
     }
 
     async fn issue_chunk_challenge(&self, commitment: FixedBytes<32>, chunk_id: u16, provider: Address) -> Result<ChallengeInfo> {
-        self.contract.issueChunkChallenge(commitment, chunk_id, provider).send().await?.watch().await?;
+        self.require_signer()?;
+        let receipt = send_and_confirm(|| async {
+            let issue = self.send_sequenced(|nonce| async move {
+                Ok(self.contract.issueChunkChallenge(commitment, chunk_id, provider).nonce(nonce).send().await?)
+            }).await?;
+            Ok(issue.get_receipt().await?)
+        }, self.confirmation_timeout).await?;
+
+        if !receipt.status() {
+            return Err(anyhow::anyhow!("Challenge issuance failed: {:?}", receipt));
+        }
         return self.get_chunk_challenge(commitment, chunk_id, provider).await;
     }
 
@@ -265,76 +722,95 @@ impl PodaClientTrait for PodaClient {
         // Use the deploy_builder to create a deployment transaction
         let deployment_tx = Poda::deploy_builder(&provider, owner, U256::from(min_stake));
         
-        // Send the deployment transaction
-        let pending_tx = deployment_tx.send().await?;
-        
-        // Get the receipt from the deployment transaction
-        match pending_tx.get_receipt().await {
-            Ok(receipt) => {
-                if receipt.status() {
-                    // Extract the deployed contract address from the receipt
-                    let deployed_address = receipt.contract_address
-                        .ok_or_else(|| anyhow::anyhow!("No contract address in deployment receipt"))?;
-                    
-                    Ok(deployed_address)
-                } else {
-                    Err(anyhow::anyhow!("Deployment failed: {:?}", receipt))
-                }
-            }
-            Err(e) => Err(anyhow::anyhow!("Failed to get receipt: {}", e))
+        // Send the deployment transaction and wait for its receipt
+        let receipt = send_and_confirm(|| async {
+            let pending_tx = deployment_tx.send().await?;
+            Ok(pending_tx.get_receipt().await?)
+        }, DEFAULT_CONFIRMATION_TIMEOUT).await?;
+
+        if !receipt.status() {
+            return Err(anyhow::anyhow!("Deployment failed: {:?}", receipt));
         }
+
+        // Extract the deployed contract address from the receipt
+        let deployed_address = receipt.contract_address
+            .ok_or_else(|| anyhow::anyhow!("No contract address in deployment receipt"))?;
+
+        Ok(deployed_address)
     }
 
-    async fn wait_for_availability(&self, commitment: FixedBytes<32>) -> Result<()> {
+    async fn wait_for_availability(&self, commitment: FixedBytes<32>, timeout: Duration) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + timeout;
         loop {
-            let (commitment_info, is_recoverable) = self.get_commitment_info(commitment).await?;
+            self.wait_until_recoverable(commitment, deadline.saturating_duration_since(tokio::time::Instant::now())).await?;
+            if self.availability_confirmations == 0 {
+                return Ok(());
+            }
+
+            let confirmed = self.get_commitment_info_confirmed(commitment, self.availability_confirmations);
+            let (_, is_recoverable) = tokio::time::timeout_at(deadline, confirmed).await.map_err(|_| anyhow::anyhow!(
+                "Timed out after {:?} waiting for {} confirmations of the commitment's availability", timeout, self.availability_confirmations
+            ))??;
             if is_recoverable {
-                info!("Commitment is recoverable with {}/{} chunks", commitment_info.availableChunks, commitment_info.totalChunks);
                 return Ok(());
             }
-            info!("Waiting for commitment to be recoverable... {}/{} chunks", commitment_info.availableChunks, commitment_info.totalChunks);
-            tokio::time::sleep(Duration::from_secs(1)).await;
+
+            // the attestations were reorged out, so wait for them to land again
+            warn!("Commitment is not recoverable once confirmed, waiting again");
         }
     }
 
     async fn respond_to_chunk_challenge(&self, commitment: FixedBytes<32>, chunk_id: u16, chunk_data: Bytes, proof: Vec<FixedBytes<32>>) -> Result<()> {
-        // Estimate gas for the transaction
-        let gas_estimate = self.contract
-            .respondToChunkChallenge(commitment, chunk_id, chunk_data.clone(), proof.clone())
-            .estimate_gas()
-            .await?; 
-        
-        let response = self.contract
-            .respondToChunkChallenge(commitment, chunk_id, chunk_data, proof)
-            .gas(gas_estimate * 2) // 2x buffer
-            .send()
-            .await?;
-        
-        match response.get_receipt().await {
-            Ok(receipt) => {
-                if receipt.status() {
-                    Ok(())
-                } else {
-                    Err(anyhow::anyhow!("Challenge response failed: {:?}", receipt))
-                }
-            }
-            Err(e) => Err(anyhow::anyhow!("Failed to get receipt: {}", e))
+        self.require_signer()?;
+        check_gas_price(self.provider.get_gas_price().await?, self.max_gas_price)?;
+
+        let (chunk_data, proof) = (&chunk_data, &proof);
+        let receipt = send_and_confirm(move || async move {
+            // Estimate gas for the transaction
+            let gas_estimate = self.contract
+                .respondToChunkChallenge(commitment, chunk_id, chunk_data.clone(), proof.clone())
+                .estimate_gas()
+                .await?;
+
+            let response = self.send_sequenced(|nonce| async move {
+                Ok(self.contract
+                    .respondToChunkChallenge(commitment, chunk_id, chunk_data.clone(), proof.clone())
+                    .gas(gas_limit(gas_estimate, self.gas_multiplier))
+                    .nonce(nonce)
+                    .send()
+                    .await?)
+            }).await?;
+            Ok(response.get_receipt().await?)
+        }, self.confirmation_timeout).await?;
+
+        if !receipt.status() {
+            return Err(anyhow::anyhow!("Challenge response failed: {:?}", receipt));
         }
+        Ok(())
     }
 
     // =============================================================================
     // STORAGE EFFICIENCY METRICS
     // =============================================================================
 
-    // async fn get_storage_efficiency(&self, commitment: FixedBytes<32>) -> Result<(u128, u128, u128)> {
-    //     let efficiency = self.contract.getStorageEfficiency(commitment).call().await?;
-    //     Ok((efficiency.originalSize.into(), efficiency.totalStoredSize.into(), efficiency.redundancyRatio.into()))
-    // }
+    async fn get_storage_efficiency(&self, commitment: FixedBytes<32>) -> Result<StorageEfficiency> {
+        let efficiency = self.contract.getStorageEfficiency(commitment).call().await?;
+        Ok(StorageEfficiency {
+            original_size: efficiency.originalSize.saturating_to(),
+            total_stored_size: efficiency.totalStoredSize.saturating_to(),
+            redundancy_ratio: efficiency.redundancyRatio.saturating_to(),
+        })
+    }
 
-    // async fn get_network_storage_stats(&self) -> Result<(u128, u128, u128, u128)> {
-    //     let stats = self.contract.getNetworkStorageStats().call().await?;
-    //     Ok((stats.totalCommitments.into(), stats.totalOriginalData.into(), stats.totalStoredData.into(), stats.averageEfficiency.into()))
-    // }
+    async fn get_network_storage_stats(&self) -> Result<NetworkStorageStats> {
+        let stats = self.contract.getNetworkStorageStats().call().await?;
+        Ok(NetworkStorageStats {
+            total_commitments: stats.totalCommitments.saturating_to(),
+            total_original_data: stats.totalOriginalData.saturating_to(),
+            total_stored_data: stats.totalStoredData.saturating_to(),
+            average_efficiency: stats.averageEfficiency.saturating_to(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -349,6 +825,167 @@ mod tests {
     const PRIVATE_KEY: &str = "6df79891f22b0f3c9e9fb53b966a8861fd6fef69f99772c5c4dbcf303f10d901";
     use common::log::{info, error, debug};
 
+    #[tokio::test]
+    async fn test_wait_for_availability_times_out() {
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(|_| {
+            let commitment = Commitment {
+                size: 1024,
+                timestamp: 0,
+                totalChunks: 24,
+                requiredChunks: 16,
+                availableChunks: 3,
+                kzgCommitment: Bytes::new(),
+            };
+            Ok((commitment, false))
+        });
+
+        let timeout = Duration::from_millis(50);
+        let start = std::time::Instant::now();
+        let result = poll_until_recoverable(&pod, FixedBytes::from([1u8; 32]), timeout, Duration::from_millis(10)).await;
+
+        assert!(start.elapsed() < timeout * 4);
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("3/24"), "unexpected error: {}", err);
+    }
+
+    #[tokio::test]
+    async fn test_confirmed_read_waits_for_confirmations() {
+        // every poll of the chain head sees one more block
+        let head = std::sync::atomic::AtomicU64::new(100);
+        let polls = std::sync::atomic::AtomicU64::new(0);
+        let block_number = || async {
+            polls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(head.fetch_add(1, std::sync::atomic::Ordering::SeqCst))
+        };
+
+        let read = read_confirmed(block_number, |block| async move { Ok(block) }, 3, Duration::from_millis(1)).await.unwrap();
+
+        // the state is read at the starting block, and only after three more blocks were seen
+        assert_eq!(read, 100);
+        assert_eq!(polls.load(std::sync::atomic::Ordering::SeqCst), 4);
+        assert_eq!(head.load(std::sync::atomic::Ordering::SeqCst), 104);
+
+        let read = read_confirmed(|| async { Ok(7) }, |block| async move { Ok(block) }, 0, Duration::from_millis(1)).await.unwrap();
+        assert_eq!(read, 7);
+    }
+
+    #[tokio::test]
+    async fn test_send_and_confirm_retries_nonce_errors() {
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let result = send_and_confirm(|| async {
+            match attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+                0 => Err(anyhow::anyhow!("nonce too low")),
+                1 => Err(anyhow::anyhow!("replacement transaction underpriced")),
+                _ => Ok(42),
+            }
+        }, Duration::from_secs(1)).await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_send_and_confirm_does_not_retry_other_errors() {
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let result: Result<()> = send_and_confirm(|| async {
+            attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Err(anyhow::anyhow!("execution reverted"))
+        }, Duration::from_secs(1)).await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_send_and_confirm_times_out() {
+        let result: Result<()> = send_and_confirm(std::future::pending, Duration::from_millis(50)).await;
+
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("not confirmed"), "unexpected error: {}", err);
+    }
+
+    #[tokio::test]
+    async fn test_below_min_stake_is_rejected() {
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_min_stake().returning(|| Ok(1000));
+
+        let err = ensure_min_stake(&pod, 999).await.unwrap_err();
+        assert!(err.to_string().contains("below the minimum stake"));
+        assert!(ensure_min_stake(&pod, 1000).await.is_ok());
+    }
+
+    #[test]
+    fn test_challenge_timing() {
+        let challenge = |issued_at: u32| ChallengeInfo {
+            challenge: ChunkChallenge { challengeId: FixedBytes::from([1u8; 32]), challenger: Address::ZERO, issuedAt: issued_at },
+            commitment: FixedBytes::from([2u8; 32]),
+            chunkId: 3,
+        };
+        let now = 1_700_000_000;
+        let period = CHALLENGE_PERIOD.as_secs() as u32;
+
+        let fresh = challenge(now as u32);
+        assert_eq!(fresh.time_remaining(now), Some(CHALLENGE_PERIOD));
+        assert!(!fresh.is_expired(now));
+
+        let expiring = challenge(now as u32 - period + 30);
+        assert_eq!(expiring.time_remaining(now), Some(Duration::from_secs(30)));
+        assert!(!expiring.is_expired(now));
+
+        // the contract stops accepting responses once issuedAt + CHALLENGE_PERIOD is reached
+        let expired = challenge(now as u32 - period);
+        assert_eq!(expired.time_remaining(now), None);
+        assert!(expired.is_expired(now));
+        assert!(challenge(0).is_expired(now));
+    }
+
+    #[test]
+    fn test_gas_limit_and_price_cap() {
+        assert_eq!(gas_limit(100_000, DEFAULT_GAS_MULTIPLIER), 200_000);
+        assert_eq!(gas_limit(100_001, 1.5), 150_002);
+        // a multiplier below one never under-provisions the estimate
+        assert_eq!(gas_limit(100_000, 0.5), 100_000);
+
+        assert!(check_gas_price(u128::MAX, None).is_ok());
+        assert!(check_gas_price(50, Some(50)).is_ok());
+        let err = check_gas_price(51, Some(50)).unwrap_err();
+        assert!(matches!(err.downcast_ref::<PodaClientError>(), Some(PodaClientError::GasPriceAboveCap { gas_price: 51, cap: 50 })));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_submits_get_sequential_nonces() {
+        let nonces = NonceManager::default();
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_submit_commitment().times(2).returning(|_, _, _, _, _| Ok(()));
+
+        // each send fetches the on-chain count, but only the first fetch should be used
+        let submit = |commitment: u8| {
+            let (nonces, pod) = (&nonces, &pod);
+            nonces.send_with_nonce(async { Ok(5) }, move |nonce| async move {
+                tokio::task::yield_now().await;
+                pod.submit_commitment(FixedBytes::from([commitment; 32]), 1024, 24, 16, Bytes::new()).await?;
+                Ok(nonce)
+            })
+        };
+        let (first, second) = tokio::join!(submit(1), submit(2));
+
+        let mut used = vec![first.unwrap(), second.unwrap()];
+        used.sort();
+        assert_eq!(used, vec![5, 6]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_send_resyncs_nonce() {
+        let nonces = NonceManager::default();
+
+        let err = nonces.send_with_nonce(async { Ok(3) }, |_| async { Err::<u64, _>(anyhow::anyhow!("nonce too low")) }).await;
+        assert!(err.is_err());
+
+        let nonce = nonces.send_with_nonce(async { Ok(9) }, |nonce| async move { Ok(nonce) }).await.unwrap();
+        assert_eq!(nonce, 9);
+    }
+
     async fn setup_test_pod() -> PodaClient {
         let signer = PrivateKeySigner::from_str(PRIVATE_KEY).expect("Invalid private key");
         let address = pod_sdk::Address::from_str(CONTRACT_ADDRESS).expect("Invalid contract address");
@@ -365,6 +1002,48 @@ mod tests {
         pod
     }
 
+    #[tokio::test]
+    async fn test_read_only_client() {
+        let address = pod_sdk::Address::from_str(CONTRACT_ADDRESS).expect("Invalid contract address");
+        let pod = PodaClient::new_read_only(RPC_URL.to_string(), address).await;
+        assert!(pod.signer_address().is_none());
+
+        pod.get_commitment_list().await.expect("Failed to read commitment list");
+
+        let err = pod.submit_commitment(FixedBytes::from([2u8; 32]), 1024, 6, 4, Bytes::from([1u8; 48]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref::<PodaClientError>(), Some(PodaClientError::NoSigner)));
+    }
+
+    #[tokio::test]
+    async fn test_watch_commitment_yields_attestations() {
+        let pod = setup_test_pod().await;
+
+        let nonce = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+        let commitment = alloy::primitives::keccak256(nonce.to_le_bytes());
+        pod.submit_commitment(commitment, 1024, 6, 4, Bytes::from([1u8; 48]))
+            .await
+            .expect("Failed to submit commitment");
+
+        let updates = pod.watch_commitment(commitment).await.expect("Failed to watch commitment");
+        let mut updates = std::pin::pin!(updates);
+
+        pod.submit_chunk_attestations(commitment, vec![0, 1])
+            .await
+            .expect("Failed to submit attestations");
+
+        let update = tokio::time::timeout(Duration::from_secs(30), updates.next())
+            .await
+            .expect("No update received")
+            .expect("Stream ended");
+
+        assert_eq!(update.commitment, commitment);
+        assert_eq!(update.total_chunks, 6);
+        assert!(update.available_chunks >= 1);
+        assert!(!update.is_recoverable);
+    }
+
     #[tokio::test]
     async fn test_submit_and_get_commitment() {
         let pod = setup_test_pod().await;
@@ -1,2 +1,2 @@
-pub use pod_sdk::{PrivateKeySigner, provider::{PodProvider, PodProviderBuilder}, network::PodNetwork, EthereumWallet, Provider, alloy_primitives::{FixedBytes, Address, U256}};
+pub use pod_sdk::{PrivateKeySigner, provider::{PodProvider, PodProviderBuilder}, network::PodNetwork, EthereumWallet, Provider, alloy_primitives::{FixedBytes, Address, U256, Bytes}};
 pub mod client;
\ No newline at end of file
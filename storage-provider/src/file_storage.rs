@@ -1,41 +1,278 @@
+use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
 use anyhow::Result;
 use merkle_tree::MerkleProof;
-use pod::FixedBytes;
+use pod::{client::PodaClientTrait, FixedBytes};
 use serde::{Deserialize, Serialize};
 use async_trait::async_trait;
-use common::types::Chunk;
-use crate::storage::ChunkStorageTrait;
+use common::{clock::{system_clock, Clock}, log::info, types::{keccak256, Chunk}};
+use crate::storage::{ChunkReader, ChunkStorageTrait};
+
+// Extension of in-flight writes, renamed onto the chunk path once complete
+const TEMP_EXTENSION: &str = "tmp";
+
+// Directory under the base path holding deduplicated chunk data, named by its keccak256
+const BLOB_DIR: &str = "blobs";
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
 
 pub struct FileStorage {
     base_path: PathBuf,
+    clock: Arc<dyn Clock>,
+    dedup: bool,
+    // serializes chunk writes and deletes against the blob reference counts
+    blob_lock: Mutex<()>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChunkWithProof {
     pub chunk: Chunk,
     pub merkle_proof: MerkleProof,
+    // unix seconds, missing for chunks stored before it was recorded
+    #[serde(default)]
+    pub stored_at: Option<u64>,
+    // keccak256 of the chunk data when it is kept in a shared blob, `chunk.data` is empty then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_hash: Option<FixedBytes<32>>,
 }
 
 impl FileStorage {
     pub fn new<P: AsRef<Path>>(path: P) -> Self {
         Self {
             base_path: path.as_ref().to_path_buf(),
+            clock: system_clock(),
+            dedup: false,
+            blob_lock: Mutex::new(()),
         }
     }
 
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Stores identical chunk data once, keyed by its keccak256, with chunk files referencing
+    /// it. Chunks stored with inline data are still read and deleted as before. Only chunks kept
+    /// in a blob are streamed by `retrieve_stream`, inline ones are read whole.
+    pub fn with_dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    fn read_chunk_file(path: &Path) -> Result<ChunkWithProof> {
+        let mut data = Vec::new();
+        File::open(path)?.read_to_end(&mut data)?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Deletes chunks of commitments that no longer exist on-chain and chunks stored more than
+    /// `older_than` ago. Chunks without a `stored_at` are only removed with their commitment.
+    /// Returns the number of chunks removed.
+    pub async fn gc<T: PodaClientTrait + ?Sized>(&self, older_than: Duration, pod: &T) -> Result<usize> {
+        self.ensure_dir_exists()?;
+
+        let now = self.clock.now().duration_since(UNIX_EPOCH)?.as_secs();
+        let cutoff = now.saturating_sub(older_than.as_secs());
+
+        let mut known_commitments: HashMap<FixedBytes<32>, bool> = HashMap::new();
+        let mut removed = 0;
+        for (commitment, index) in self.scan_chunk_files()? {
+            let exists = match known_commitments.get(&commitment) {
+                Some(exists) => *exists,
+                None => {
+                    let exists = pod.commitment_exists(commitment).await?;
+                    known_commitments.insert(commitment, exists);
+                    exists
+                }
+            };
+
+            let path = self.chunk_path(commitment, index);
+            let expired = exists && Self::read_chunk_file(&path)?.stored_at.is_some_and(|stored_at| stored_at < cutoff);
+            if !exists || expired {
+                self.remove_chunk_file(&path)?;
+                removed += 1;
+            }
+        }
+
+        info!("Garbage collected {} chunks", removed);
+        Ok(removed)
+    }
+
     fn chunk_path(&self, commitment: FixedBytes<32>, index: u16) -> PathBuf {
         self.base_path.join(format!("{}_{}.chunk", commitment, index))
     }
 
-    fn ensure_dir_exists(&self) -> Result<()> {
-        if !self.base_path.exists() {
-            fs::create_dir_all(&self.base_path)?;
+    fn blob_path(&self, hash: FixedBytes<32>) -> PathBuf {
+        self.base_path.join(BLOB_DIR).join(format!("{}.blob", hash))
+    }
+
+    // Number of chunk files referencing the blob
+    fn refs_path(&self, hash: FixedBytes<32>) -> PathBuf {
+        self.base_path.join(BLOB_DIR).join(format!("{}.refs", hash))
+    }
+
+    fn blob_temp_path(&self, path: &Path) -> PathBuf {
+        let unique = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        path.with_extension(format!("{}-{}.{}", std::process::id(), unique, TEMP_EXTENSION))
+    }
+
+    fn blob_refs(&self, hash: FixedBytes<32>) -> Result<u64> {
+        match fs::read_to_string(self.refs_path(hash)) {
+            Ok(refs) => Ok(refs.trim().parse()?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set_blob_refs(&self, hash: FixedBytes<32>, refs: u64) -> Result<()> {
+        let path = self.refs_path(hash);
+        self.write_atomically(&path, &self.blob_temp_path(&path), refs.to_string().as_bytes())
+    }
+
+    // Callers hold `blob_lock`
+    fn retain_blob(&self, data: &[u8]) -> Result<FixedBytes<32>> {
+        let hash = keccak256(data);
+        fs::create_dir_all(self.base_path.join(BLOB_DIR))?;
+
+        let refs = self.blob_refs(hash)?;
+        if refs == 0 {
+            let path = self.blob_path(hash);
+            self.write_atomically(&path, &self.blob_temp_path(&path), data)?;
+        }
+        self.set_blob_refs(hash, refs + 1)?;
+        Ok(hash)
+    }
+
+    // Callers hold `blob_lock`
+    fn release_blob(&self, hash: FixedBytes<32>) -> Result<()> {
+        let refs = self.blob_refs(hash)?;
+        if refs > 1 {
+            return self.set_blob_refs(hash, refs - 1);
+        }
+
+        for path in [self.blob_path(hash), self.refs_path(hash)] {
+            match fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
         }
         Ok(())
     }
+
+    // Blob referenced by the chunk file at `path`, if any. Missing and unreadable files
+    // reference nothing.
+    fn referenced_blob(path: &Path) -> Option<FixedBytes<32>> {
+        Self::read_chunk_file(path).ok().and_then(|chunk| chunk.data_hash)
+    }
+
+    // Removes a chunk file along with its reference to a shared blob
+    fn remove_chunk_file(&self, path: &Path) -> Result<()> {
+        let _guard = self.blob_lock.lock().unwrap();
+        let blob = Self::referenced_blob(path);
+        fs::remove_file(path)?;
+        if let Some(hash) = blob {
+            self.release_blob(hash)?;
+        }
+        Ok(())
+    }
+
+    // Unique per write so concurrent stores of the same chunk never share a temp file
+    fn temp_path(&self, commitment: FixedBytes<32>, index: u16) -> PathBuf {
+        let unique = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        self.base_path.join(format!("{}_{}.chunk.{}-{}.{}", commitment, index, std::process::id(), unique, TEMP_EXTENSION))
+    }
+
+    // Writes to a temp file in the same directory and renames it into place, so readers
+    // see either the previous file or the complete new one
+    fn write_atomically(&self, path: &Path, temp_path: &Path, data: &[u8]) -> Result<()> {
+        let result = File::create(temp_path)
+            .and_then(|mut file| file.write_all(data).and_then(|_| file.sync_all()))
+            .and_then(|_| fs::rename(temp_path, path));
+        if result.is_err() {
+            let _ = fs::remove_file(temp_path);
+        }
+        Ok(result?)
+    }
+
+    // Filename format: {commitment}_{index}.chunk
+    fn parse_chunk_path(path: &Path) -> Option<(FixedBytes<32>, u16)> {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("chunk") {
+            return None;
+        }
+
+        let stem = path.file_stem()?.to_str()?;
+        let (commitment, index) = stem.split_once('_')?;
+        Some((FixedBytes::from_str(commitment).ok()?, index.parse::<u16>().ok()?))
+    }
+
+    // Parses every chunk filename in the storage directory once, skipping anything malformed
+    fn scan_chunk_files(&self) -> Result<Vec<(FixedBytes<32>, u16)>> {
+        let mut chunks = Vec::new();
+        for entry in fs::read_dir(&self.base_path)? {
+            if let Some(parsed) = Self::parse_chunk_path(&entry?.path()) {
+                chunks.push(parsed);
+            }
+        }
+        Ok(chunks)
+    }
+
+    // create_dir_all succeeds if another task created the directory first
+    fn ensure_dir_exists(&self) -> Result<()> {
+        fs::create_dir_all(&self.base_path)?;
+        Ok(())
+    }
+
+    /// Lists files in the storage directory that are not valid chunk files or that
+    /// belong to commitments which do not exist on-chain
+    pub async fn scan_orphans<T: PodaClientTrait + ?Sized>(&self, pod: &T) -> Result<Vec<PathBuf>> {
+        self.ensure_dir_exists()?;
+
+        let mut known_commitments: HashMap<FixedBytes<32>, bool> = HashMap::new();
+        let mut orphans = Vec::new();
+        for entry in fs::read_dir(&self.base_path)? {
+            let path = entry?.path();
+            // skip directories and writes still in flight
+            if !path.is_file() || path.extension().and_then(|ext| ext.to_str()) == Some(TEMP_EXTENSION) {
+                continue;
+            }
+
+            let Some((commitment, _)) = Self::parse_chunk_path(&path) else {
+                orphans.push(path);
+                continue;
+            };
+
+            let exists = match known_commitments.get(&commitment) {
+                Some(exists) => *exists,
+                None => {
+                    let exists = pod.commitment_exists(commitment).await?;
+                    known_commitments.insert(commitment, exists);
+                    exists
+                }
+            };
+            if !exists {
+                orphans.push(path);
+            }
+        }
+
+        orphans.sort();
+        Ok(orphans)
+    }
+
+    /// Deletes every file reported by `scan_orphans` and returns the removed paths
+    pub async fn sweep_orphans<T: PodaClientTrait + ?Sized>(&self, pod: &T) -> Result<Vec<PathBuf>> {
+        let orphans = self.scan_orphans(pod).await?;
+        for path in &orphans {
+            self.remove_chunk_file(path)?;
+        }
+
+        Ok(orphans)
+    }
 }
 
 #[async_trait]
@@ -43,16 +280,30 @@ impl ChunkStorageTrait for FileStorage {
     async fn store(&self, commitment: FixedBytes<32>, chunk: &Chunk, merkle_proof: &MerkleProof) -> Result<()> {
         self.ensure_dir_exists()?;
 
-        // Store the chunk data
-        let chunk_path = self.chunk_path(commitment, chunk.index);
-        let mut file = File::create(&chunk_path)?;
+        let stored_at = self.clock.now().duration_since(UNIX_EPOCH)?.as_secs();
+        let path = self.chunk_path(commitment, chunk.index);
 
-        let chunk_with_proof = ChunkWithProof { chunk: chunk.clone(), merkle_proof: merkle_proof.clone() };
+        let _guard = self.blob_lock.lock().unwrap();
+        let previous_blob = Self::referenced_blob(&path);
+        let chunk_with_proof = if self.dedup {
+            let data_hash = self.retain_blob(&chunk.data)?;
+            ChunkWithProof { chunk: Chunk { index: chunk.index, data: Vec::new() }, merkle_proof: merkle_proof.clone(), stored_at: Some(stored_at), data_hash: Some(data_hash) }
+        } else {
+            ChunkWithProof { chunk: chunk.clone(), merkle_proof: merkle_proof.clone(), stored_at: Some(stored_at), data_hash: None }
+        };
         let serialized_chunk = serde_json::to_vec(&chunk_with_proof)?;
 
-        file.write_all(&serialized_chunk)?;
-
-        Ok(())
+        let written = self.write_atomically(&path, &self.temp_path(commitment, chunk.index), &serialized_chunk);
+        // on failure the previous file stays in place, so the new reference is the one to drop
+        let released = match (&written, chunk_with_proof.data_hash) {
+            (Err(_), Some(hash)) => Some(hash),
+            (Err(_), None) => None,
+            (Ok(()), _) => previous_blob,
+        };
+        if let Some(hash) = released {
+            self.release_blob(hash)?;
+        }
+        written
     }
 
     async fn retrieve(&self, commitment: FixedBytes<32>, index: u16) -> Result<Option<(Chunk, MerkleProof)>> {
@@ -62,17 +313,40 @@ impl ChunkStorageTrait for FileStorage {
             return Ok(None);
         }
 
-        // Read the chunk data
-        let mut data = Vec::new();
-        let mut file = File::open(&chunk_path)?;
-        file.read_to_end(&mut data)?;
-
-        let deserialized_chunk: ChunkWithProof = serde_json::from_slice(&data)?;
+        // a concurrent delete may remove the file between the check and the read
+        let mut deserialized_chunk = match Self::read_chunk_file(&chunk_path) {
+            Ok(chunk) => chunk,
+            Err(e) if e.downcast_ref::<std::io::Error>().is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound) => return Ok(None),
+            Err(e) => return Err(e),
+        };
         if deserialized_chunk.chunk.index != index {
             return Err(anyhow::anyhow!("Chunk index mismatch"));
         }
+        if let Some(hash) = deserialized_chunk.data_hash {
+            deserialized_chunk.chunk.data = fs::read(self.blob_path(hash))?;
+        }
 
-        Ok(Some((deserialized_chunk.chunk.clone(), deserialized_chunk.merkle_proof.clone())))
+        Ok(Some((deserialized_chunk.chunk, deserialized_chunk.merkle_proof)))
+    }
+
+    // Only chunks kept in a shared blob (see `with_dedup`) stream: their data is read from the blob
+    // file as it is consumed. Inline data is part of the JSON chunk file, so that file is read and
+    // parsed whole, off the async runtime, and served from memory
+    async fn retrieve_stream(&self, commitment: FixedBytes<32>, index: u16) -> Result<Option<ChunkReader>> {
+        let chunk_path = self.chunk_path(commitment, index);
+        let chunk_with_proof = match tokio::task::spawn_blocking(move || Self::read_chunk_file(&chunk_path)).await? {
+            Ok(chunk) => chunk,
+            Err(e) if e.downcast_ref::<std::io::Error>().is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        if chunk_with_proof.chunk.index != index {
+            return Err(anyhow::anyhow!("Chunk index mismatch"));
+        }
+
+        match chunk_with_proof.data_hash {
+            Some(hash) => Ok(Some(Box::new(tokio::fs::File::open(self.blob_path(hash)).await?))),
+            None => Ok(Some(Box::new(std::io::Cursor::new(chunk_with_proof.chunk.data)))),
+        }
     }
 
     async fn exists(&self, commitment: FixedBytes<32>, index: u16) -> Result<bool> {
@@ -88,42 +362,53 @@ impl ChunkStorageTrait for FileStorage {
             return Ok(false);
         }
 
-        fs::remove_file(&chunk_path)?;
+        self.remove_chunk_file(&chunk_path)?;
         Ok(true)
     }
 
     async fn list_chunks(&self, commitment: FixedBytes<32>) -> Result<Vec<u16>> {
         self.ensure_dir_exists()?;
 
-        let mut chunks = Vec::new();
-        for entry in fs::read_dir(&self.base_path)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.extension().and_then(|ext| ext.to_str()) == Some("chunk") {
-                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                    // Filename format: {commitment}_{index}.chunk
-                    let parts: Vec<&str> = stem.split('_').collect();
-                    if parts.len() >= 2 {
-                        if let Ok(index) = parts[1].parse::<u16>() {
-                            if parts[0] == commitment.to_string() {
-                                chunks.push(index);
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        let mut chunks = self.scan_chunk_files()?
+            .into_iter()
+            .filter(|(chunk_commitment, _)| *chunk_commitment == commitment)
+            .map(|(_, index)| index)
+            .collect::<Vec<_>>();
 
         // Sort chunks for consistent ordering
         chunks.sort();
         Ok(chunks)
     }
+
+    async fn list_commitments(&self) -> Result<Vec<FixedBytes<32>>> {
+        self.ensure_dir_exists()?;
+
+        let mut commitments = self.scan_chunk_files()?
+            .into_iter()
+            .map(|(commitment, _)| commitment)
+            .collect::<Vec<_>>();
+
+        commitments.sort();
+        commitments.dedup();
+        Ok(commitments)
+    }
+
+    async fn check_writable(&self) -> Result<()> {
+        self.ensure_dir_exists()?;
+
+        let unique = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let probe = self.base_path.join(format!("ready.{}-{}.{}", std::process::id(), unique, TEMP_EXTENSION));
+        fs::write(&probe, b"ready")?;
+        fs::remove_file(&probe)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use pod::FixedBytes;
+    use common::clock::MockClock;
+    use pod::{client::MockPodaClientTrait, FixedBytes};
     use sha3::{Digest, Keccak256};
     use tempfile::TempDir;
 
@@ -147,9 +432,7 @@ mod tests {
     async fn test_store_and_retrieve() {
         let (storage, _temp_dir, commitment) = setup().await;
         let chunk = create_test_chunk(1);
-        let merkle_proof = MerkleProof {
-            path: vec![],
-        };
+        let merkle_proof = MerkleProof::default();
 
         // Test store
         storage.store(commitment, &chunk, &merkle_proof).await.unwrap();
@@ -165,9 +448,7 @@ mod tests {
     async fn test_exists() {
         let (storage, _temp_dir, commitment) = setup().await;
         let chunk = create_test_chunk(1);
-        let merkle_proof = MerkleProof {
-            path: vec![],
-        };
+        let merkle_proof = MerkleProof::default();
 
         // Initially should not exist
         assert!(!storage.exists(commitment, 1).await.unwrap());
@@ -183,9 +464,7 @@ mod tests {
     async fn test_delete() {
         let (storage, _temp_dir, commitment) = setup().await;
         let chunk = create_test_chunk(1);
-        let merkle_proof = MerkleProof {
-            path: vec![],
-        };
+        let merkle_proof = MerkleProof::default();
 
         // Store the chunk
         storage.store(commitment, &chunk, &merkle_proof).await.unwrap();
@@ -202,9 +481,7 @@ mod tests {
     #[tokio::test]
     async fn test_list_chunks() {
         let (storage, _temp_dir, commitment) = setup().await;
-        let merkle_proof = MerkleProof {
-            path: vec![],
-        };
+        let merkle_proof = MerkleProof::default();
 
         // Store multiple chunks
         for i in 1..=5 {
@@ -218,6 +495,116 @@ mod tests {
         assert_eq!(listed, vec![1, 2, 3, 4, 5]);
     }
 
+    #[tokio::test]
+    async fn test_list_chunks_paged() {
+        let (storage, _temp_dir, commitment) = setup().await;
+        let merkle_proof = MerkleProof::default();
+        for i in [4, 1, 3, 2, 5] {
+            storage.store(commitment, &create_test_chunk(i), &merkle_proof).await.unwrap();
+        }
+
+        // no params returns everything
+        assert_eq!(storage.list_chunks_paged(commitment, 0, None).await.unwrap(), (vec![1, 2, 3, 4, 5], 5));
+        assert_eq!(storage.list_chunks_paged(commitment, 1, Some(2)).await.unwrap(), (vec![2, 3], 5));
+        assert_eq!(storage.list_chunks_paged(commitment, 3, Some(10)).await.unwrap(), (vec![4, 5], 5));
+        assert_eq!(storage.list_chunks_paged(commitment, 10, Some(2)).await.unwrap(), (vec![], 5));
+    }
+
+    #[tokio::test]
+    async fn test_list_commitments() {
+        let (storage, temp_dir, _) = setup().await;
+        let merkle_proof = MerkleProof::default();
+        let mut commitments = [b"first", b"secnd", b"third"].map(|seed| FixedBytes::from_slice(&Keccak256::digest(seed)));
+
+        for (i, commitment) in commitments.iter().enumerate() {
+            for index in 0..=i as u16 {
+                storage.store(*commitment, &create_test_chunk(index), &merkle_proof).await.unwrap();
+            }
+        }
+        std::fs::write(temp_dir.path().join("garbage.chunk"), "junk").unwrap();
+
+        commitments.sort();
+        assert_eq!(storage.list_commitments().await.unwrap(), commitments.to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_gc() {
+        let (_, temp_dir, commitment) = setup().await;
+        let clock = Arc::new(MockClock::default());
+        let storage = FileStorage::new(temp_dir.path()).with_clock(clock.clone());
+        let merkle_proof = MerkleProof::default();
+        let deleted = FixedBytes::from_slice(&Keccak256::digest(b"deleted-data"));
+
+        for i in 0..3 {
+            storage.store(commitment, &create_test_chunk(i), &merkle_proof).await.unwrap();
+            storage.store(deleted, &create_test_chunk(i), &merkle_proof).await.unwrap();
+        }
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_commitment_exists().returning(move |c| Ok(c == commitment));
+
+        // only the deleted commitment goes while the rest is fresh
+        let ttl = Duration::from_secs(3600);
+        assert_eq!(storage.gc(ttl, &pod).await.unwrap(), 3);
+        assert!(storage.list_chunks(deleted).await.unwrap().is_empty());
+        assert_eq!(storage.list_chunks(commitment).await.unwrap(), vec![0, 1, 2]);
+
+        clock.advance(ttl + Duration::from_secs(1));
+        assert_eq!(storage.gc(ttl, &pod).await.unwrap(), 3);
+        assert!(storage.list_chunks(commitment).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scan_orphans() {
+        let (storage, temp_dir, commitment) = setup().await;
+        let merkle_proof = MerkleProof::default();
+        let gone = FixedBytes::from_slice(&Keccak256::digest(b"deleted-data"));
+
+        storage.store(commitment, &create_test_chunk(1), &merkle_proof).await.unwrap();
+        storage.store(gone, &create_test_chunk(2), &merkle_proof).await.unwrap();
+        let malformed = temp_dir.path().join("not-a-commitment_x.chunk");
+        std::fs::write(&malformed, "junk").unwrap();
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_commitment_exists().returning(move |c| Ok(c == commitment));
+
+        let mut expected = vec![malformed, storage.chunk_path(gone, 2)];
+        expected.sort();
+        assert_eq!(storage.scan_orphans(&pod).await.unwrap(), expected);
+
+        assert_eq!(storage.sweep_orphans(&pod).await.unwrap(), expected);
+        assert!(storage.scan_orphans(&pod).await.unwrap().is_empty());
+        assert!(storage.exists(commitment, 1).await.unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_store_and_retrieve() {
+        let (storage, temp_dir, commitment) = setup().await;
+        let storage = Arc::new(storage);
+        let merkle_proof = MerkleProof::default();
+
+        let mut tasks = Vec::new();
+        for task in 0..16u8 {
+            let storage = storage.clone();
+            let merkle_proof = merkle_proof.clone();
+            tasks.push(tokio::spawn(async move {
+                // differently sized payloads make a torn read fail to parse
+                let chunk = Chunk { index: 1, data: vec![task; 4096 * (task as usize + 1)] };
+                for _ in 0..20 {
+                    storage.store(commitment, &chunk, &merkle_proof).await.unwrap();
+                    let (retrieved, _) = storage.retrieve(commitment, 1).await.unwrap().unwrap();
+                    assert!(retrieved.data.iter().all(|b| *b == retrieved.data[0]));
+                }
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        // no temp files are left behind
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
     #[tokio::test]
     async fn test_retrieve_nonexistent() {
         let (storage, _temp_dir, commitment) = setup().await;
@@ -229,9 +616,7 @@ mod tests {
     async fn test_corrupted_chunk() {
         let (storage, _temp_dir, commitment) = setup().await;
         let chunk = create_test_chunk(1);
-        let merkle_proof = MerkleProof {
-            path: vec![],
-        };
+        let merkle_proof = MerkleProof::default();
 
         // Store valid data
         storage.store(commitment, &chunk, &merkle_proof).await.unwrap();
@@ -243,4 +628,55 @@ mod tests {
         // Attempt to retrieve should fail
         assert!(storage.retrieve(commitment, 1).await.is_err());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_dedup_stores_identical_data_once() {
+        let (storage, temp_dir, commitment) = setup().await;
+        let storage = storage.with_dedup(true);
+        let blobs = || fs::read_dir(temp_dir.path().join(BLOB_DIR)).map(|dir| {
+            dir.filter_map(|e| e.ok()).filter(|e| e.path().extension().is_some_and(|ext| ext == "blob")).count()
+        }).unwrap_or(0);
+
+        storage.store(commitment, &create_test_chunk(1), &MerkleProof::default()).await.unwrap();
+        storage.store(commitment, &create_test_chunk(2), &MerkleProof::default()).await.unwrap();
+        assert_eq!(blobs(), 1);
+
+        for index in [1, 2] {
+            let (chunk, _) = storage.retrieve(commitment, index).await.unwrap().unwrap();
+            assert_eq!(chunk.index, index);
+            assert_eq!(chunk.data, create_test_chunk(index).data);
+        }
+
+        // overwriting a chunk with other data moves its reference to a new blob
+        storage.store(commitment, &Chunk { index: 2, data: b"Other".to_vec() }, &MerkleProof::default()).await.unwrap();
+        assert_eq!(blobs(), 2);
+
+        assert!(storage.delete(commitment, 1).await.unwrap());
+        assert_eq!(blobs(), 1);
+        assert_eq!(storage.retrieve(commitment, 2).await.unwrap().unwrap().0.data, b"Other".to_vec());
+
+        assert!(storage.delete(commitment, 2).await.unwrap());
+        assert_eq!(blobs(), 0);
+        assert_eq!(storage.list_chunks(commitment).await.unwrap(), Vec::<u16>::new());
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_stream_matches_retrieve() {
+        let (storage, temp_dir, commitment) = setup().await;
+        let large = Chunk { index: 3, data: (0..200_000u32).map(|i| i as u8).collect() };
+
+        // chunk 1 is kept inline, chunk 3 in a shared blob
+        storage.store(commitment, &create_test_chunk(1), &MerkleProof::default()).await.unwrap();
+        FileStorage::new(temp_dir.path()).with_dedup(true).store(commitment, &large, &MerkleProof::default()).await.unwrap();
+
+        for index in [1, 3] {
+            let mut streamed = Vec::new();
+            let mut reader = storage.retrieve_stream(commitment, index).await.unwrap().unwrap();
+            tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut streamed).await.unwrap();
+
+            let (buffered, _) = storage.retrieve(commitment, index).await.unwrap().unwrap();
+            assert_eq!(streamed, buffered.data);
+        }
+        assert!(storage.retrieve_stream(commitment, 7).await.unwrap().is_none());
+    }
+}
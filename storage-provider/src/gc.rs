@@ -0,0 +1,16 @@
+use std::{sync::Arc, time::Duration};
+use pod::client::PodaClient;
+use common::{clock::Clock, log::{error, info}};
+use crate::FileStorage;
+
+/// Garbage collects chunks older than `older_than` or of removed commitments every `interval`, forever
+pub async fn run_gc(file_storage: Arc<FileStorage>, pod: Arc<PodaClient>, older_than: Duration, interval: Duration, clock: Arc<dyn Clock>) {
+    loop {
+        match file_storage.gc(older_than, pod.as_ref()).await {
+            Ok(removed) => info!("Garbage collection succeeded, removed {} chunks", removed),
+            Err(e) => error!("Garbage collection failed {:?}", e)
+        }
+
+        clock.sleep(interval).await;
+    }
+}
@@ -1,24 +1,39 @@
+use std::collections::HashMap;
 use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::str::FromStr;
 use std::sync::Arc;
-use alloy::primitives::FixedBytes;
-use kzg::{kzg_multi_verify, kzg_verify};
-use merkle_tree::MerkleProof;
-use warp::Filter;
-use serde::{Deserialize, Serialize};
-use pod::client::{PodaClient, PodaClientTrait};
-use crate::storage::ChunkStorageTrait;
+use alloy::primitives::{Address, Bytes, FixedBytes, PrimitiveSignature};
+use alloy::signers::SignerSync;
+use merkle_tree::{MerkleMultiProof, MerkleProof};
+use tokio_util::io::ReaderStream;
+use warp::{Filter, Reply};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use pod::{client::{Commitment, PodaClientTrait}, PrivateKeySigner};
+use crate::{metrics::ProviderMetrics, rate_limit::RateLimiter, storage::ChunkStorageTrait, utils::{verify_chunk_full, verify_chunks_full, ChunkVerifyError}};
+use prometheus::Registry;
 use kzg::types::KzgProof;
 use common::{
-    log::{info, debug, error},
-    types::Chunk
+    constants::TOTAL_SHARDS,
+    http::shared_client,
+    log::{info, debug, error, warn},
+    types::{check_chunk_indices, keccak256, Chunk}
 };
 
-#[derive(Debug, Deserialize)]
-struct StoreRequest {
-    commitment: FixedBytes<32>,
-    chunk: Chunk,
-    kzg_proof: KzgProof,
-    merkle_proof: MerkleProof,
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StoreRequest {
+    pub commitment: FixedBytes<32>,
+    pub chunk: Chunk,
+    pub kzg_proof: KzgProof,
+    pub merkle_proof: MerkleProof,
+}
+
+/// Asks for the chunk at `index` of a commitment to be rebuilt. Sent to a provider's `/recover`,
+/// which forwards it to the dispenser's `/reconstruct` and gets a `StoreRequest` back.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RecoverRequest {
+    pub commitment: FixedBytes<32>,
+    pub index: u16,
 }
 
 #[derive(Debug, Serialize)]
@@ -37,7 +52,56 @@ pub struct BatchStoreRequest {
     pub commitment: FixedBytes<32>,
     pub chunks: Vec<Chunk>,
     pub kzg_proof: KzgProof,
+    // one proof per chunk, left empty when `merkle_multi_proof` is sent instead
+    #[serde(default)]
     pub merkle_proofs: Vec<MerkleProof>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merkle_multi_proof: Option<MerkleMultiProof>,
+}
+
+/// Shape of the merkle proofs a provider accepts in a `BatchStoreRequest`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofFormat {
+    PerChunk,
+    MultiProof,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CapabilitiesResponse {
+    pub proof_formats: Vec<ProofFormat>,
+}
+
+// Bumped on breaking changes to the shape of requests and responses
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Optional parts of the wire protocol a provider can serve
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolFeature {
+    // `BatchStoreRequest::merkle_multi_proof` is accepted
+    Multiproof,
+    // chunks are attested with possession proofs
+    PossessionProofs,
+    // POST /recover rebuilds lost chunks through a dispenser
+    Recovery,
+    // a feature added by a newer provider
+    #[serde(other)]
+    Unknown,
+}
+
+// Every feature this build can serve, whether or not the configuration enables it
+pub const SUPPORTED_FEATURES: &[ProtocolFeature] = &[
+    ProtocolFeature::Multiproof,
+    ProtocolFeature::PossessionProofs,
+    ProtocolFeature::Recovery,
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VersionResponse {
+    pub crate_version: String,
+    pub protocol_version: u32,
+    pub features: Vec<ProtocolFeature>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -52,51 +116,269 @@ pub struct BatchRetrieveResponse {
     pub proofs: Vec<Option<MerkleProof>>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DumpResponse {
+    pub chunks: Vec<Chunk>,
+    pub proofs: Vec<MerkleProof>,
+    pub truncated: bool,
+    pub total: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BatchDeleteRequest {
     pub commitment: FixedBytes<32>,
     pub indices: Vec<u16>,
 }
 
+const DELETE_DOMAIN: &[u8] = b"poda.delete.v1";
+const RECOVER_DOMAIN: &[u8] = b"poda.recover.v1";
+
+/// A request whose signer is recovered from `signature` over its signing hash
+pub trait Signable {
+    fn signing_hash(&self) -> FixedBytes<32>;
+}
+
+impl Signable for BatchDeleteRequest {
+    // keccak256(domain || commitment || indices), indices big-endian
+    fn signing_hash(&self) -> FixedBytes<32> {
+        let indices = self.indices.iter().flat_map(|index| index.to_be_bytes()).collect::<Vec<_>>();
+        keccak256([DELETE_DOMAIN, self.commitment.as_slice(), &indices].concat())
+    }
+}
+
+impl Signable for RecoverRequest {
+    // keccak256(domain || commitment || index), index big-endian
+    fn signing_hash(&self) -> FixedBytes<32> {
+        keccak256([RECOVER_DOMAIN, self.commitment.as_slice(), &self.index.to_be_bytes()].concat())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignedRequest<T> {
+    pub request: T,
+    pub signature: Bytes,
+}
+
+impl<T: Signable> SignedRequest<T> {
+    pub fn sign(request: T, signer: &PrivateKeySigner) -> anyhow::Result<Self> {
+        let signature = signer.sign_hash_sync(&request.signing_hash())?;
+        Ok(Self { request, signature: Bytes::from(signature.as_bytes().to_vec()) })
+    }
+
+    /// The address that signed the request, `None` when the signature is malformed
+    pub fn signer(&self) -> Option<Address> {
+        let signature = PrimitiveSignature::try_from(self.signature.as_ref()).ok()?;
+        signature.recover_address_from_prehash(&self.request.signing_hash()).ok()
+    }
+}
+
+/// A provider's signed statement that it stored `indices` of `commitment`, kept by the dispenser as
+/// evidence should the provider later fail to serve them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoreReceipt {
+    pub commitment: FixedBytes<32>,
+    pub indices: Vec<u16>,
+    pub provider_address: Address,
+    pub signature: Bytes,
+}
+
+impl Signable for StoreReceipt {
+    // keccak256(commitment || indices), indices big-endian
+    fn signing_hash(&self) -> FixedBytes<32> {
+        let indices = self.indices.iter().flat_map(|index| index.to_be_bytes()).collect::<Vec<_>>();
+        keccak256([self.commitment.as_slice(), &indices].concat())
+    }
+}
+
+impl StoreReceipt {
+    pub fn sign(commitment: FixedBytes<32>, indices: Vec<u16>, signer: &PrivateKeySigner) -> anyhow::Result<Self> {
+        let mut receipt = Self { commitment, indices, provider_address: signer.address(), signature: Bytes::new() };
+        let signature = signer.sign_hash_sync(&receipt.signing_hash())?;
+        receipt.signature = Bytes::from(signature.as_bytes().to_vec());
+        Ok(receipt)
+    }
+
+    /// The address that signed the receipt, `None` when the signature is malformed
+    pub fn signer(&self) -> Option<Address> {
+        let signature = PrimitiveSignature::try_from(self.signature.as_ref()).ok()?;
+        signature.recover_address_from_prehash(&self.signing_hash()).ok()
+    }
+
+    /// Whether the receipt was signed by the provider it names
+    pub fn verify(&self) -> bool {
+        self.signer() == Some(self.provider_address)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchStoreResponse {
+    pub success: bool,
+    // left out by providers without a receipt signer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<StoreReceipt>,
+}
+
 #[derive(Debug, Deserialize)]
 struct ListQuery {
     commitment: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
 }
 
 #[derive(Debug, Serialize)]
 struct ListResponse {
     indices: Vec<u16>,
+    // number of chunks stored for the commitment, regardless of the page
+    total: usize,
+}
+
+
+// Default cap on /store and /batch-store bodies, which carry chunk data
+pub const DEFAULT_MAX_BODY_BYTES: u64 = 64 * 1024 * 1024;
+// Cap on bodies of the control endpoints, which only carry commitments and indices
+pub const CONTROL_MAX_BODY_BYTES: u64 = 64 * 1024;
+const OCTET_STREAM: &str = "application/octet-stream";
+// A commitment never has more chunks than this, so larger /batch-retrieve requests are refused
+pub const MAX_BATCH_RETRIEVE_INDICES: usize = TOTAL_SHARDS;
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub max_body_bytes: u64,
+    // chunks stored from a multiproof keep no per-chunk proof, so they cannot answer storage challenges
+    pub accept_multiproof: bool,
+    // attest with per-chunk possession proofs that challenge responses are checked against
+    pub possession_proofs: bool,
+    // dispenser that /recover asks to rebuild lost chunks, recovery is disabled when unset
+    pub dispenser_url: Option<String>,
+    // our registered address, /recover only rebuilds chunks it owns or was assigned
+    pub provider_address: Option<Address>,
+    // keys allowed to sign /delete requests, every delete is refused when empty
+    pub admin_signers: Vec<Address>,
+    // limits /store and /batch-store requests per client IP, unlimited when unset
+    pub store_rate_limiter: Option<Arc<RateLimiter>>,
+    // signs a receipt for every successful /batch-store, no receipts are returned when unset
+    pub receipt_signer: Option<PrivateKeySigner>,
+}
+
+impl ServerConfig {
+    /// The supported features this configuration enables
+    pub fn features(&self) -> Vec<ProtocolFeature> {
+        SUPPORTED_FEATURES.iter().copied().filter(|feature| match feature {
+            ProtocolFeature::Multiproof => self.accept_multiproof,
+            ProtocolFeature::PossessionProofs => self.possession_proofs,
+            ProtocolFeature::Recovery => self.dispenser_url.is_some() && self.provider_address.is_some(),
+            ProtocolFeature::Unknown => false,
+        }).collect()
+    }
 }
 
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self { max_body_bytes: DEFAULT_MAX_BODY_BYTES, accept_multiproof: false, possession_proofs: true, dispenser_url: None, provider_address: None, admin_signers: vec![], store_rate_limiter: None, receipt_signer: None }
+    }
+}
 
-pub async fn start_server<T: ChunkStorageTrait + Send + Sync + 'static>(
+pub async fn start_server<T: ChunkStorageTrait + Send + Sync + 'static, P: PodaClientTrait + Send + Sync + 'static>(
     storage: Arc<T>,
-    pod: Arc<PodaClient>,
+    pod: Arc<P>,
     port: u16,
+    config: ServerConfig,
+    registry: Registry,
 ) {
+    info!("🦀 Rust Storage Provider API starting on port {}", port);
+    warp::serve(routes(storage, pod, config, registry)).run(([0, 0, 0, 0], port)).await;
+}
+
+// JSON body that is rejected with 413 Payload Too Large past `limit` bytes
+fn json_body<B: DeserializeOwned + Send>(limit: u64) -> impl Filter<Extract = (B,), Error = warp::Rejection> + Clone {
+    warp::body::content_length_limit(limit).and(warp::body::json())
+}
+
+#[derive(Debug)]
+struct RateLimited;
+
+impl warp::reject::Reject for RateLimited {}
+
+// Rejects with `RateLimited` once the client has used up its tokens
+fn rate_limit(limiter: Option<Arc<RateLimiter>>) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    warp::addr::remote()
+        .and_then(move |addr: Option<SocketAddr>| {
+            let limiter = limiter.clone();
+            async move {
+                match limiter {
+                    Some(limiter) if !limiter.try_acquire(addr.map(|addr| addr.ip())) => Err(warp::reject::custom(RateLimited)),
+                    _ => Ok(()),
+                }
+            }
+        })
+        .untuple_one()
+}
+
+async fn handle_rate_limited(rejection: warp::Rejection) -> Result<impl warp::Reply, warp::Rejection> {
+    if rejection.find::<RateLimited>().is_none() {
+        return Err(rejection);
+    }
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&StoreResponse { success: false, message: "Too many store requests".to_string() }),
+        warp::http::StatusCode::TOO_MANY_REQUESTS,
+    ))
+}
+
+fn routes<T: ChunkStorageTrait + Send + Sync + 'static, P: PodaClientTrait + Send + Sync + 'static>(
+    storage: Arc<T>,
+    pod: Arc<P>,
+    config: ServerConfig,
+    registry: Registry,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let ServerConfig { max_body_bytes, accept_multiproof, store_rate_limiter, .. } = config.clone();
+    let control_body_bytes = CONTROL_MAX_BODY_BYTES.min(max_body_bytes);
+    let metrics = Arc::new(ProviderMetrics::register(registry).expect("provider metrics are registered once per registry"));
+    let request_metrics = metrics.clone();
     let storage_filter = warp::any().map(move || storage.clone());
     let pod_filter = warp::any().map(move || pod.clone());
+    let metrics_filter = warp::any().map(move || metrics.clone());
+    let config_filter = warp::any().map(move || config.clone());
 
 
     // POST /store - Store a new chunk
     let store = warp::path("store")
         .and(warp::post())
-        .and(warp::body::json())
+        .and(rate_limit(store_rate_limiter.clone()))
+        .and(json_body(max_body_bytes))
         .and(storage_filter.clone())
         .and(pod_filter.clone())
+        .and(metrics_filter.clone())
+        .and(config_filter.clone())
         .and_then(handle_store);
 
     // POST /batch-store - Store multiple chunks
     let batch_store = warp::path("batch-store")
         .and(warp::post())
-        .and(warp::body::json())
+        .and(rate_limit(store_rate_limiter.clone()))
+        .and(json_body(max_body_bytes))
         .and(storage_filter.clone())
         .and(pod_filter.clone())
+        .and(metrics_filter.clone())
+        .and(config_filter.clone())
         .and_then(handle_batch_store);
 
-    // GET /retrieve/{chunk_id} - Retrieve a chunk
+    // POST /recover - Rebuild a lost chunk through the dispenser, store it and re-attest it.
+    // Costs a dispenser reconstruction and possibly gas, so it is signed and rate limited like stores
+    let recover = warp::path("recover")
+        .and(warp::post())
+        .and(rate_limit(store_rate_limiter))
+        .and(json_body(control_body_bytes))
+        .and(storage_filter.clone())
+        .and(pod_filter.clone())
+        .and(metrics_filter.clone())
+        .and(config_filter.clone())
+        .and_then(handle_recover);
+
+    // GET /retrieve/{chunk_id} - Retrieve a chunk, or only its data with Accept: application/octet-stream
     let retrieve = warp::path!("retrieve" / String)
         .and(warp::get())
+        .and(warp::header::optional::<String>("accept"))
         .and(storage_filter.clone())
         .and(pod_filter.clone())
         .and_then(handle_retrieve);
@@ -104,7 +386,7 @@ pub async fn start_server<T: ChunkStorageTrait + Send + Sync + 'static>(
     // POST /batch-retrieve - Retrieve multiple chunks
     let batch_retrieve = warp::path("batch-retrieve")
         .and(warp::post())
-        .and(warp::body::json())
+        .and(json_body(control_body_bytes))
         .and(storage_filter.clone())
         .and(pod_filter.clone())
         .and_then(handle_batch_retrieve);
@@ -116,15 +398,22 @@ pub async fn start_server<T: ChunkStorageTrait + Send + Sync + 'static>(
         .and(pod_filter.clone())
         .and_then(handle_status);
 
-    // DELETE /delete/{chunk_id} - Delete a chunk
+    // GET /dump/{commitment} - Every chunk held for a commitment with its proof, capped in size
+    let dump = warp::path!("dump" / String)
+        .and(warp::get())
+        .and(storage_filter.clone())
+        .and(warp::any().map(move || max_body_bytes))
+        .and_then(handle_dump);
+
+    // POST /delete - Delete chunks, signed by one of the admin signers
     let delete = warp::path!("delete")
         .and(warp::post())
-        .and(warp::body::json())
+        .and(json_body(control_body_bytes))
         .and(storage_filter.clone())
-        .and(pod_filter.clone())
+        .and(config_filter.clone())
         .and_then(handle_batch_delete);
 
-    // GET /list?offset=0&limit=10 - List chunks
+    // GET /list?commitment={commitment}&offset=0&limit=10 - List chunks
     let list = warp::path("list")
         .and(warp::get())
         .and(warp::query::<ListQuery>())
@@ -137,19 +426,54 @@ pub async fn start_server<T: ChunkStorageTrait + Send + Sync + 'static>(
         .and(warp::get())
         .and_then(handle_health_check);
 
-    let routes = store
+    // GET /ready - Readiness check that probes the RPC node and the storage directory
+    let ready = warp::path("ready")
+        .and(warp::get())
+        .and(storage_filter.clone())
+        .and(pod_filter.clone())
+        .and_then(handle_ready);
+
+    // GET /capabilities - Proof formats accepted by /batch-store
+    let capabilities = warp::path("capabilities")
+        .and(warp::get())
+        .map(move || {
+            let proof_formats = if accept_multiproof { vec![ProofFormat::PerChunk, ProofFormat::MultiProof] } else { vec![ProofFormat::PerChunk] };
+            warp::reply::json(&CapabilitiesResponse { proof_formats })
+        });
+
+    // GET /version - Crate and protocol version with the protocol features enabled
+    let version = warp::path("version")
+        .and(warp::get())
+        .and(config_filter.clone())
+        .map(|config: ServerConfig| warp::reply::json(&VersionResponse {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            features: config.features(),
+        }));
+
+    // GET /metrics - Prometheus text format counters
+    let metrics = warp::path("metrics")
+        .and(warp::get())
+        .and(metrics_filter.clone())
+        .map(|metrics: Arc<ProviderMetrics>| warp::reply::with_header(metrics.encode(), "content-type", prometheus::TEXT_FORMAT));
+
+    store
         .or(batch_store)
+        .or(recover)
         .or(retrieve)
         .or(batch_retrieve)
         .or(status)
+        .or(dump)
         .or(delete)
         .or(list)
         .or(health_check)
-        .with(warp::cors().allow_any_origin());
-
-
-    info!("🦀 Rust Storage Provider API starting on port {}", port);
-    warp::serve(routes).run(([0, 0, 0, 0], port)).await;
+        .or(ready)
+        .or(capabilities)
+        .or(version)
+        .or(metrics)
+        .recover(handle_rate_limited)
+        .with(warp::log::custom(move |info| request_metrics.observe_request(info.path(), info.status().as_u16())))
+        .with(warp::cors().allow_any_origin())
 }
 
 async fn handle_health_check() -> Result<impl warp::Reply, Infallible> {
@@ -159,14 +483,86 @@ async fn handle_health_check() -> Result<impl warp::Reply, Infallible> {
     ))
 }
 
-async fn handle_store<T: ChunkStorageTrait>(
+async fn handle_ready<T: ChunkStorageTrait, P: PodaClientTrait>(
+    storage: Arc<T>,
+    pod: Arc<P>,
+) -> Result<impl warp::Reply, Infallible> {
+    let reason = if let Err(e) = pod.get_min_stake().await {
+        Some(format!("Pod RPC is unreachable: {}", e))
+    } else if let Err(e) = storage.check_writable().await {
+        Some(format!("Storage is not writable: {}", e))
+    } else {
+        None
+    };
+
+    Ok(match reason {
+        None => warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"status": "ok"})),
+            warp::http::StatusCode::OK,
+        ),
+        Some(reason) => {
+            warn!(reason, "Provider is not ready");
+            warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({"status": "unavailable", "reason": reason})),
+                warp::http::StatusCode::SERVICE_UNAVAILABLE,
+            )
+        }
+    })
+}
+
+// Logs why a store request was refused so operators can spot a misbehaving dispenser
+fn reject_store(commitment: FixedBytes<32>, indices: &[u16], reason: &str) -> warp::reply::WithStatus<warp::reply::Json> {
+    warn!(%commitment, ?indices, reason, "Rejected store request");
+    warp::reply::with_status(
+        warp::reply::json(&StoreResponse {
+            success: false,
+            message: reason.to_string(),
+        }),
+        warp::http::StatusCode::BAD_REQUEST,
+    )
+}
+
+async fn attest<P: PodaClientTrait>(pod: &P, commitment: FixedBytes<32>, chunks: Vec<Chunk>, possession_proofs: bool) -> anyhow::Result<()> {
+    if possession_proofs {
+        pod.submit_chunk_attestations_with_proof(commitment, chunks).await
+    } else {
+        pod.submit_chunk_attestations(commitment, chunks.iter().map(|c| c.index).collect()).await
+    }
+}
+
+// The commitment is the merkle root the chunks are proven against, so it has to be registered
+// on-chain and every index has to appear once and fall within its chunk count before anything
+// is stored
+fn check_commitment_indices(commitment_info: &Commitment, indices: &[u16]) -> Result<(), String> {
+    if commitment_info.timestamp == 0 {
+        return Err("Commitment is not registered on-chain".to_string());
+    }
+    check_chunk_indices(indices, commitment_info.totalChunks as usize).map_err(|e| e.to_string())
+}
+
+async fn handle_store<T: ChunkStorageTrait, P: PodaClientTrait>(
     request: StoreRequest,
     storage: Arc<T>,
-    pod: Arc<PodaClient>,
+    pod: Arc<P>,
+    metrics: Arc<ProviderMetrics>,
+    config: ServerConfig,
 ) -> Result<impl warp::Reply, Infallible> {
+    if let Err(rejection) = verify_store_request(&request, pod.as_ref(), &metrics).await {
+        return Ok(rejection);
+    }
+
+    Ok(store_and_attest(request, storage.as_ref(), pod.as_ref(), &metrics, config.possession_proofs, true).await)
+}
+
+// Checks a single chunk against the on-chain commitment before it is stored
+async fn verify_store_request<P: PodaClientTrait>(
+    request: &StoreRequest,
+    pod: &P,
+    metrics: &ProviderMetrics,
+) -> Result<(), warp::reply::WithStatus<warp::reply::Json>> {
     let commitment = pod.get_commitment_info(request.commitment).await;
     if commitment.is_err() {
-        return Ok(warp::reply::with_status(
+        return Err(warp::reply::with_status(
             warp::reply::json(&StoreResponse {
                 success: false,
                 message: format!("Failed to get commitment info: {:?}", commitment.err()),
@@ -175,73 +571,192 @@ async fn handle_store<T: ChunkStorageTrait>(
         ));
     }
 
-    let is_valid = merkle_tree::verify_proof(request.commitment, &request.chunk, request.merkle_proof.clone());
-    debug!("Merkle proof verification result for chunk {:?}: {:?}", request.chunk.index, is_valid);
-    if !is_valid {
-        return Ok(warp::reply::with_status(
-            warp::reply::json(&StoreResponse {
-                success: false,
-                message: "Merkle proof verification failed".to_string(),
-            }),
-            warp::http::StatusCode::BAD_REQUEST,
-        ));
+    let (commitment_info, _) = commitment.unwrap();
+    if let Err(reason) = check_commitment_indices(&commitment_info, &[request.chunk.index]) {
+        return Err(reject_store(request.commitment, &[request.chunk.index], &reason));
     }
 
-    let (commitment_info, _) = commitment.unwrap();
-    let is_valid = kzg_verify(&request.chunk, request.chunk.index as usize, commitment_info.kzgCommitment.try_into().unwrap(), request.kzg_proof);
-    if !is_valid {
-        return Ok(warp::reply::with_status(
-            warp::reply::json(&StoreResponse {
-                success: false,
-                message: "KZG proof verification failed".to_string(),
-            }),
-            warp::http::StatusCode::BAD_REQUEST,
-        ));
+    let verified = verify_chunk_full(request.commitment, &request.chunk, &request.merkle_proof, &request.kzg_proof, &commitment_info.kzgCommitment);
+    debug!("Verification result for chunk {:?}: {:?}", request.chunk.index, verified);
+    if let Err(e) = verified {
+        record_verify_failure(metrics, &e);
+        return Err(reject_store(request.commitment, &[request.chunk.index], &e.to_string()));
+    }
+
+    Ok(())
+}
+
+fn record_verify_failure(metrics: &ProviderMetrics, error: &ChunkVerifyError) {
+    match error {
+        ChunkVerifyError::MerkleProof { .. } | ChunkVerifyError::MerkleMultiProof => metrics.merkle_verification_failures.inc(),
+        ChunkVerifyError::KzgProof => metrics.kzg_verification_failures.inc(),
+        ChunkVerifyError::InvalidKzgCommitment => {}
     }
+}
 
+// Stores a verified chunk and, when `attest_chunk` is set, attests it on-chain
+async fn store_and_attest<T: ChunkStorageTrait, P: PodaClientTrait>(
+    request: StoreRequest,
+    storage: &T,
+    pod: &P,
+    metrics: &ProviderMetrics,
+    possession_proofs: bool,
+    attest_chunk: bool,
+) -> warp::reply::WithStatus<warp::reply::Json> {
     match storage.store(request.commitment, &request.chunk, &request.merkle_proof).await {
         Ok(_) => {
             debug!("Chunk stored successfully");
+            metrics.bytes_stored.inc_by(request.chunk.data.len() as u64);
 
-            let res = pod.submit_chunk_attestations(request.commitment, vec![request.chunk.index]).await;
-            if res.is_err() {
-                return Ok(warp::reply::with_status(
-                    warp::reply::json(&StoreResponse {
-                        success: false,
-                        message: format!("Failed to submit chunk attestation: {:?}", res.err()),
-                    }),
-                    warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-                ));
+            if attest_chunk {
+                let res = attest(pod, request.commitment, vec![request.chunk.clone()], possession_proofs).await;
+                if res.is_err() {
+                    return warp::reply::with_status(
+                        warp::reply::json(&StoreResponse {
+                            success: false,
+                            message: format!("Failed to submit chunk attestation: {:?}", res.err()),
+                        }),
+                        warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    );
+                }
             }
 
-            Ok(warp::reply::with_status(
+            warp::reply::with_status(
                 warp::reply::json(&StoreResponse {
                     success: true,
                     message: "Chunk stored successfully".to_string(),
                 }),
                 warp::http::StatusCode::OK,
-            ))
+            )
         }
 
         Err(e) => {
             error!("Error storing chunk: {:?}", e);
-            Ok(warp::reply::with_status(
+            warp::reply::with_status(
                 warp::reply::json(&StoreResponse {
                     success: false,
                     message: format!("Failed to store chunk: {:?}", e),
                 }),
                 warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-            ))
+            )
+        }
+    }
+}
+
+async fn handle_recover<T: ChunkStorageTrait, P: PodaClientTrait>(
+    signed: SignedRequest<RecoverRequest>,
+    storage: Arc<T>,
+    pod: Arc<P>,
+    metrics: Arc<ProviderMetrics>,
+    config: ServerConfig,
+) -> Result<impl warp::Reply, Infallible> {
+    let failure = |message: String, status| warp::reply::with_status(warp::reply::json(&StoreResponse { success: false, message }), status);
+
+    let signer = signed.signer();
+    if !signer.is_some_and(|signer| config.admin_signers.contains(&signer)) {
+        warn!(commitment = %signed.request.commitment, ?signer, "Refusing recovery from an unauthorized signer");
+        return Ok(failure("Unauthorized".to_string(), warp::http::StatusCode::UNAUTHORIZED));
+    }
+    let request = signed.request;
+
+    let (Some(dispenser_url), Some(my_address)) = (config.dispenser_url.as_deref(), config.provider_address) else {
+        return Ok(failure("No dispenser is configured for recovery".to_string(), warp::http::StatusCode::SERVICE_UNAVAILABLE));
+    };
+
+    // only lost chunks are rebuilt, a held chunk would be re-attested for nothing
+    match storage.exists(request.commitment, request.index).await {
+        Ok(false) => {},
+        Ok(true) => return Ok(failure("Chunk is already stored".to_string(), warp::http::StatusCode::CONFLICT)),
+        Err(e) => return Ok(failure(format!("Failed to check chunk: {:?}", e), warp::http::StatusCode::INTERNAL_SERVER_ERROR)),
+    }
+
+    // only chunks that are ours are rebuilt: still attested by us, or cleared after the dispenser
+    // assigned them to us. Anything else would be stored, and attested, on a provider it never chose
+    let owner = match pod.get_chunk_owner(request.commitment, request.index).await {
+        Ok(owner) => owner,
+        Err(e) => return Ok(failure(format!("Failed to get chunk owner: {:?}", e), warp::http::StatusCode::INTERNAL_SERVER_ERROR)),
+    };
+    if owner.is_zero() {
+        match fetch_recorded_assignment(dispenser_url, request.commitment).await {
+            Ok(Some(assignment)) if assignment.get(&my_address).is_some_and(|ids| ids.contains(&request.index)) => {},
+            Ok(_) => return Ok(failure("Chunk was not assigned to this provider".to_string(), warp::http::StatusCode::CONFLICT)),
+            Err(e) => return Ok(failure(format!("Failed to get chunk assignment: {}", e), warp::http::StatusCode::BAD_GATEWAY)),
+        }
+    } else if owner != my_address {
+        return Ok(failure(format!("Chunk is owned by provider {}", owner), warp::http::StatusCode::CONFLICT));
+    }
+
+    let rebuilt = match fetch_reconstructed_chunk(dispenser_url, &request).await {
+        Ok(rebuilt) => rebuilt,
+        Err(e) => {
+            warn!(commitment = %request.commitment, chunk_id = request.index, "Dispenser failed to reconstruct chunk: {:?}", e);
+            return Ok(failure(format!("Dispenser failed to reconstruct chunk: {}", e), warp::http::StatusCode::BAD_GATEWAY));
         }
+    };
+    if rebuilt.commitment != request.commitment || rebuilt.chunk.index != request.index {
+        return Ok(failure("Dispenser returned a different chunk than requested".to_string(), warp::http::StatusCode::BAD_GATEWAY));
+    }
+
+    if let Err(rejection) = verify_store_request(&rebuilt, pod.as_ref(), &metrics).await {
+        return Ok(rejection);
+    }
+
+    // a chunk that was only lost locally is still attested, and attesting it twice reverts
+    info!(commitment = %request.commitment, chunk_id = request.index, reattest = owner.is_zero(), "Recovered chunk");
+    Ok(store_and_attest(rebuilt, storage.as_ref(), pod.as_ref(), &metrics, config.possession_proofs, owner.is_zero()).await)
+}
+
+async fn fetch_reconstructed_chunk(dispenser_url: &str, request: &RecoverRequest) -> anyhow::Result<StoreRequest> {
+    let response = shared_client()
+        .post(format!("{}/reconstruct", dispenser_url.trim_end_matches('/')))
+        .json(request)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(response.json().await?)
+}
+
+// The dispenser's recorded assignment of a commitment, `None` when it kept none
+async fn fetch_recorded_assignment(dispenser_url: &str, commitment: FixedBytes<32>) -> anyhow::Result<Option<HashMap<Address, Vec<u16>>>> {
+    let response = shared_client()
+        .get(format!("{}/assignment/{}", dispenser_url.trim_end_matches('/'), commitment))
+        .send()
+        .await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
     }
+
+    Ok(Some(response.error_for_status()?.json().await?))
 }
 
-async fn handle_batch_retrieve<T: ChunkStorageTrait>(
+async fn handle_batch_retrieve<T: ChunkStorageTrait, P: PodaClientTrait>(
     request: BatchRetrieveRequest,
     storage: Arc<T>,
-    _: Arc<PodaClient>,
+    pod: Arc<P>,
 ) -> Result<impl warp::Reply, Infallible> {
     debug!("Retrieving chunks: {:?}", request);
+    if request.indices.len() > MAX_BATCH_RETRIEVE_INDICES {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": format!("Too many indices: {} (max {})", request.indices.len(), MAX_BATCH_RETRIEVE_INDICES)})),
+            warp::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let commitment_info = match pod.get_commitment_info(request.commitment).await {
+        Ok((commitment_info, _)) => commitment_info,
+        Err(e) => return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": format!("Failed to get commitment info: {:?}", e)})),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    };
+    if let Err(reason) = check_commitment_indices(&commitment_info, &request.indices) {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": reason})),
+            warp::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
     let mut chunks = Vec::new();
     let mut proofs = Vec::new();
     let mut errors = Vec::new();
@@ -279,94 +794,125 @@ async fn handle_batch_retrieve<T: ChunkStorageTrait>(
     ))
 }
 
-async fn handle_retrieve<T: ChunkStorageTrait>(
-    chunk_id: String,
+// Returns chunks in index order until MAX_BATCH_RETRIEVE_INDICES chunks or `max_bytes` of chunk
+// data would be exceeded, the same bound /batch-store puts on what it accepts
+async fn handle_dump<T: ChunkStorageTrait>(
+    commitment: String,
     storage: Arc<T>,
-    _: Arc<PodaClient>,
+    max_bytes: u64,
 ) -> Result<impl warp::Reply, Infallible> {
-    // Parse chunk_id to extract namespace, commitment, and index
-    // Format: {namespace}_{commitment}_{index}
-    let parts: Vec<&str> = chunk_id.split('_').collect();
-    if parts.len() < 3 {
+    let Ok(commitment) = FixedBytes::from_str(&commitment) else {
         return Ok(warp::reply::with_status(
-            warp::reply::json(&serde_json::json!({"error": "Invalid chunk ID format"})),
+            warp::reply::json(&serde_json::json!({"error": "Invalid commitment format"})),
             warp::http::StatusCode::BAD_REQUEST,
         ));
-    }
+    };
 
-    let commitment_hex = parts[1];
-    let index_str = parts[2];
+    let Ok((indices, total)) = storage.list_chunks_paged(commitment, 0, Some(MAX_BATCH_RETRIEVE_INDICES)).await else {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "Internal server error"})),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    };
 
-    let commitment = match hex::decode(commitment_hex) {
-        Ok(bytes) if bytes.len() == 32 => FixedBytes::from_slice(&bytes),
-        _ => {
-            return Ok(warp::reply::with_status(
-                warp::reply::json(&serde_json::json!({"error": "Invalid commitment format"})),
-                warp::http::StatusCode::BAD_REQUEST,
-            ));
+    let mut chunks = Vec::new();
+    let mut proofs = Vec::new();
+    let mut bytes = 0;
+    for index in indices {
+        let Ok(Some((chunk, proof))) = storage.retrieve(commitment, index).await else {
+            continue;
+        };
+        // chunk data serializes as a JSON number array, so cap what actually goes on the wire
+        bytes += (serde_json::to_vec(&chunk).map_or(0, |v| v.len()) + serde_json::to_vec(&proof).map_or(0, |v| v.len())) as u64;
+        if bytes > max_bytes {
+            break;
         }
-    };
+        chunks.push(chunk);
+        proofs.push(proof);
+    }
+    let truncated = chunks.len() < total;
+    if truncated {
+        warn!(%commitment, returned = chunks.len(), total, "Dump was truncated");
+    }
 
-    let index = match index_str.parse::<u16>() {
-        Ok(idx) => idx,
-        Err(_) => {
+    Ok(warp::reply::with_status(
+        warp::reply::json(&DumpResponse { chunks, proofs, truncated, total }),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+// Chunk ids use the same format as stored chunk files: {commitment}_{index}
+fn parse_chunk_id(chunk_id: &str) -> Result<(FixedBytes<32>, u16), &'static str> {
+    let (commitment, index) = chunk_id.split_once('_').ok_or("Invalid chunk ID format")?;
+    let commitment = FixedBytes::from_str(commitment).map_err(|_| "Invalid commitment format")?;
+    let index = index.parse::<u16>().map_err(|_| "Invalid index format")?;
+
+    Ok((commitment, index))
+}
+
+async fn handle_retrieve<T: ChunkStorageTrait, P: PodaClientTrait>(
+    chunk_id: String,
+    accept: Option<String>,
+    storage: Arc<T>,
+    _: Arc<P>,
+) -> Result<warp::reply::Response, Infallible> {
+    let (commitment, index) = match parse_chunk_id(&chunk_id) {
+        Ok(parsed) => parsed,
+        Err(message) => {
             return Ok(warp::reply::with_status(
-                warp::reply::json(&serde_json::json!({"error": "Invalid index format"})),
+                warp::reply::json(&serde_json::json!({"error": message})),
                 warp::http::StatusCode::BAD_REQUEST,
-            ));
+            ).into_response());
         }
     };
 
+    if accept.is_some_and(|accept| accept.contains(OCTET_STREAM)) {
+        return Ok(stream_chunk(storage.as_ref(), commitment, index).await);
+    }
+
     match storage.retrieve(commitment, index).await {
         Ok(Some(chunk)) => {
             Ok(warp::reply::with_status(
                 warp::reply::json(&Some(chunk)),
                 warp::http::StatusCode::OK,
-            ))
+            ).into_response())
         }
         Ok(None) => Ok(warp::reply::with_status(
             warp::reply::json(&None::<Chunk>),
             warp::http::StatusCode::NOT_FOUND,
-        )),
+        ).into_response()),
         Err(_) => Ok(warp::reply::with_status(
             warp::reply::json(&None::<Chunk>),
             warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-        )),
+        ).into_response()),
+    }
+}
+
+// The chunk data alone as the response body, read from storage while it is sent
+async fn stream_chunk<T: ChunkStorageTrait>(storage: &T, commitment: FixedBytes<32>, index: u16) -> warp::reply::Response {
+    match storage.retrieve_stream(commitment, index).await {
+        Ok(Some(reader)) => {
+            let body = warp::hyper::Body::wrap_stream(ReaderStream::new(reader));
+            warp::reply::with_header(warp::reply::Response::new(body), "content-type", OCTET_STREAM).into_response()
+        }
+        Ok(None) => warp::http::StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            error!("Failed to stream chunk {} of {}: {:?}", index, commitment, e);
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
     }
 }
 
-async fn handle_status<T: ChunkStorageTrait>(
+async fn handle_status<T: ChunkStorageTrait, P: PodaClientTrait>(
     chunk_id: String,
     storage: Arc<T>,
-    _: Arc<PodaClient>,
+    _: Arc<P>,
 ) -> Result<impl warp::Reply, Infallible> {
-    // Parse chunk_id to extract namespace, commitment, and index
-    let parts: Vec<&str> = chunk_id.split('_').collect();
-    if parts.len() < 3 {
-        return Ok(warp::reply::with_status(
-            warp::reply::json(&serde_json::json!({"error": "Invalid chunk ID format"})),
-            warp::http::StatusCode::BAD_REQUEST,
-        ));
-    }
-
-    let commitment_hex = parts[1];
-    let index_str = parts[2];
-
-    let commitment = match hex::decode(commitment_hex) {
-        Ok(bytes) if bytes.len() == 32 => FixedBytes::from_slice(&bytes),
-        _ => {
-            return Ok(warp::reply::with_status(
-                warp::reply::json(&serde_json::json!({"error": "Invalid commitment format"})),
-                warp::http::StatusCode::BAD_REQUEST,
-            ));
-        }
-    };
-
-    let index = match index_str.parse::<u16>() {
-        Ok(idx) => idx,
-        Err(_) => {
+    let (commitment, index) = match parse_chunk_id(&chunk_id) {
+        Ok(parsed) => parsed,
+        Err(message) => {
             return Ok(warp::reply::with_status(
-                warp::reply::json(&serde_json::json!({"error": "Invalid index format"})),
+                warp::reply::json(&serde_json::json!({"error": message})),
                 warp::http::StatusCode::BAD_REQUEST,
             ));
         }
@@ -387,10 +933,20 @@ async fn handle_status<T: ChunkStorageTrait>(
 }
 
 async fn handle_batch_delete<T: ChunkStorageTrait>(
-    request: BatchDeleteRequest,
+    signed: SignedRequest<BatchDeleteRequest>,
     storage: Arc<T>,
-    _: Arc<PodaClient>,
+    config: ServerConfig,
 ) -> Result<impl warp::Reply, Infallible> {
+    let signer = signed.signer();
+    if !signer.is_some_and(|signer| config.admin_signers.contains(&signer)) {
+        warn!(commitment = %signed.request.commitment, ?signer, "Refusing delete from an unauthorized signer");
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "Unauthorized"})),
+            warp::http::StatusCode::UNAUTHORIZED,
+        ));
+    }
+
+    let request = signed.request;
     for index in request.indices {
         match storage.delete(request.commitment, index).await {
             Ok(_) => {},
@@ -406,19 +962,19 @@ async fn handle_batch_delete<T: ChunkStorageTrait>(
     Ok(warp::reply::with_status(warp::reply::json(&serde_json::json!({"success": true})), warp::http::StatusCode::OK))
 }
 
-async fn handle_batch_store<T: ChunkStorageTrait>(
+async fn handle_batch_store<T: ChunkStorageTrait, P: PodaClientTrait>(
     request: BatchStoreRequest,
     storage: Arc<T>,
-    pod: Arc<PodaClient>,
+    pod: Arc<P>,
+    metrics: Arc<ProviderMetrics>,
+    config: ServerConfig,
 ) -> Result<impl warp::Reply, Infallible> {
-    if request.merkle_proofs.len() != request.chunks.len() {
-        return Ok(warp::reply::with_status(
-            warp::reply::json(&StoreResponse {
-                success: false,
-                message: "Merkle proofs length does not match chunks length".to_string(),
-            }),
-            warp::http::StatusCode::BAD_REQUEST,
-        ));
+    let indices = request.chunks.iter().map(|c| c.index).collect::<Vec<_>>();
+    if request.merkle_multi_proof.is_some() && !config.accept_multiproof {
+        return Ok(reject_store(request.commitment, &indices, "Merkle multiproofs are not accepted by this provider"));
+    }
+    if request.merkle_multi_proof.is_none() && request.merkle_proofs.len() != request.chunks.len() {
+        return Ok(reject_store(request.commitment, &indices, "Merkle proofs length does not match chunks length"));
     }
 
     let commitment = pod.get_commitment_info(request.commitment).await;
@@ -435,40 +991,35 @@ async fn handle_batch_store<T: ChunkStorageTrait>(
         ));
     }
 
-    for (chunk, merkle_proof) in request.chunks.iter().zip(request.merkle_proofs.iter()) {
-        let is_valid = merkle_tree::verify_proof(request.commitment, chunk, merkle_proof.clone());
-        debug!("Merkle proof verification result for chunk {:?}: {:?}", chunk.index, is_valid);
-        if !is_valid {
-            return Ok(warp::reply::with_status(
-                warp::reply::json(&StoreResponse {
-                    success: false,
-                    message: format!("Merkle proof verification failed for chunk: {:?}", chunk.index),
-                }),
-                warp::http::StatusCode::BAD_REQUEST,
-            ));
-        }
-    }
-
     let (commitment_info, _) = commitment.unwrap();
     info!("Got commitment info: {:?}", commitment_info);
-    let chunk_indices = request.chunks.iter().map(|c| c.index as usize).collect::<Vec<_>>();
-    debug!("Verifying KZG proof for chunks: {:?}", chunk_indices);
-    let is_valid = kzg_multi_verify(&request.chunks, chunk_indices.as_slice(), commitment_info.kzgCommitment.try_into().unwrap(), request.kzg_proof);
-    info!("KZG proof verification result: {:?}", is_valid);
+    if let Err(reason) = check_commitment_indices(&commitment_info, &indices) {
+        return Ok(reject_store(request.commitment, &indices, &reason));
+    }
 
-    if !is_valid {
-        return Ok(warp::reply::with_status(
-            warp::reply::json(&StoreResponse {
-                success: false,
-                message: "KZG proof verification failed".to_string(),
-            }),
-            warp::http::StatusCode::BAD_REQUEST,
-        ));
+    let verified = verify_chunks_full(
+        request.commitment,
+        &request.chunks,
+        &request.merkle_proofs,
+        request.merkle_multi_proof.as_ref(),
+        &request.kzg_proof,
+        &commitment_info.kzgCommitment,
+    );
+    info!("Verification result for chunks {:?}: {:?}", indices, verified);
+    if let Err(e) = verified {
+        record_verify_failure(&metrics, &e);
+        return Ok(reject_store(request.commitment, &indices, &e.to_string()));
     }
 
-    for (chunk, merkle_proof) in request.chunks.iter().zip(request.merkle_proofs.iter()) {
+    // a multiproof cannot be split back into per-chunk proofs, so those chunks are stored without one
+    let merkle_proofs = match request.merkle_multi_proof {
+        Some(_) => vec![MerkleProof::default(); request.chunks.len()],
+        None => request.merkle_proofs,
+    };
+    for (chunk, merkle_proof) in request.chunks.iter().zip(merkle_proofs.iter()) {
         match storage.store(request.commitment, chunk, merkle_proof).await {
             Ok(_) => {
+                metrics.bytes_stored.inc_by(chunk.data.len() as u64);
             }
             Err(e) => {
                 return Ok(warp::reply::with_status(
@@ -482,9 +1033,8 @@ async fn handle_batch_store<T: ChunkStorageTrait>(
         }
     }
 
-    let indices = request.chunks.iter().map(|c| c.index).collect::<Vec<_>>();
     info!("Submitting chunk attestation for indices: {:?}", indices);
-    let res = pod.submit_chunk_attestations(request.commitment, indices).await;
+    let res = attest(pod.as_ref(), request.commitment, request.chunks, config.possession_proofs).await;
     if res.is_err() {
         return Ok(warp::reply::with_status(
             warp::reply::json(&serde_json::json!({"error": "Failed to submit chunk attestation"})),
@@ -492,17 +1042,24 @@ async fn handle_batch_store<T: ChunkStorageTrait>(
         ));
     }
 
-    Ok(warp::reply::with_status(warp::reply::json(&serde_json::json!({"success": true})), warp::http::StatusCode::OK))
+    // the chunks are stored and attested either way, so a failed signature only costs the receipt
+    let receipt = config.receipt_signer.as_ref().and_then(|signer| {
+        StoreReceipt::sign(request.commitment, indices, signer)
+            .inspect_err(|e| error!("Failed to sign store receipt: {:?}", e))
+            .ok()
+    });
+
+    Ok(warp::reply::with_status(warp::reply::json(&BatchStoreResponse { success: true, receipt }), warp::http::StatusCode::OK))
 }
 
-async fn handle_list<T: ChunkStorageTrait>(
+async fn handle_list<T: ChunkStorageTrait, P: PodaClientTrait>(
     query: ListQuery,
     storage: Arc<T>,
-    _: Arc<PodaClient>,
+    _: Arc<P>,
 ) -> Result<impl warp::Reply, Infallible> {
     // Parse commitment from string to FixedBytes
-    let commitment = match hex::decode(&query.commitment) {
-        Ok(bytes) if bytes.len() == 32 => FixedBytes::from_slice(&bytes),
+    let commitment = match FixedBytes::from_str(&query.commitment) {
+        Ok(commitment) => commitment,
         _ => {
             return Ok(warp::reply::with_status(
                 warp::reply::json(&serde_json::json!({"error": "Invalid commitment format"})),
@@ -511,9 +1068,9 @@ async fn handle_list<T: ChunkStorageTrait>(
         }
     };
 
-    match storage.list_chunks(commitment).await {
-        Ok(indices) => Ok(warp::reply::with_status(
-            warp::reply::json(&ListResponse { indices }),
+    match storage.list_chunks_paged(commitment, query.offset.unwrap_or(0), query.limit).await {
+        Ok((indices, total)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ListResponse { indices, total }),
             warp::http::StatusCode::OK,
         )),
         Err(_) => Ok(warp::reply::with_status(
@@ -521,4 +1078,581 @@ async fn handle_list<T: ChunkStorageTrait>(
             warp::http::StatusCode::INTERNAL_SERVER_ERROR,
         )),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{io::Write, sync::Mutex};
+    use kzg::{kzg_commit, kzg_multi_prove};
+    use pod::{client::MockPodaClientTrait, Bytes};
+    use tracing_subscriber::util::SubscriberInitExt;
+    use crate::FileStorage;
+    use merkle_tree::{gen_merkle_tree, gen_multi_proof, gen_proof, StandardMerkleTree};
+
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // `count` chunks of 32 bytes, with their merkle tree and serialized KZG commitment
+    fn test_chunks(count: u16) -> (Vec<Chunk>, StandardMerkleTree, Bytes) {
+        let chunks = (0..count).map(|index| Chunk { index, data: vec![index as u8; 32] }).collect::<Vec<_>>();
+        let (kzg_commitment, _) = kzg_commit(&chunks);
+        let merkle_tree = gen_merkle_tree(&chunks);
+        (chunks, merkle_tree, kzg_commitment.into())
+    }
+
+    // On-chain record of `total` chunks of 32 bytes, `available` of them attested
+    fn commitment_info(total: u16, available: u16, kzg_commitment: Bytes) -> Commitment {
+        Commitment {
+            size: total as u32 * 32,
+            timestamp: 1,
+            totalChunks: total,
+            requiredChunks: total.div_ceil(2),
+            availableChunks: available,
+            kzgCommitment: kzg_commitment,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bad_merkle_proof_rejection_is_logged() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let _guard = tracing_subscriber::fmt().with_writer(move || writer.clone()).with_ansi(false).finish().set_default();
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(|_| Ok((commitment_info(24, 0, Bytes::new()), false)));
+        pod.expect_submit_chunk_attestations_with_proof().never();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(FileStorage::new(temp_dir.path()));
+
+        let commitment = FixedBytes::from([0x11; 32]);
+        let chunks = vec![Chunk { index: 3, data: b"not in the tree".to_vec() }];
+        let request = BatchStoreRequest {
+            commitment,
+            kzg_proof: kzg_multi_prove(&chunks, &[3]),
+            chunks,
+            merkle_proofs: vec![MerkleProof::default()],
+            merkle_multi_proof: None,
+        };
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/batch-store")
+            .json(&request)
+            .reply(&routes(storage, Arc::new(pod), ServerConfig::default(), Registry::new()))
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let rejection = logs.lines().find(|line| line.contains("Rejected store request")).expect("rejection was not logged");
+        assert!(rejection.contains("WARN"));
+        assert!(rejection.contains(&format!("commitment={}", commitment)));
+        assert!(rejection.contains("indices=[3]"));
+        assert!(rejection.contains("reason=\"Merkle proof verification failed for chunk: 3\""));
+    }
+
+    #[tokio::test]
+    async fn test_batch_store_rejects_duplicate_and_out_of_range_indices() {
+        let (chunks, merkle_tree, _) = test_chunks(8);
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(|_| Ok((commitment_info(8, 0, Bytes::new()), false)));
+        pod.expect_submit_chunk_attestations_with_proof().never();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let routes = routes(Arc::new(FileStorage::new(temp_dir.path())), Arc::new(pod), ServerConfig::default(), Registry::new());
+
+        let past_the_end = Chunk { index: 8, data: vec![8; 32] };
+        for (stored, reason) in [
+            (vec![chunks[2].clone(), chunks[2].clone()], "Chunk index 2 appears more than once"),
+            (vec![chunks[2].clone(), past_the_end], "Chunk index 8 out of range (total chunks: 8)"),
+        ] {
+            let request = BatchStoreRequest {
+                commitment: merkle_tree.root(),
+                kzg_proof: kzg_multi_prove(&chunks, &[2]),
+                merkle_proofs: stored.iter().map(|c| gen_proof(&merkle_tree, chunks[c.index as usize % 8].clone()).unwrap()).collect(),
+                chunks: stored,
+                merkle_multi_proof: None,
+            };
+
+            let response = warp::test::request().method("POST").path("/batch-store").json(&request).reply(&routes).await;
+            assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+            assert!(String::from_utf8_lossy(response.body()).contains(reason));
+        }
+        assert!(temp_dir.path().read_dir().unwrap().next().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_version_reports_the_enabled_features() {
+        let version = |config: ServerConfig| async move {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let routes = routes(Arc::new(FileStorage::new(temp_dir.path())), Arc::new(MockPodaClientTrait::new()), config, Registry::new());
+            let response = warp::test::request().path("/version").reply(&routes).await;
+            assert_eq!(response.status(), warp::http::StatusCode::OK);
+            serde_json::from_slice::<VersionResponse>(response.body()).unwrap()
+        };
+
+        let everything = ServerConfig { accept_multiproof: true, dispenser_url: Some("http://localhost:5555".to_string()), provider_address: Some(Address::with_last_byte(1)), ..Default::default() };
+        let response = version(everything).await;
+        assert_eq!(response.crate_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(response.protocol_version, PROTOCOL_VERSION);
+        assert_eq!(response.features, SUPPORTED_FEATURES);
+
+        let response = version(ServerConfig { possession_proofs: false, ..Default::default() }).await;
+        assert!(response.features.is_empty());
+
+        // features of newer providers still parse
+        let newer: VersionResponse = serde_json::from_str(r#"{"crate_version":"9.0.0","protocol_version":1,"features":["multiproof","compression"]}"#).unwrap();
+        assert_eq!(newer.features, vec![ProtocolFeature::Multiproof, ProtocolFeature::Unknown]);
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_streams_raw_data_on_request() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(FileStorage::new(temp_dir.path()).with_dedup(true));
+        let commitment = FixedBytes::from([3; 32]);
+        let chunk = Chunk { index: 2, data: (0..100_000u32).map(|i| (i % 251) as u8).collect() };
+        storage.store(commitment, &chunk, &MerkleProof::default()).await.unwrap();
+        let routes = routes(storage, Arc::new(MockPodaClientTrait::new()), ServerConfig::default(), Registry::new());
+
+        let raw = |path: String| warp::test::request().path(&path).header("accept", OCTET_STREAM).reply(&routes);
+        let response = raw(format!("/retrieve/{}_2", commitment)).await;
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], OCTET_STREAM);
+        assert_eq!(response.body().as_ref(), chunk.data.as_slice());
+        assert_eq!(raw(format!("/retrieve/{}_5", commitment)).await.status(), warp::http::StatusCode::NOT_FOUND);
+
+        // without the header the chunk and its proof still come back as JSON
+        let response = warp::test::request().path(&format!("/retrieve/{}_2", commitment)).reply(&routes).await;
+        let (json_chunk, _) = serde_json::from_slice::<(Chunk, MerkleProof)>(response.body()).unwrap();
+        assert_eq!(json_chunk.data, chunk.data);
+    }
+
+    #[test]
+    fn test_parse_chunk_id() {
+        let commitment = FixedBytes::from([0xab; 32]);
+
+        assert_eq!(parse_chunk_id(&format!("{}_7", commitment)), Ok((commitment, 7)));
+        assert_eq!(parse_chunk_id(&format!("{}_7", hex::encode(commitment))), Ok((commitment, 7)));
+        assert_eq!(parse_chunk_id("ns_0xab_7"), Err("Invalid commitment format"));
+        assert_eq!(parse_chunk_id(&format!("{}_x", commitment)), Err("Invalid index format"));
+        assert_eq!(parse_chunk_id("no-separator"), Err("Invalid chunk ID format"));
+    }
+
+    #[tokio::test]
+    async fn test_batch_store_accepts_multiproof() {
+        let (chunks, merkle_tree, kzg_bytes) = test_chunks(8);
+        let stored = vec![chunks[1].clone(), chunks[4].clone(), chunks[6].clone()];
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(move |_| Ok((commitment_info(8, 0, kzg_bytes.clone()), false)));
+        pod.expect_submit_chunk_attestations_with_proof().times(1).returning(|_, _| Ok(()));
+        let pod = Arc::new(pod);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(FileStorage::new(temp_dir.path()));
+
+        let request = BatchStoreRequest {
+            commitment: merkle_tree.root(),
+            kzg_proof: kzg_multi_prove(&chunks, &[1, 4, 6]),
+            merkle_proofs: vec![],
+            merkle_multi_proof: Some(gen_multi_proof(&merkle_tree, &stored).unwrap()),
+            chunks: stored,
+        };
+
+        // multiproofs are opt-in since the stored chunks could not answer challenges
+        let response = warp::test::request()
+            .method("POST")
+            .path("/batch-store")
+            .json(&request)
+            .reply(&routes(storage.clone(), pod.clone(), ServerConfig::default(), Registry::new()))
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+
+        let config = ServerConfig { accept_multiproof: true, ..Default::default() };
+        let response = warp::test::request()
+            .method("POST")
+            .path("/batch-store")
+            .json(&request)
+            .reply(&routes(storage.clone(), pod, config, Registry::new()))
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+        assert_eq!(storage.list_chunks(merkle_tree.root()).await.unwrap(), vec![1, 4, 6]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_store_returns_a_signed_receipt() {
+        let (chunks, merkle_tree, kzg_bytes) = test_chunks(8);
+        let stored = vec![chunks[2].clone(), chunks[5].clone()];
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(move |_| Ok((commitment_info(8, 0, kzg_bytes.clone()), false)));
+        pod.expect_submit_chunk_attestations_with_proof().times(1).returning(|_, _| Ok(()));
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(FileStorage::new(temp_dir.path()));
+
+        let request = BatchStoreRequest {
+            commitment: merkle_tree.root(),
+            kzg_proof: kzg_multi_prove(&chunks, &[2, 5]),
+            merkle_proofs: stored.iter().map(|chunk| gen_proof(&merkle_tree, chunk.clone()).unwrap()).collect(),
+            merkle_multi_proof: None,
+            chunks: stored,
+        };
+        let provider = PrivateKeySigner::random();
+        let config = ServerConfig { receipt_signer: Some(provider.clone()), ..Default::default() };
+        let response = warp::test::request()
+            .method("POST")
+            .path("/batch-store")
+            .json(&request)
+            .reply(&routes(storage, Arc::new(pod), config, Registry::new()))
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+
+        let receipt = serde_json::from_slice::<BatchStoreResponse>(response.body()).unwrap().receipt.unwrap();
+        assert_eq!((receipt.commitment, receipt.indices.clone(), receipt.provider_address), (merkle_tree.root(), vec![2, 5], provider.address()));
+        assert_eq!(receipt.signer(), Some(provider.address()));
+        assert!(receipt.verify());
+
+        let forged = StoreReceipt { indices: vec![2, 5, 6], ..receipt };
+        assert!(!forged.verify());
+    }
+
+    #[tokio::test]
+    async fn test_recover_rebuilds_and_reattests_lost_chunk() {
+        let (chunks, merkle_tree, kzg_bytes) = test_chunks(8);
+        let commitment = merkle_tree.root();
+
+        let rebuilt = StoreRequest {
+            commitment,
+            chunk: chunks[5].clone(),
+            kzg_proof: kzg::kzg_prove(&chunks, 5),
+            merkle_proof: gen_proof(&merkle_tree, chunks[5].clone()).unwrap(),
+        };
+        let rebuilt = serde_json::to_value(&rebuilt).unwrap();
+        let me = Address::with_last_byte(9);
+        let other = Address::with_last_byte(2);
+        let reconstruct = warp::path("reconstruct")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(move |request: RecoverRequest| {
+                assert_eq!(request.index, 5);
+                warp::reply::json(&rebuilt)
+            });
+        // the dispenser assigned chunk 5 to us and chunk 4 elsewhere
+        let assignment = warp::path!("assignment" / String)
+            .map(move |_| warp::reply::json(&HashMap::from([(me, vec![5u16]), (other, vec![4u16])])));
+        let (addr, server) = warp::serve(reconstruct.or(assignment)).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(move |_| Ok((commitment_info(8, 7, kzg_bytes.clone()), true)));
+        // chunks 4 and 5 were slashed, so their attestations were cleared, and chunk 6 moved to another provider
+        pod.expect_get_chunk_owner().returning(move |_, index| Ok(if index == 6 { other } else { pod::Address::ZERO }));
+        pod.expect_submit_chunk_attestations_with_proof()
+            .withf(|_, chunks| chunks.len() == 1 && chunks[0].index == 5)
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(FileStorage::new(temp_dir.path()));
+        let pod = Arc::new(pod);
+        let operator = PrivateKeySigner::random();
+        let signed = SignedRequest::sign(RecoverRequest { commitment, index: 5 }, &operator).unwrap();
+        let recover = |body: &SignedRequest<RecoverRequest>, config: ServerConfig| {
+            let routes = routes(storage.clone(), pod.clone(), config, Registry::new());
+            let request = warp::test::request().method("POST").path("/recover").json(body);
+            async move { request.reply(&routes).await.status() }
+        };
+
+        let config = ServerConfig { admin_signers: vec![operator.address()], provider_address: Some(me), ..Default::default() };
+        assert_eq!(recover(&signed, config.clone()).await, warp::http::StatusCode::SERVICE_UNAVAILABLE);
+
+        let config = ServerConfig { dispenser_url: Some(format!("http://{}", addr)), ..config };
+        let unsigned = SignedRequest { request: RecoverRequest { commitment, index: 5 }, signature: Bytes::new() };
+        assert_eq!(recover(&unsigned, config.clone()).await, warp::http::StatusCode::UNAUTHORIZED);
+        let stranger = SignedRequest::sign(RecoverRequest { commitment, index: 5 }, &PrivateKeySigner::random()).unwrap();
+        assert_eq!(recover(&stranger, config.clone()).await, warp::http::StatusCode::UNAUTHORIZED);
+
+        // chunks that are not ours are neither rebuilt nor stored
+        for index in [4, 6] {
+            let signed = SignedRequest::sign(RecoverRequest { commitment, index }, &operator).unwrap();
+            assert_eq!(recover(&signed, config.clone()).await, warp::http::StatusCode::CONFLICT);
+            assert!(!storage.exists(commitment, index).await.unwrap());
+        }
+
+        assert_eq!(recover(&signed, config.clone()).await, warp::http::StatusCode::OK);
+        // the chunk is held again, so a second recovery neither rebuilds nor re-attests it
+        assert_eq!(recover(&signed, config).await, warp::http::StatusCode::CONFLICT);
+
+        let (chunk, _) = storage.retrieve(commitment, 5).await.unwrap().expect("recovered chunk is stored");
+        assert_eq!(chunk.data, chunks[5].data);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_bodies_are_rejected() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(FileStorage::new(temp_dir.path()));
+        let routes = routes(storage, Arc::new(MockPodaClientTrait::new()), ServerConfig { max_body_bytes: 1024 * 1024, ..Default::default() }, Registry::new());
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/batch-store")
+            .body(vec![b' '; 1024 * 1024 + 1])
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::PAYLOAD_TOO_LARGE);
+
+        // control endpoints get the tighter limit even though the body is well under max_body_bytes
+        let response = warp::test::request()
+            .method("POST")
+            .path("/batch-retrieve")
+            .body(vec![b' '; CONTROL_MAX_BODY_BYTES as usize + 1])
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_batch_retrieve_rejects_invalid_indices() {
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(|_| Ok((commitment_info(4, 4, Bytes::new()), true)));
+        let temp_dir = tempfile::tempdir().unwrap();
+        let routes = routes(Arc::new(FileStorage::new(temp_dir.path())), Arc::new(pod), ServerConfig::default(), Registry::new());
+        let commitment = FixedBytes::from([0x22; 32]);
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/batch-retrieve")
+            .json(&BatchRetrieveRequest { commitment, indices: vec![1, 4] })
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+        assert!(String::from_utf8_lossy(response.body()).contains("Chunk index 4 out of range"));
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/batch-retrieve")
+            .json(&BatchRetrieveRequest { commitment, indices: vec![0; MAX_BATCH_RETRIEVE_INDICES + 1] })
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+        assert!(String::from_utf8_lossy(response.body()).contains("Too many indices"));
+    }
+
+    #[tokio::test]
+    async fn test_dump_returns_every_stored_chunk() {
+        let (chunks, merkle_tree, _) = test_chunks(6);
+        let commitment = merkle_tree.root();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(FileStorage::new(temp_dir.path()));
+        for index in [0, 2, 3, 5] {
+            let chunk = &chunks[index];
+            storage.store(commitment, chunk, &gen_proof(&merkle_tree, chunk.clone()).unwrap()).await.unwrap();
+        }
+        let dump = |config: ServerConfig| {
+            let routes = routes(storage.clone(), Arc::new(MockPodaClientTrait::new()), config, Registry::new());
+            async move { warp::test::request().method("GET").path(&format!("/dump/{}", commitment)).reply(&routes).await }
+        };
+
+        let response = dump(ServerConfig::default()).await;
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+        let body: DumpResponse = serde_json::from_slice(response.body()).unwrap();
+        let indices = body.chunks.iter().map(|c| c.index).collect::<Vec<_>>();
+        assert_eq!(indices, vec![0, 2, 3, 5]);
+        assert!(!body.truncated);
+        assert_eq!(body.total, 4);
+        for (chunk, proof) in body.chunks.into_iter().zip(body.proofs) {
+            assert!(merkle_tree::verify_proof(commitment, &chunk, proof));
+        }
+
+        // only as many chunks as fit in the body limit once serialized, and the client is told so
+        let serialized = |index: usize| {
+            let chunk = &chunks[index];
+            let proof = gen_proof(&merkle_tree, chunk.clone()).unwrap();
+            (serde_json::to_vec(chunk).unwrap().len() + serde_json::to_vec(&proof).unwrap().len()) as u64
+        };
+        let max_body_bytes = serialized(0) + serialized(2);
+        let response = dump(ServerConfig { max_body_bytes, ..Default::default() }).await;
+        let body: DumpResponse = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body.chunks.len(), 2);
+        assert!(body.truncated);
+        assert_eq!(body.total, 4);
+    }
+
+    #[tokio::test]
+    async fn test_delete_requires_admin_signature() {
+        let chunk = Chunk { index: 0, data: vec![1u8; 32] };
+        let merkle_tree = gen_merkle_tree(std::slice::from_ref(&chunk));
+        let commitment = merkle_tree.root();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(FileStorage::new(temp_dir.path()));
+        storage.store(commitment, &chunk, &gen_proof(&merkle_tree, chunk.clone()).unwrap()).await.unwrap();
+
+        let operator = PrivateKeySigner::random();
+        let config = ServerConfig { admin_signers: vec![operator.address()], ..Default::default() };
+        let routes = routes(storage.clone(), Arc::new(MockPodaClientTrait::new()), config, Registry::new());
+        let delete = |body: SignedRequest<BatchDeleteRequest>| warp::test::request().method("POST").path("/delete").json(&body).reply(&routes);
+        let request = || BatchDeleteRequest { commitment, indices: vec![0] };
+
+        let unsigned = SignedRequest { request: request(), signature: Bytes::new() };
+        assert_eq!(delete(unsigned).await.status(), warp::http::StatusCode::UNAUTHORIZED);
+        let wrong_key = SignedRequest::sign(request(), &PrivateKeySigner::random()).unwrap();
+        assert_eq!(delete(wrong_key).await.status(), warp::http::StatusCode::UNAUTHORIZED);
+        // a signature over different indices recovers to some other address
+        let mut replayed = SignedRequest::sign(BatchDeleteRequest { commitment, indices: vec![1] }, &operator).unwrap();
+        replayed.request.indices = vec![0];
+        assert_eq!(delete(replayed).await.status(), warp::http::StatusCode::UNAUTHORIZED);
+        assert!(storage.retrieve(commitment, 0).await.unwrap().is_some());
+
+        let signed = SignedRequest::sign(request(), &operator).unwrap();
+        assert_eq!(signed.signer(), Some(operator.address()));
+        assert_eq!(delete(signed).await.status(), warp::http::StatusCode::OK);
+        assert!(storage.retrieve(commitment, 0).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_metrics_count_operations() {
+        let (chunks, merkle_tree, kzg_bytes) = test_chunks(4);
+        let commitment = merkle_tree.root();
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_commitment_info().returning(move |_| Ok((commitment_info(4, 0, kzg_bytes.clone()), false)));
+        pod.expect_submit_chunk_attestations_with_proof().returning(|_, _| Ok(()));
+        let temp_dir = tempfile::tempdir().unwrap();
+        let admin = PrivateKeySigner::random();
+        let config = ServerConfig { accept_multiproof: true, admin_signers: vec![admin.address()], ..Default::default() };
+        let routes = routes(Arc::new(FileStorage::new(temp_dir.path())), Arc::new(pod), config, Registry::new());
+
+        let post = |path: &'static str, body: serde_json::Value| warp::test::request().method("POST").path(path).json(&body);
+        let stored = BatchStoreRequest {
+            commitment,
+            kzg_proof: kzg_multi_prove(&chunks, &[0, 1]),
+            merkle_proofs: vec![],
+            merkle_multi_proof: Some(gen_multi_proof(&merkle_tree, &chunks[..2]).unwrap()),
+            chunks: chunks[..2].to_vec(),
+        };
+        let tampered = BatchStoreRequest {
+            commitment,
+            kzg_proof: kzg_multi_prove(&chunks, &[2]),
+            merkle_proofs: vec![gen_proof(&merkle_tree, chunks[3].clone()).unwrap()],
+            merkle_multi_proof: None,
+            chunks: vec![chunks[2].clone()],
+        };
+        assert_eq!(post("/batch-store", serde_json::to_value(&stored).unwrap()).reply(&routes).await.status(), 200);
+        assert_eq!(post("/batch-store", serde_json::to_value(&tampered).unwrap()).reply(&routes).await.status(), 400);
+        let retrieve = serde_json::to_value(BatchRetrieveRequest { commitment, indices: vec![0, 1] }).unwrap();
+        assert_eq!(post("/batch-retrieve", retrieve).reply(&routes).await.status(), 200);
+        let retrieve_missing = serde_json::to_value(BatchRetrieveRequest { commitment, indices: vec![3] }).unwrap();
+        assert_eq!(post("/batch-retrieve", retrieve_missing).reply(&routes).await.status(), 404);
+        let delete = serde_json::to_value(SignedRequest::sign(BatchDeleteRequest { commitment, indices: vec![0] }, &admin).unwrap()).unwrap();
+        assert_eq!(post("/delete", delete).reply(&routes).await.status(), 200);
+
+        let response = warp::test::request().method("GET").path("/metrics").reply(&routes).await;
+        assert_eq!(response.status(), 200);
+        let body = String::from_utf8(response.body().to_vec()).unwrap();
+        for line in [
+            "poda_provider_requests_total{operation=\"store\"} 2",
+            "poda_provider_request_failures_total{operation=\"store\"} 1",
+            "poda_provider_requests_total{operation=\"retrieve\"} 2",
+            "poda_provider_request_failures_total{operation=\"retrieve\"} 1",
+            "poda_provider_requests_total{operation=\"delete\"} 1",
+            "poda_provider_stored_bytes_total 64",
+            "poda_provider_merkle_verification_failures_total 1",
+            "poda_provider_kzg_verification_failures_total 0",
+        ] {
+            assert!(body.lines().any(|l| l == line), "missing {:?} in:\n{}", line, body);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_out_of_range_index_is_rejected() {
+        let (chunks, merkle_tree, _) = test_chunks(4);
+
+        let mut pod = MockPodaClientTrait::new();
+        // the commitment only claims 3 chunks, so index 3 is out of range even with a valid proof
+        pod.expect_get_commitment_info().returning(|_| Ok((commitment_info(3, 0, Bytes::new()), false)));
+        pod.expect_submit_chunk_attestations_with_proof().never();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(FileStorage::new(temp_dir.path()));
+
+        let request = BatchStoreRequest {
+            commitment: merkle_tree.root(),
+            kzg_proof: kzg_multi_prove(&chunks, &[2, 3]),
+            merkle_proofs: vec![gen_proof(&merkle_tree, chunks[2].clone()).unwrap(), gen_proof(&merkle_tree, chunks[3].clone()).unwrap()],
+            merkle_multi_proof: None,
+            chunks: chunks[2..].to_vec(),
+        };
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/batch-store")
+            .json(&request)
+            .reply(&routes(storage.clone(), Arc::new(pod), ServerConfig::default(), Registry::new()))
+            .await;
+
+        assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["message"], "Chunk index 3 out of range (total chunks: 3)");
+        assert!(storage.list_chunks(merkle_tree.root()).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ready_reports_dead_rpc() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let ready = |pod: MockPodaClientTrait| {
+            let routes = routes(Arc::new(FileStorage::new(temp_dir.path())), Arc::new(pod), Default::default(), Registry::new());
+            async move { warp::test::request().method("GET").path("/ready").reply(&routes).await }
+        };
+
+        let mut dead = MockPodaClientTrait::new();
+        dead.expect_get_min_stake().returning(|| Err(anyhow::anyhow!("error sending request: connection refused")));
+        let response = ready(dead).await;
+        assert_eq!(response.status(), warp::http::StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert!(body["reason"].as_str().unwrap().contains("RPC"));
+
+        let mut live = MockPodaClientTrait::new();
+        live.expect_get_min_stake().returning(|| Ok(1));
+        assert_eq!(ready(live).await.status(), warp::http::StatusCode::OK);
+        // the write probe leaves nothing behind
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_store_requests_past_the_rate_limit_get_429() {
+        let clock = Arc::new(common::clock::MockClock::default());
+        let limiter = Arc::new(RateLimiter::new(3, 1.0).with_clock(clock.clone()));
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(FileStorage::new(temp_dir.path()));
+        let config = ServerConfig { store_rate_limiter: Some(limiter), ..Default::default() };
+        let routes = routes(storage, Arc::new(MockPodaClientTrait::new()), config, Registry::new());
+
+        // the limiter runs before the body is parsed, so malformed requests still use up tokens
+        let store = |path: &'static str, client: [u8; 4]| warp::test::request()
+            .method("POST")
+            .path(path)
+            .remote_addr(SocketAddr::from((client, 4000)))
+            .body("{}")
+            .reply(&routes);
+
+        for path in ["/store", "/batch-store", "/store"] {
+            assert_eq!(store(path, [10, 0, 0, 1]).await.status(), warp::http::StatusCode::BAD_REQUEST);
+        }
+        assert_eq!(store("/batch-store", [10, 0, 0, 1]).await.status(), warp::http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(store("/store", [10, 0, 0, 1]).await.status(), warp::http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(store("/store", [10, 0, 0, 2]).await.status(), warp::http::StatusCode::BAD_REQUEST);
+
+        clock.advance(std::time::Duration::from_secs(2));
+        for _ in 0..2 {
+            assert_eq!(store("/batch-store", [10, 0, 0, 1]).await.status(), warp::http::StatusCode::BAD_REQUEST);
+        }
+        assert_eq!(store("/batch-store", [10, 0, 0, 1]).await.status(), warp::http::StatusCode::TOO_MANY_REQUESTS);
+    }
+}
@@ -4,6 +4,10 @@ pub mod file_storage;
 pub mod handlers;
 pub mod utils;
 pub mod responder;
+pub mod gc;
+pub mod metrics;
+pub mod rate_limit;
+pub mod registration;
 
 pub use storage::ChunkStorageTrait;
 pub use file_storage::FileStorage;
@@ -1,17 +1,40 @@
-mod storage;
-mod file_storage;
-mod http;
-mod utils;
-mod responder;
-
 use std::{str::FromStr, sync::Arc, time::Duration};
-use pod::{client::PodaClient, PrivateKeySigner, Address};
-use file_storage::FileStorage;
+use pod::{client::{PodaClient, DEFAULT_GAS_MULTIPLIER}, PrivateKeySigner, Address};
+use storage_provider::{file_storage::FileStorage, gc::run_gc, http::{self, ServerConfig, DEFAULT_MAX_BODY_BYTES}, rate_limit::RateLimiter, registration::sync_provider_url, responder::{run_responder, DEFAULT_RESPONDER_CONCURRENCY, DEFAULT_RESPONSE_MARGIN}};
 use dotenv::dotenv;
-use common::log::{error, info, init_logging};
-use crate::responder::respond_to_active_challenges;
+use prometheus::Registry;
+use common::{clock::system_clock, constants::TOTAL_SHARDS, http::{init_shared_client, HttpTimeouts}, log::{init_logging, warn}};
+
+const DEFAULT_GC_INTERVAL_SECS: u64 = 3600;
+// Store requests regained per second by each client when STORAGE_PROVIDER_STORE_RATE_LIMIT is set
+const DEFAULT_STORE_RATE_REFILL: f64 = 1.0;
 
-fn load_config() -> (String, Address, u16, String, u64) {
+struct Config {
+    rpc_url: String,
+    poda_address: Address,
+    port: u16,
+    private_key: String,
+    responder_interval: u64,
+    responder_concurrency: usize,
+    response_margin: Duration,
+    sweep_orphans: bool,
+    dedup: bool,
+    gc_interval: u64,
+    chunk_ttl: Option<u64>,
+    max_body_bytes: u64,
+    store_rate_limit: Option<u32>,
+    store_rate_refill: f64,
+    accept_multiproof: bool,
+    possession_proofs: bool,
+    dispenser_url: Option<String>,
+    dispenser_address: Option<Address>,
+    gas_multiplier: f64,
+    max_gas_price: Option<u128>,
+    kzg_ceremony_path: Option<String>,
+    public_url: Option<String>,
+}
+
+fn load_config() -> Config {
     dotenv().ok();
     init_logging();
 
@@ -20,35 +43,70 @@ fn load_config() -> (String, Address, u16, String, u64) {
     let port = std::env::var("STORAGE_PROVIDER_PORT").unwrap().parse::<u16>().unwrap();
     let private_key = std::env::var("STORAGE_PROVIDER_PRIVATE_KEY").unwrap();
     let responder_interval = std::env::var("STORAGE_PROVIDER_RESPONDER_INTERVAL").unwrap_or("20".to_string()).parse::<u64>().unwrap();
+    let responder_concurrency = std::env::var("STORAGE_PROVIDER_RESPONDER_CONCURRENCY").map(|v| v.parse::<usize>().unwrap()).unwrap_or(DEFAULT_RESPONDER_CONCURRENCY);
+    let response_margin = std::env::var("STORAGE_PROVIDER_RESPONSE_MARGIN_SECS").map(|v| Duration::from_secs(v.parse::<u64>().unwrap())).unwrap_or(DEFAULT_RESPONSE_MARGIN);
+    let sweep_orphans = std::env::var("STORAGE_PROVIDER_SWEEP_ORPHANS").map(|v| v == "true").unwrap_or(false);
+    let dedup = std::env::var("STORAGE_PROVIDER_DEDUP").map(|v| v == "true").unwrap_or(false);
+    let gc_interval = std::env::var("STORAGE_PROVIDER_GC_INTERVAL").map(|v| v.parse::<u64>().unwrap()).unwrap_or(DEFAULT_GC_INTERVAL_SECS);
+    let chunk_ttl = std::env::var("STORAGE_PROVIDER_CHUNK_TTL").ok().map(|v| v.parse::<u64>().unwrap());
+    let max_body_bytes = std::env::var("STORAGE_PROVIDER_MAX_BODY_BYTES").map(|v| v.parse::<u64>().unwrap()).unwrap_or(DEFAULT_MAX_BODY_BYTES);
+    let store_rate_limit = std::env::var("STORAGE_PROVIDER_STORE_RATE_LIMIT").ok().map(|v| v.parse::<u32>().unwrap());
+    let store_rate_refill = std::env::var("STORAGE_PROVIDER_STORE_RATE_REFILL").map(|v| v.parse::<f64>().unwrap()).unwrap_or(DEFAULT_STORE_RATE_REFILL);
+    let accept_multiproof = std::env::var("STORAGE_PROVIDER_ACCEPT_MULTIPROOF").map(|v| v == "true").unwrap_or(false);
+    let possession_proofs = std::env::var("STORAGE_PROVIDER_POSSESSION_PROOFS").map(|v| v != "false").unwrap_or(true);
+    let dispenser_url = std::env::var("STORAGE_PROVIDER_DISPENSER_URL").ok();
+    let dispenser_address = std::env::var("STORAGE_PROVIDER_DISPENSER_ADDRESS").ok().map(|v| v.parse::<Address>().unwrap());
+    let gas_multiplier = std::env::var("STORAGE_PROVIDER_GAS_MULTIPLIER").map(|v| v.parse::<f64>().unwrap()).unwrap_or(DEFAULT_GAS_MULTIPLIER);
+    let max_gas_price = std::env::var("STORAGE_PROVIDER_MAX_GAS_PRICE").ok().map(|v| v.parse::<u128>().unwrap());
+    let kzg_ceremony_path = std::env::var("KZG_CEREMONY_PATH").ok();
+    let public_url = std::env::var("STORAGE_PROVIDER_PUBLIC_URL").ok();
+    init_shared_client(HttpTimeouts::from_env());
 
-    (rpc_url, poda_address, port, private_key, responder_interval)
+    Config { rpc_url, poda_address, port, private_key, responder_interval, responder_concurrency, response_margin, sweep_orphans, dedup, gc_interval, chunk_ttl, max_body_bytes, store_rate_limit, store_rate_refill, accept_multiproof, possession_proofs, dispenser_url, dispenser_address, gas_multiplier, max_gas_price, kzg_ceremony_path, public_url }
 }
 
 
 #[tokio::main(flavor = "current_thread")]
 pub async fn main() {
-    let (rpc_url, poda_address, port, private_key, responder_interval) = load_config();
+    let Config { rpc_url, poda_address, port, private_key, responder_interval, responder_concurrency, response_margin, sweep_orphans, dedup, gc_interval, chunk_ttl, max_body_bytes, store_rate_limit, store_rate_refill, accept_multiproof, possession_proofs, dispenser_url, dispenser_address, gas_multiplier, max_gas_price, kzg_ceremony_path, public_url } = load_config();
+
+    if let Some(path) = kzg_ceremony_path {
+        kzg::init_from_path(&path, TOTAL_SHARDS).unwrap_or_else(|e| panic!("Failed to load KZG ceremony from {}: {}", path, e));
+    }
 
-    let storage = FileStorage::new("test_storage");
+    let storage = FileStorage::new("test_storage").with_dedup(dedup);
     let storage = Arc::new(storage);
 
     let signer = PrivateKeySigner::from_str(&private_key).unwrap();
     let my_address = signer.address();
 
-    let pod = PodaClient::new(signer, rpc_url.clone(), poda_address).await;
+    let receipt_signer = Some(signer.clone());
+    let pod = PodaClient::new(signer, rpc_url.clone(), poda_address).await
+        .with_gas_multiplier(gas_multiplier)
+        .with_max_gas_price(max_gas_price);
     let pod = Arc::new(pod);
-    let http_server = http::start_server(storage.clone(), pod.clone(), port);
-
-    tokio::spawn(async move {
-        loop {
-            match respond_to_active_challenges(&storage, &pod, my_address).await {
-                Ok(()) => info!("Responding to active challenges succeeded"), 
-                Err(e) => error!("Responding to active challenges failed {:?}", e)
-            }
-
-            tokio::time::sleep(Duration::from_secs(responder_interval)).await;
-        }
-    });
+
+    if let Some(url) = public_url && let Err(e) = sync_provider_url(pod.as_ref(), my_address, &url).await {
+        warn!("Failed to update the registered provider URL to {}: {:?}", url, e);
+    }
+
+    let orphans = if sweep_orphans { storage.sweep_orphans(pod.as_ref()).await } else { storage.scan_orphans(pod.as_ref()).await };
+    match orphans {
+        Ok(orphans) if !orphans.is_empty() => warn!("Found {} orphaned chunk files (swept: {}): {:?}", orphans.len(), sweep_orphans, orphans),
+        Ok(_) => {},
+        Err(e) => warn!("Failed to scan for orphaned chunk files: {:?}", e),
+    }
+
+    // deletes and recoveries must be signed by our own operator key, or by the dispenser for repairs
+    let admin_signers = std::iter::once(my_address).chain(dispenser_address).collect();
+    let store_rate_limiter = store_rate_limit.map(|capacity| Arc::new(RateLimiter::new(capacity, store_rate_refill)));
+    let config = ServerConfig { max_body_bytes, accept_multiproof, possession_proofs, dispenser_url, provider_address: Some(my_address), admin_signers, store_rate_limiter, receipt_signer };
+    let http_server = http::start_server(storage.clone(), pod.clone(), port, config, Registry::new());
+
+    // chunks are kept for as long as their commitment exists unless a TTL is configured
+    let chunk_ttl = chunk_ttl.map(Duration::from_secs).unwrap_or(Duration::MAX);
+    tokio::spawn(run_gc(storage.clone(), pod.clone(), chunk_ttl, Duration::from_secs(gc_interval), system_clock()));
+    tokio::spawn(run_responder(storage, pod, my_address, Duration::from_secs(responder_interval), responder_concurrency, response_margin, system_clock()));
 
     http_server.await;
 }
@@ -0,0 +1,52 @@
+use prometheus::{IntCounter, IntCounterVec, Opts, Registry, TextEncoder};
+
+/// Prometheus counters exposed on the provider's /metrics route
+pub struct ProviderMetrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    failures: IntCounterVec,
+    pub bytes_stored: IntCounter,
+    pub merkle_verification_failures: IntCounter,
+    pub kzg_verification_failures: IntCounter,
+}
+
+impl ProviderMetrics {
+    pub fn register(registry: Registry) -> prometheus::Result<Self> {
+        let requests = IntCounterVec::new(Opts::new("poda_provider_requests_total", "Requests handled, by operation"), &["operation"])?;
+        let failures = IntCounterVec::new(Opts::new("poda_provider_request_failures_total", "Requests answered with an error status, by operation"), &["operation"])?;
+        let bytes_stored = IntCounter::new("poda_provider_stored_bytes_total", "Chunk bytes accepted and written to storage")?;
+        let merkle_verification_failures = IntCounter::new("poda_provider_merkle_verification_failures_total", "Store requests rejected for an invalid merkle proof")?;
+        let kzg_verification_failures = IntCounter::new("poda_provider_kzg_verification_failures_total", "Store requests rejected for an invalid KZG proof")?;
+
+        registry.register(Box::new(requests.clone()))?;
+        registry.register(Box::new(failures.clone()))?;
+        registry.register(Box::new(bytes_stored.clone()))?;
+        registry.register(Box::new(merkle_verification_failures.clone()))?;
+        registry.register(Box::new(kzg_verification_failures.clone()))?;
+
+        Ok(Self { registry, requests, failures, bytes_stored, merkle_verification_failures, kzg_verification_failures })
+    }
+
+    /// Counts a finished request against its operation, derived from the request path
+    pub fn observe_request(&self, path: &str, status: u16) {
+        let operation = match path.trim_start_matches('/').split('/').next() {
+            Some("store") | Some("batch-store") => "store",
+            Some("retrieve") | Some("batch-retrieve") => "retrieve",
+            Some("delete") => "delete",
+            _ => return,
+        };
+
+        self.requests.with_label_values(&[operation]).inc();
+        if status >= 400 {
+            self.failures.with_label_values(&[operation]).inc();
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let mut buffer = String::new();
+        if let Err(e) = TextEncoder::new().encode_utf8(&self.registry.gather(), &mut buffer) {
+            common::log::error!("Failed to encode metrics: {:?}", e);
+        }
+        buffer
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use common::clock::{system_clock, Clock};
+
+// Past this many tracked clients, buckets that have refilled completely are dropped
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    updated_at: SystemTime,
+}
+
+/// Token bucket per client IP. Each request takes a token, and a client holds at most
+/// `capacity` tokens, regained at `refill_per_sec`
+pub struct RateLimiter {
+    capacity: u32,
+    refill_per_sec: f64,
+    clock: Arc<dyn Clock>,
+    // requests without a known remote address share the `None` bucket
+    buckets: Mutex<HashMap<Option<IpAddr>, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(capacity: u32, refill_per_sec: f64) -> Self {
+        Self { capacity, refill_per_sec, clock: system_clock(), buckets: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    fn refilled(&self, bucket: &Bucket, now: SystemTime) -> f64 {
+        let elapsed = now.duration_since(bucket.updated_at).unwrap_or_default().as_secs_f64();
+        (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity as f64)
+    }
+
+    /// Takes a token from the client's bucket, returns false when it is empty
+    pub fn try_acquire(&self, client: Option<IpAddr>) -> bool {
+        let now = self.clock.now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            buckets.retain(|_, bucket| self.refilled(bucket, now) < self.capacity as f64);
+        }
+
+        let bucket = buckets.entry(client).or_insert(Bucket { tokens: self.capacity as f64, updated_at: now });
+        bucket.tokens = self.refilled(bucket, now);
+        bucket.updated_at = now;
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+
+        bucket.tokens -= 1.0;
+        true
+    }
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimiter").field("capacity", &self.capacity).field("refill_per_sec", &self.refill_per_sec).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use common::clock::MockClock;
+
+    #[test]
+    fn test_clients_have_separate_buckets() {
+        let clock = Arc::new(MockClock::default());
+        let limiter = RateLimiter::new(2, 0.5).with_clock(clock.clone());
+        let first = Some(IpAddr::from([10, 0, 0, 1]));
+        let second = Some(IpAddr::from([10, 0, 0, 2]));
+
+        assert!(limiter.try_acquire(first));
+        assert!(limiter.try_acquire(first));
+        assert!(!limiter.try_acquire(first));
+        assert!(limiter.try_acquire(second));
+
+        // one token back every two seconds, never more than the capacity
+        clock.advance(Duration::from_secs(2));
+        assert!(limiter.try_acquire(first));
+        assert!(!limiter.try_acquire(first));
+        clock.advance(Duration::from_secs(60));
+        assert!(limiter.try_acquire(first));
+        assert!(limiter.try_acquire(first));
+        assert!(!limiter.try_acquire(first));
+    }
+}
@@ -0,0 +1,58 @@
+use anyhow::Result;
+use pod::{client::PodaClientTrait, Address};
+use common::log::{info, warn};
+
+/// Updates the URL registered on-chain for `address` when it differs from `url`, the one the
+/// provider is reachable at now. Returns whether an update was sent.
+pub async fn sync_provider_url<P: PodaClientTrait>(pod: &P, address: Address, url: &str) -> Result<bool> {
+    let info = pod.get_provider_info(address).await?;
+    if !info.active {
+        warn!(%address, "Provider is not registered, not updating its URL");
+        return Ok(false);
+    }
+    if info.url == url {
+        return Ok(false);
+    }
+
+    info!(%address, from = %info.url, to = %url, "Updating registered provider URL");
+    pod.update_provider_url(url.to_string()).await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pod::{client::{MockPodaClientTrait, ProviderInfo}, U256};
+
+    fn registered_at(url: &str) -> ProviderInfo {
+        ProviderInfo {
+            name: "Provider".to_string(),
+            addr: Address::repeat_byte(1),
+            url: url.to_string(),
+            registeredAt: 1,
+            challengeCount: 0,
+            challengeSuccessCount: 0,
+            active: true,
+            stakedAmount: U256::from(100),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_provider_url_only_updates_a_changed_url() {
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_provider_info().returning(|_| Ok(registered_at("http://old-host:5556")));
+        pod.expect_update_provider_url().withf(|url| url == "http://new-host:5556").times(1).returning(|_| Ok(()));
+
+        assert!(sync_provider_url(&pod, Address::repeat_byte(1), "http://new-host:5556").await.unwrap());
+        assert!(!sync_provider_url(&pod, Address::repeat_byte(1), "http://old-host:5556").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_sync_provider_url_skips_unregistered_providers() {
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_provider_info().returning(|_| Ok(ProviderInfo { active: false, ..registered_at("") }));
+        pod.expect_update_provider_url().never();
+
+        assert!(!sync_provider_url(&pod, Address::repeat_byte(1), "http://new-host:5556").await.unwrap());
+    }
+}
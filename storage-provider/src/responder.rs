@@ -1,37 +1,209 @@
-use pod::client::{PodaClient, PodaClientTrait};
-use common::{log::{error, info}, types::Address};
+use std::{collections::VecDeque, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}, time::{Duration, UNIX_EPOCH}};
+use futures::{stream, StreamExt};
+use pod::client::{ChallengeInfo, ChallengeTiming, PodaClient, PodaClientError, PodaClientTrait};
+use common::{clock::Clock, log::{error, info, warn}, types::{verify_possession_proof, Address}};
 use anyhow::Result;
 use crate::{storage::ChunkStorageTrait, FileStorage};
 
-pub async fn respond_to_active_challenges(file_storage: &FileStorage, pod: &PodaClient, my_address: Address) -> Result<()> {
-    info!("🫡 Responding to active challenges");
+/// Challenge responses a provider keeps in flight at once unless configured otherwise
+pub const DEFAULT_RESPONDER_CONCURRENCY: usize = 4;
+/// Time a challenge response is assumed to need to confirm unless configured otherwise
+pub const DEFAULT_RESPONSE_MARGIN: Duration = Duration::from_secs(30);
+// Recent confirmation times the estimate is taken from
+const CONFIRMATION_SAMPLES: usize = 16;
 
-    let challenges = pod.get_provider_active_challenges(my_address).await?;
-    info!("🕵️‍♂️ Found {} active challenges", challenges.len());
+/// How long a challenge response takes from being sent until it is confirmed: the slowest of the
+/// recent confirmations, and never less than `margin`
+pub struct ConfirmationEstimate {
+    margin: Duration,
+    recent: Mutex<VecDeque<Duration>>,
+}
 
-    for challenge in challenges {
-        let commitment = challenge.commitment;
-        let chunk_id = challenge.chunkId;
+impl ConfirmationEstimate {
+    pub fn new(margin: Duration) -> Self {
+        Self { margin, recent: Mutex::new(VecDeque::with_capacity(CONFIRMATION_SAMPLES)) }
+    }
 
-        let chunk_with_proof = file_storage.retrieve(commitment, chunk_id).await?;
-        if chunk_with_proof.is_none() {
-            error!("👺 Oooops, we lost a chunk {}, {}", commitment, chunk_id);
-            error!("👺 We will not submit");
-            continue;
+    pub fn record(&self, took: Duration) {
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() == CONFIRMATION_SAMPLES {
+            recent.pop_front();
         }
-        let (chunk, proof) = chunk_with_proof.unwrap();
+        recent.push_back(took);
+    }
 
-        info!("🙌 Responding to challenge: {:?}, {:?}, {:?}", challenge.challenge.challengeId, commitment, chunk_id);
+    pub fn estimate(&self) -> Duration {
+        self.recent.lock().unwrap().iter().copied().fold(self.margin, Duration::max)
+    }
+}
 
-        let result = pod.respond_to_chunk_challenge(commitment, chunk_id, chunk.data.clone().into(), proof.path.clone()).await;
-        if result.is_err() {
-            error!("👺 Failed to respond to challenge: {:?}, {:?}, {:?}", challenge.challenge.challengeId, commitment, chunk_id);
-            continue;
+/// Responds to active challenges every `interval`, forever
+pub async fn run_responder(file_storage: Arc<FileStorage>, pod: Arc<PodaClient>, my_address: Address, interval: Duration, concurrency: usize, response_margin: Duration, clock: Arc<dyn Clock>) {
+    let confirmations = ConfirmationEstimate::new(response_margin);
+    loop {
+        match respond_to_active_challenges(&file_storage, pod.as_ref(), my_address, concurrency, clock.as_ref(), &confirmations).await {
+            Ok(()) => info!("Responding to active challenges succeeded"),
+            Err(e) => error!(error = ?e, "Responding to active challenges failed")
         }
 
-        info!("🍻 Respond success");
+        clock.sleep(interval).await;
+    }
+}
+
+/// Responds to every active challenge of `my_address`, with at most `concurrency` responses in
+/// flight. Responses are started in deadline order, and an error in one does not stop the others.
+/// Challenges that would expire before a response confirms, going by `confirmations`, are skipped.
+pub async fn respond_to_active_challenges<T: PodaClientTrait + ?Sized>(file_storage: &FileStorage, pod: &T, my_address: Address, concurrency: usize, clock: &dyn Clock, confirmations: &ConfirmationEstimate) -> Result<()> {
+    info!("🫡 Responding to active challenges");
+
+    let mut challenges = pod.get_provider_active_challenges(my_address).await?;
+    info!(provider = %my_address, active = challenges.len(), "🕵️‍♂️ Found active challenges");
+    // answer the challenges closest to expiring first
+    challenges.sort_by_key(|challenge| challenge.deadline());
+
+    let deferred = AtomicBool::new(false);
+    let results = stream::iter(challenges)
+        .map(|challenge| respond_to_challenge(file_storage, pod, my_address, challenge, &deferred, clock, confirmations))
+        .buffer_unordered(concurrency.max(1))
+        .collect::<Vec<_>>()
+        .await;
+
+    results.into_iter().collect()
+}
+
+// Set `deferred` stops responses that have not been sent yet, the ones already in flight finish
+async fn respond_to_challenge<T: PodaClientTrait + ?Sized>(file_storage: &FileStorage, pod: &T, my_address: Address, challenge: ChallengeInfo, deferred: &AtomicBool, clock: &dyn Clock, confirmations: &ConfirmationEstimate) -> Result<()> {
+    let commitment = challenge.commitment;
+    let chunk_id = challenge.chunkId;
+
+    let chunk_with_proof = file_storage.retrieve(commitment, chunk_id).await?;
+    if chunk_with_proof.is_none() {
+        error!(%commitment, chunk_id, "👺 Oooops, we lost a chunk. We will not submit");
+        return Ok(());
+    }
+    let (chunk, proof) = chunk_with_proof.unwrap();
+
+    // the contract rejects responses that don't match the proof we attested with
+    let possession_proof = pod.get_possession_proof(commitment, chunk_id).await?;
+    if !possession_proof.is_zero() && !verify_possession_proof(commitment, &chunk, my_address, possession_proof) {
+        error!(%commitment, chunk_id, "👺 Stored chunk does not match our possession proof. We will not submit");
+        return Ok(());
+    }
+
+    if deferred.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    // a response confirmed past the deadline is rejected, so it would only cost gas
+    let now = clock.now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let needed = confirmations.estimate();
+    let remaining = challenge.time_remaining(now).unwrap_or_default();
+    if remaining <= needed {
+        warn!(challenge_id = %challenge.challenge.challengeId, %commitment, chunk_id, deadline = challenge.deadline(), ?remaining, ?needed, "⏰ Challenge expires before a response could confirm, skipping it");
+        return Ok(());
     }
 
+    info!(challenge_id = %challenge.challenge.challengeId, %commitment, chunk_id, "🙌 Responding to challenge");
 
+    let sent_at = clock.now();
+    let result = pod.respond_to_chunk_challenge(commitment, chunk_id, chunk.data.clone().into(), proof.path.clone()).await;
+    if let Err(e) = result {
+        // the fee applies to every remaining response, so they all wait for the next round
+        if let Some(PodaClientError::GasPriceAboveCap { gas_price, cap }) = e.downcast_ref::<PodaClientError>() {
+            warn!(gas_price, cap, "⛽ Gas price is above the cap, deferring challenge responses");
+            deferred.store(true, Ordering::Relaxed);
+            return Ok(());
+        }
+        error!(challenge_id = %challenge.challenge.challengeId, %commitment, chunk_id, "👺 Failed to respond to challenge");
+        return Ok(());
+    }
+
+    confirmations.record(clock.now().duration_since(sent_at).unwrap_or_default());
+    info!(%commitment, chunk_id, "🍻 Respond success");
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::HashSet, sync::Mutex};
+    use pod::{client::{ChunkChallenge, MockPodaClientTrait, CHALLENGE_PERIOD}, FixedBytes};
+    use common::{clock::MockClock, types::Chunk};
+    use merkle_tree::MerkleProof;
+    use tempfile::TempDir;
+
+    fn challenge(commitment: FixedBytes<32>, chunk_id: u16) -> ChallengeInfo {
+        ChallengeInfo {
+            challenge: ChunkChallenge {
+                challengeId: FixedBytes::with_last_byte(chunk_id as u8),
+                challenger: Address::ZERO,
+                issuedAt: chunk_id as u32,
+            },
+            commitment,
+            chunkId: chunk_id,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_responds_to_every_challenge_with_bounded_concurrency() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = FileStorage::new(temp_dir.path());
+        let commitment = FixedBytes::from([9u8; 32]);
+        let chunk_ids = (0..7u16).collect::<Vec<_>>();
+        for &index in &chunk_ids {
+            storage.store(commitment, &Chunk { index, data: vec![index as u8; 32] }, &MerkleProof::default()).await.unwrap();
+        }
+
+        let challenges = chunk_ids.iter().rev().map(|&id| challenge(commitment, id)).collect::<Vec<_>>();
+        let responded = Arc::new(Mutex::new(Vec::new()));
+        let recorded = responded.clone();
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_provider_active_challenges().returning(move |_| Ok(challenges.clone()));
+        pod.expect_get_possession_proof().returning(|_, _| Ok(FixedBytes::ZERO));
+        pod.expect_respond_to_chunk_challenge()
+            .times(chunk_ids.len())
+            .returning(move |_, chunk_id, data, _| {
+                assert_eq!(data.to_vec(), vec![chunk_id as u8; 32]);
+                recorded.lock().unwrap().push(chunk_id);
+                Ok(())
+            });
+
+        let clock = MockClock::new(UNIX_EPOCH);
+        respond_to_active_challenges(&storage, &pod, Address::repeat_byte(1), 2, &clock, &ConfirmationEstimate::new(DEFAULT_RESPONSE_MARGIN)).await.unwrap();
+
+        let responded = responded.lock().unwrap();
+        assert_eq!(responded.len(), chunk_ids.len());
+        assert_eq!(responded.iter().copied().collect::<HashSet<_>>(), chunk_ids.into_iter().collect());
+    }
+
+    #[tokio::test]
+    async fn test_skips_challenges_expiring_before_a_response_confirms() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = FileStorage::new(temp_dir.path());
+        let commitment = FixedBytes::from([4u8; 32]);
+        for index in [1, 2] {
+            storage.store(commitment, &Chunk { index, data: vec![index as u8; 32] }, &MerkleProof::default()).await.unwrap();
+        }
+
+        // chunk 1 was challenged a period ago and has seconds left, chunk 2 was just challenged
+        let clock = MockClock::default();
+        let now = clock.now().duration_since(UNIX_EPOCH).unwrap().as_secs() as u32;
+        let mut imminent = challenge(commitment, 1);
+        imminent.challenge.issuedAt = now - CHALLENGE_PERIOD.as_secs() as u32 + 5;
+        let mut fresh = challenge(commitment, 2);
+        fresh.challenge.issuedAt = now;
+
+        let mut pod = MockPodaClientTrait::new();
+        pod.expect_get_provider_active_challenges().returning(move |_| Ok(vec![imminent.clone(), fresh.clone()]));
+        pod.expect_get_possession_proof().returning(|_, _| Ok(FixedBytes::ZERO));
+        pod.expect_respond_to_chunk_challenge().times(1).withf(|_, chunk_id, _, _| *chunk_id == 2).returning(|_, _, _, _| Ok(()));
+
+        let confirmations = ConfirmationEstimate::new(DEFAULT_RESPONSE_MARGIN);
+        respond_to_active_challenges(&storage, &pod, Address::repeat_byte(1), 2, &clock, &confirmations).await.unwrap();
+
+        // slow confirmations raise the estimate above the margin
+        confirmations.record(Duration::from_secs(90));
+        assert_eq!(confirmations.estimate(), Duration::from_secs(90));
+    }
+}
@@ -2,12 +2,36 @@ use alloy::primitives::FixedBytes;
 use anyhow::Result;
 use merkle_tree::MerkleProof;
 use common::types::Chunk;
+use tokio::io::AsyncRead;
+
+/// Chunk data read as it is consumed
+pub type ChunkReader = Box<dyn AsyncRead + Send + Unpin>;
 
 #[async_trait::async_trait]
-pub trait ChunkStorageTrait {
+pub trait ChunkStorageTrait: Send + Sync {
     async fn store(&self, commitment: FixedBytes<32>, chunk: &Chunk, merkle_proof: &MerkleProof) -> Result<()>;
     async fn retrieve(&self, commitment: FixedBytes<32>, index: u16) -> Result<Option<(Chunk, MerkleProof)>>;
     async fn exists(&self, commitment: FixedBytes<32>, index: u16) -> Result<bool>;
     async fn delete(&self, commitment: FixedBytes<32>, index: u16) -> Result<bool>;
     async fn list_chunks(&self, commitment: FixedBytes<32>) -> Result<Vec<u16>>;
+    /// Every commitment with at least one stored chunk, sorted
+    async fn list_commitments(&self) -> Result<Vec<FixedBytes<32>>>;
+    /// Fails if new chunks could not be written right now
+    async fn check_writable(&self) -> Result<()>;
+
+    /// The data of a chunk as a reader. Buffers the whole chunk unless the storage can do better
+    async fn retrieve_stream(&self, commitment: FixedBytes<32>, index: u16) -> Result<Option<ChunkReader>> {
+        let chunk = self.retrieve(commitment, index).await?;
+        Ok(chunk.map(|(chunk, _)| Box::new(std::io::Cursor::new(chunk.data)) as ChunkReader))
+    }
+
+    /// Returns a page of the sorted chunk indices of a commitment together with the total count
+    async fn list_chunks_paged(&self, commitment: FixedBytes<32>, offset: usize, limit: Option<usize>) -> Result<(Vec<u16>, usize)> {
+        let mut chunks = self.list_chunks(commitment).await?;
+        chunks.sort();
+
+        let total = chunks.len();
+        let page = chunks.into_iter().skip(offset).take(limit.unwrap_or(usize::MAX)).collect();
+        Ok((page, total))
+    }
 }
\ No newline at end of file
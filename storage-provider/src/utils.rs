@@ -0,0 +1,165 @@
+use alloy::primitives::{Bytes, FixedBytes};
+use common::types::Chunk;
+use kzg::{kzg_multi_verify, kzg_verify, types::{KzgCommitment, KzgProof}};
+use merkle_tree::{MerkleMultiProof, MerkleProof};
+
+/// Which check a chunk failed before it could be stored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkVerifyError {
+    MerkleProof { index: u16 },
+    MerkleMultiProof,
+    // the commitment recorded on-chain does not decode to a KZG commitment
+    InvalidKzgCommitment,
+    KzgProof,
+}
+
+impl std::fmt::Display for ChunkVerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChunkVerifyError::MerkleProof { index } => write!(f, "Merkle proof verification failed for chunk: {}", index),
+            ChunkVerifyError::MerkleMultiProof => write!(f, "Merkle multiproof verification failed"),
+            ChunkVerifyError::InvalidKzgCommitment => write!(f, "Invalid on-chain KZG commitment"),
+            ChunkVerifyError::KzgProof => write!(f, "KZG proof verification failed"),
+        }
+    }
+}
+
+impl std::error::Error for ChunkVerifyError {}
+
+/// Checks a chunk's merkle proof against `commitment`, then its KZG proof against the on-chain
+/// `kzg_commitment`
+pub fn verify_chunk_full(
+    commitment: FixedBytes<32>,
+    chunk: &Chunk,
+    merkle_proof: &MerkleProof,
+    kzg_proof: &KzgProof,
+    kzg_commitment: &Bytes,
+) -> Result<(), ChunkVerifyError> {
+    if !merkle_tree::verify_proof(commitment, chunk, merkle_proof.clone()) {
+        return Err(ChunkVerifyError::MerkleProof { index: chunk.index });
+    }
+
+    let kzg_commitment = KzgCommitment::try_from(kzg_commitment.clone()).map_err(|_| ChunkVerifyError::InvalidKzgCommitment)?;
+    if !kzg_verify(chunk, chunk.index as usize, kzg_commitment, kzg_proof.clone()) {
+        return Err(ChunkVerifyError::KzgProof);
+    }
+
+    Ok(())
+}
+
+/// Batch form of `verify_chunk_full`, with either one merkle proof per chunk or a single
+/// multiproof, and one KZG proof opening every chunk
+pub fn verify_chunks_full(
+    commitment: FixedBytes<32>,
+    chunks: &[Chunk],
+    merkle_proofs: &[MerkleProof],
+    merkle_multi_proof: Option<&MerkleMultiProof>,
+    kzg_proof: &KzgProof,
+    kzg_commitment: &Bytes,
+) -> Result<(), ChunkVerifyError> {
+    match merkle_multi_proof {
+        Some(multi_proof) => {
+            if !merkle_tree::verify_multi_proof(commitment, chunks, multi_proof.clone()) {
+                return Err(ChunkVerifyError::MerkleMultiProof);
+            }
+        }
+        None => {
+            for (chunk, merkle_proof) in chunks.iter().zip(merkle_proofs) {
+                if !merkle_tree::verify_proof(commitment, chunk, merkle_proof.clone()) {
+                    return Err(ChunkVerifyError::MerkleProof { index: chunk.index });
+                }
+            }
+        }
+    }
+
+    let kzg_commitment = KzgCommitment::try_from(kzg_commitment.clone()).map_err(|_| ChunkVerifyError::InvalidKzgCommitment)?;
+    let chunk_indices = chunks.iter().map(|c| c.index as usize).collect::<Vec<_>>();
+    if !kzg_multi_verify(chunks, &chunk_indices, kzg_commitment, kzg_proof.clone()) {
+        return Err(ChunkVerifyError::KzgProof);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kzg::{kzg_commit, kzg_multi_prove, kzg_prove};
+    use merkle_tree::{gen_merkle_tree, gen_multi_proof, gen_proof};
+
+    fn sample_chunks() -> Vec<Chunk> {
+        (0..8u16).map(|index| Chunk { index, data: vec![index as u8; 32] }).collect()
+    }
+
+    #[test]
+    fn test_verify_chunk_full() {
+        let chunks = sample_chunks();
+        let merkle_tree = gen_merkle_tree(&chunks);
+        let (kzg_commitment, _) = kzg_commit(&chunks);
+        let kzg_commitment: Bytes = kzg_commitment.into();
+        let chunk = &chunks[3];
+        let merkle_proof = gen_proof(&merkle_tree, chunk.clone()).unwrap();
+        let kzg_proof = kzg_prove(&chunks, 3);
+
+        assert_eq!(verify_chunk_full(merkle_tree.root(), chunk, &merkle_proof, &kzg_proof, &kzg_commitment), Ok(()));
+
+        let wrong_proof = gen_proof(&merkle_tree, chunks[4].clone()).unwrap();
+        assert_eq!(
+            verify_chunk_full(merkle_tree.root(), chunk, &wrong_proof, &kzg_proof, &kzg_commitment),
+            Err(ChunkVerifyError::MerkleProof { index: 3 })
+        );
+
+        // chunk 3's data and genuine proof, claimed as chunk 2
+        let relabeled = Chunk { index: 2, ..chunk.clone() };
+        assert_eq!(
+            verify_chunk_full(merkle_tree.root(), &relabeled, &merkle_proof, &kzg_proof, &kzg_commitment),
+            Err(ChunkVerifyError::MerkleProof { index: 2 })
+        );
+
+        let wrong_kzg_proof = kzg_prove(&chunks, 4);
+        assert_eq!(
+            verify_chunk_full(merkle_tree.root(), chunk, &merkle_proof, &wrong_kzg_proof, &kzg_commitment),
+            Err(ChunkVerifyError::KzgProof)
+        );
+
+        assert_eq!(
+            verify_chunk_full(merkle_tree.root(), chunk, &merkle_proof, &kzg_proof, &Bytes::from(vec![1, 2, 3])),
+            Err(ChunkVerifyError::InvalidKzgCommitment)
+        );
+    }
+
+    #[test]
+    fn test_verify_chunks_full() {
+        let chunks = sample_chunks();
+        let merkle_tree = gen_merkle_tree(&chunks);
+        let (kzg_commitment, _) = kzg_commit(&chunks);
+        let kzg_commitment: Bytes = kzg_commitment.into();
+        let batch = vec![chunks[1].clone(), chunks[4].clone()];
+        let merkle_proofs = batch.iter().map(|c| gen_proof(&merkle_tree, c.clone()).unwrap()).collect::<Vec<_>>();
+        let multi_proof = gen_multi_proof(&merkle_tree, &batch).unwrap();
+        let kzg_proof = kzg_multi_prove(&chunks, &[1, 4]);
+
+        assert_eq!(verify_chunks_full(merkle_tree.root(), &batch, &merkle_proofs, None, &kzg_proof, &kzg_commitment), Ok(()));
+        assert_eq!(verify_chunks_full(merkle_tree.root(), &batch, &[], Some(&multi_proof), &kzg_proof, &kzg_commitment), Ok(()));
+
+        let swapped = vec![merkle_proofs[1].clone(), merkle_proofs[0].clone()];
+        assert_eq!(
+            verify_chunks_full(merkle_tree.root(), &batch, &swapped, None, &kzg_proof, &kzg_commitment),
+            Err(ChunkVerifyError::MerkleProof { index: 1 })
+        );
+
+        let other_batch = vec![chunks[2].clone(), chunks[5].clone()];
+        let other_multi_proof = gen_multi_proof(&merkle_tree, &other_batch).unwrap();
+        assert_eq!(
+            verify_chunks_full(merkle_tree.root(), &batch, &[], Some(&other_multi_proof), &kzg_proof, &kzg_commitment),
+            Err(ChunkVerifyError::MerkleMultiProof)
+        );
+
+        let other_chunks = chunks.iter().map(|c| Chunk { index: c.index, data: vec![0xff; 32] }).collect::<Vec<_>>();
+        let (other_commitment, _) = kzg_commit(&other_chunks);
+        assert_eq!(
+            verify_chunks_full(merkle_tree.root(), &batch, &merkle_proofs, None, &kzg_proof, &other_commitment.into()),
+            Err(ChunkVerifyError::KzgProof)
+        );
+    }
+}
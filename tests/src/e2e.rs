@@ -2,41 +2,39 @@
 mod tests {
     use crate::setup;
 
-    use client::{health_check, retrieve_data, submit_data};
-    use merkle_tree::MerkleProof;
-    use pod::{client::{PodaClient, PodaClientTrait}, Address, FixedBytes, PrivateKeySigner, U256};
+    use client::{audit_chunk, health_check, retrieve_data, submit_data};
+    use merkle_tree::{gen_merkle_tree, MerkleProof};
+    use pod::{client::{PodaClient, PodaClientTrait}, Address, FixedBytes, U256};
     use reqwest::Response;
-    use common::{constants::{ONE_ETH, REQUIRED_SHARDS, TOTAL_SHARDS}, log::info, types::Chunk};
+    use common::{clock::system_clock, constants::{ONE_ETH, REQUIRED_SHARDS, TOTAL_SHARDS}, log::info, types::Chunk};
     use kzg::types::{KzgCommitment, KzgProof};
     use anyhow::Result;
-    use setup::setup::{setup_pod, Setup};
-    use storage_provider::{responder::respond_to_active_challenges, storage::ChunkStorageTrait};
+    use setup::setup::{setup_pod, Setup, StorageServerHandle};
+    use storage_provider::{http::{BatchDeleteRequest, RecoverRequest, SignedRequest}, registration::sync_provider_url, responder::{respond_to_active_challenges, ConfirmationEstimate, DEFAULT_RESPONDER_CONCURRENCY, DEFAULT_RESPONSE_MARGIN}, storage::ChunkStorageTrait};
     use ark_bls12_381::G1Projective as G1;
     use ark_std::UniformRand;
 
     const RPC_URL: &str = "http://localhost:8545";
     const N_STORAGE_PROVIDERS: usize = 3;
 
-    async fn delete_provider_chunk(provider_url: &str, commitment: &FixedBytes<32>, chunks: &Vec<u16>) -> Result<Response, reqwest::Error> {
+    // deletes are signed with the provider's own operator key
+    async fn delete_provider_chunk(provider: &StorageServerHandle, commitment: &FixedBytes<32>, chunks: &[u16]) -> Result<Response> {
         let client = reqwest::Client::new();
-        let url = format!("{}/delete", provider_url);
+        let url = format!("{}/delete", provider.base_url);
+        let request = BatchDeleteRequest { commitment: *commitment, indices: chunks.to_vec() };
+        let signed = SignedRequest::sign(request, provider.pod.signer.as_ref().unwrap())?;
 
-        client.post(&url).json(&serde_json::json!({
-            "commitment": commitment,
-            "indices": chunks
-        })).send().await
+        Ok(client.post(&url).json(&signed).send().await?)
     }
 
     async fn get_view_poda_client(poda_address: Address) -> PodaClient {
-        let random_signer = PrivateKeySigner::random();
-        PodaClient::new(random_signer, RPC_URL.to_string(), poda_address).await
+        PodaClient::new_read_only(RPC_URL.to_string(), poda_address).await
     }
 
     #[tokio::test]
     async fn test_setup() {
         let Setup { poda_address, dispencer_handle, storage_server_handles, challenger: _ } = setup_pod(N_STORAGE_PROVIDERS, RPC_URL, false).await;
-        let random_signer = PrivateKeySigner::random();
-        let poda_client = PodaClient::new(random_signer, RPC_URL.to_string(), poda_address).await;
+        let poda_client = PodaClient::new_read_only(RPC_URL.to_string(), poda_address).await;
 
         if health_check(dispencer_handle.base_url.clone()).await.is_err() {
             panic!("Dispencer health check failed");
@@ -56,8 +54,7 @@ mod tests {
     async fn test_store_data() {
         #[allow(unused_variables)]
         let Setup { poda_address, dispencer_handle, storage_server_handles, challenger: _ } = setup_pod(N_STORAGE_PROVIDERS, RPC_URL, false).await;
-        let random_signer = PrivateKeySigner::random();
-        let poda_client = PodaClient::new(random_signer, RPC_URL.to_string(), poda_address).await;
+        let poda_client = PodaClient::new_read_only(RPC_URL.to_string(), poda_address).await;
 
         let data = b"hello, world".repeat(10);
 
@@ -73,7 +70,7 @@ mod tests {
         let providers = poda_client.get_eligible_providers().await.unwrap();
         for provider in providers {
             let provider_chunks = poda_client.get_provider_chunks(result.commitment, provider.addr).await.unwrap();
-            let assignment = result.assignments.get(&provider.name).unwrap();
+            let assignment = result.assignments.get(&provider.addr).unwrap();
 
 
             for chunk in assignment {
@@ -82,6 +79,29 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_submitted_commitment_matches_chain() {
+        let Setup { poda_address, dispencer_handle, storage_server_handles, challenger: _ } = setup_pod(N_STORAGE_PROVIDERS, RPC_URL, false).await;
+        let poda_client = get_view_poda_client(poda_address).await;
+
+        let data = b"hello, world".repeat(10);
+        let result = submit_data(&dispencer_handle.base_url, &data).await.unwrap();
+
+        let chunks = dispencer_handle.dispencer.encode_payload(&data);
+        assert_eq!(result.commitment, gen_merkle_tree(&chunks).root());
+        assert!(poda_client.commitment_exists(result.commitment).await.unwrap());
+        assert!(poda_client.get_commitment_list().await.unwrap().contains(&result.commitment));
+
+        // providers address chunks as {commitment}_{index}
+        for provider in &storage_server_handles {
+            for index in poda_client.get_provider_chunks(result.commitment, provider.owner_address).await.unwrap() {
+                let url = format!("{}/status/{}_{}", provider.base_url, result.commitment, index);
+                let status: serde_json::Value = reqwest::get(&url).await.unwrap().json().await.unwrap();
+                assert_eq!(status["exists"], true);
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_retrieve_data() {
         #[allow(unused_variables)]
@@ -96,20 +116,39 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_retrieve_some_data() {
+    async fn test_storage_stats() {
         #[allow(unused_variables)]
         let Setup { poda_address, dispencer_handle, storage_server_handles, challenger: _ } = setup_pod(N_STORAGE_PROVIDERS, RPC_URL, false).await;
         let poda_client = get_view_poda_client(poda_address).await;
 
+        let data = b"hello, world".repeat(10);
+        let result = submit_data(&dispencer_handle.base_url, &data).await.unwrap();
+
+        let efficiency = poda_client.get_storage_efficiency(result.commitment).await.unwrap();
+        assert_eq!(efficiency.original_size, data.len() as u128);
+        assert_eq!(efficiency.total_stored_size, (data.len() * TOTAL_SHARDS / REQUIRED_SHARDS) as u128);
+        assert_eq!(efficiency.redundancy_ratio, (TOTAL_SHARDS * 10000 / REQUIRED_SHARDS) as u128);
+
+        let stats = poda_client.get_network_storage_stats().await.unwrap();
+        assert!(stats.total_commitments >= 1);
+        assert!(stats.total_original_data >= efficiency.original_size);
+        assert!(stats.total_stored_data >= efficiency.total_stored_size);
+        assert!(stats.average_efficiency > 10000);
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_some_data() {
+        #[allow(unused_variables)]
+        let Setup { poda_address, dispencer_handle, storage_server_handles, challenger: _ } = setup_pod(N_STORAGE_PROVIDERS, RPC_URL, false).await;
+
         let data = b"hello, world".repeat(10);
 
         let result = submit_data(&dispencer_handle.base_url, &data).await.unwrap();
 
-        let providers = poda_client.get_providers().await.unwrap();
-        for (provider_name, chunks) in result.assignments.iter() {
-            let provider = providers.iter().find(|p| p.name == *provider_name).unwrap();
+        for (provider_addr, chunks) in result.assignments.iter() {
+            let provider = storage_server_handles.iter().find(|p| p.owner_address == *provider_addr).unwrap();
             let chunk_index = chunks.first().unwrap();
-            delete_provider_chunk(provider.url.as_str(), &result.commitment, &vec![*chunk_index]).await.unwrap();
+            delete_provider_chunk(provider, &result.commitment, &[*chunk_index]).await.unwrap();
         }
 
         let retrieved_data = retrieve_data(&dispencer_handle.base_url, &result.commitment).await.unwrap();
@@ -120,18 +159,16 @@ mod tests {
     async fn test_retrieve_no_data() {
         #[allow(unused_variables)]
         let Setup { poda_address, dispencer_handle, storage_server_handles, challenger: _ } = setup_pod(N_STORAGE_PROVIDERS, RPC_URL, false).await;
-        let poda_client = get_view_poda_client(poda_address).await;
 
         let data = b"hello, world".repeat(10);
 
         let result = submit_data(&dispencer_handle.base_url, &data).await.unwrap();
 
-        let providers = poda_client.get_providers().await.unwrap();
         let mut to_delete: usize = 9;
-        for (provider_name, chunks) in result.assignments.iter() {
-            let provider = providers.iter().find(|p| p.name == *provider_name).unwrap();
+        for (provider_addr, chunks) in result.assignments.iter() {
+            let provider = storage_server_handles.iter().find(|p| p.owner_address == *provider_addr).unwrap();
             let to_delete_chunks = chunks.iter().take(to_delete).copied().collect::<Vec<_>>();
-            delete_provider_chunk(provider.url.as_str(), &result.commitment, &to_delete_chunks).await.unwrap();
+            delete_provider_chunk(provider, &result.commitment, &to_delete_chunks).await.unwrap();
             to_delete -= to_delete_chunks.len();
             if to_delete == 0 {
                 break;
@@ -159,7 +196,7 @@ mod tests {
         let invalid_g1_point = G1::rand(&mut rng);
         let invalid_kzg_commitment = KzgCommitment::new(invalid_g1_point);
         
-        dispencer_handle.dispencer.pod.submit_commitment(merkle_tree.root(), data.len() as u32, TOTAL_SHARDS as u16, REQUIRED_SHARDS as u16, invalid_kzg_commitment.try_into().unwrap()).await.unwrap();
+        dispencer_handle.dispencer.pod.submit_commitment(merkle_tree.root(), data.len() as u32, TOTAL_SHARDS as u16, REQUIRED_SHARDS as u16, invalid_kzg_commitment.into()).await.unwrap();
         let providers = dispencer_handle.dispencer.pod.get_providers().await.unwrap();
 
         let mut rng = ark_std::test_rng();
@@ -167,7 +204,7 @@ mod tests {
         let proof = KzgProof::new(another_invalid_g1_point);
         let merkle_proofs = chunks.iter().map(|c| merkle_tree::gen_proof(&merkle_tree, c.clone()).unwrap()).collect::<Vec<_>>();
 
-        let result = dispencer_handle.dispencer.batch_submit_to_provider(chunks, merkle_tree.root(), &providers[0], proof, merkle_proofs).await;
+        let result = dispencer_handle.dispencer.batch_submit_to_provider(chunks, merkle_tree.root(), &providers[0], proof, merkle_proofs, None).await;
         if result.is_ok() {
             panic!("Should have failed to submit chunks");
         }
@@ -239,7 +276,7 @@ mod tests {
         }
 
         for storage_server_handle in storage_server_handles {
-            respond_to_active_challenges(&storage_server_handle.storage, &storage_server_handle.pod, storage_server_handle.owner_address).await.unwrap();
+            respond_to_active_challenges(&storage_server_handle.storage, &storage_server_handle.pod, storage_server_handle.owner_address, DEFAULT_RESPONDER_CONCURRENCY, system_clock().as_ref(), &ConfirmationEstimate::new(DEFAULT_RESPONSE_MARGIN)).await.unwrap();
             let active_challenges = dispencer_handle.dispencer.pod.get_provider_active_challenges(storage_server_handle.owner_address).await.unwrap();
             assert_eq!(active_challenges.len(), 0);
 
@@ -259,7 +296,7 @@ mod tests {
 
         let random_index = rand::random_range(0..storage_server_handles.len());
         let provider = storage_server_handles.get(random_index).unwrap();
-        let assigments_of_provider = result.assignments.get(&provider.name).unwrap();
+        let assigments_of_provider = result.assignments.get(&provider.owner_address).unwrap();
         let random_index = rand::random_range(0..assigments_of_provider.len());
         let chunk_id = assigments_of_provider[random_index];
 
@@ -283,4 +320,106 @@ mod tests {
         assert!(provider_info.active);
         assert_eq!(provider_info.challengeSuccessCount, provider_info.challengeCount - 1);
     }
+    #[tokio::test]
+    async fn test_repair_restores_availability() {
+        let Setup { poda_address: _, dispencer_handle, storage_server_handles, challenger } = setup_pod(N_STORAGE_PROVIDERS, RPC_URL, true).await;
+        let challenger = challenger.unwrap();
+
+        let data = b"hello, world".repeat(10);
+        let result = submit_data(&dispencer_handle.base_url, &data).await.unwrap();
+
+        // lose chunks and get slashed for them until one chunk above the recovery threshold remains
+        let mut to_lose = TOTAL_SHARDS - REQUIRED_SHARDS - 1;
+        for provider in &storage_server_handles {
+            let assigned = result.assignments.get(&provider.owner_address).cloned().unwrap_or_default();
+            for chunk_id in assigned.into_iter().take(to_lose) {
+                let (_, proof) = provider.storage.retrieve(result.commitment, chunk_id).await.unwrap().unwrap();
+                provider.storage.delete(result.commitment, chunk_id).await.unwrap();
+
+                challenger.pod.issue_chunk_challenge(result.commitment, chunk_id, provider.owner_address).await.unwrap();
+                provider.pod.respond_to_chunk_challenge(result.commitment, chunk_id, b"lost".to_vec().into(), proof.path).await.unwrap();
+                to_lose -= 1;
+            }
+        }
+
+        let (commitment_info, _) = dispencer_handle.dispencer.pod.get_commitment_info(result.commitment).await.unwrap();
+        assert_eq!(commitment_info.availableChunks as usize, REQUIRED_SHARDS + 1);
+
+        let repaired = dispencer_handle.dispencer.repair(result.commitment).await.unwrap();
+        assert_eq!(repaired, TOTAL_SHARDS - REQUIRED_SHARDS - 1);
+
+        let (commitment_info, _) = dispencer_handle.dispencer.pod.get_commitment_info(result.commitment).await.unwrap();
+        assert_eq!(commitment_info.availableChunks as usize, TOTAL_SHARDS);
+        assert_eq!(dispencer_handle.dispencer.repair(result.commitment).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_recover_restores_lost_chunk() {
+        let Setup { poda_address: _, dispencer_handle, storage_server_handles, challenger: _ } = setup_pod(N_STORAGE_PROVIDERS, RPC_URL, false).await;
+
+        let data = b"hello, world".repeat(10);
+        let result = submit_data(&dispencer_handle.base_url, &data).await.unwrap();
+
+        let provider = storage_server_handles.iter().find(|p| result.assignments.get(&p.owner_address).is_some_and(|a| !a.is_empty())).unwrap();
+        let chunk_id = result.assignments[&provider.owner_address][0];
+        let (original, _) = provider.storage.retrieve(result.commitment, chunk_id).await.unwrap().unwrap();
+        provider.storage.delete(result.commitment, chunk_id).await.unwrap();
+        assert!(provider.storage.retrieve(result.commitment, chunk_id).await.unwrap().is_none());
+
+        let request = RecoverRequest { commitment: result.commitment, index: chunk_id };
+        let signed = SignedRequest::sign(request, provider.pod.signer.as_ref().unwrap()).unwrap();
+        let response = reqwest::Client::new()
+            .post(format!("{}/recover", provider.base_url))
+            .json(&signed)
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+
+        let (chunk, proof) = provider.storage.retrieve(result.commitment, chunk_id).await.unwrap().unwrap();
+        assert_eq!(chunk.data, original.data);
+        assert!(merkle_tree::verify_proof(result.commitment, &chunk, proof));
+        assert_eq!(provider.pod.get_chunk_owner(result.commitment, chunk_id).await.unwrap(), provider.owner_address);
+    }
+
+    #[tokio::test]
+    async fn test_audit_chunk() {
+        let Setup { poda_address: _, dispencer_handle, storage_server_handles, challenger: _ } = setup_pod(N_STORAGE_PROVIDERS, RPC_URL, false).await;
+
+        let data = b"hello, world".repeat(10);
+        let result = submit_data(&dispencer_handle.base_url, &data).await.unwrap();
+
+        let provider = storage_server_handles.iter().find(|p| result.assignments.get(&p.owner_address).is_some_and(|a| !a.is_empty())).unwrap();
+        let chunk_id = result.assignments[&provider.owner_address][0];
+
+        let audit = audit_chunk(&dispencer_handle.base_url, &result.commitment, chunk_id).await.unwrap();
+        assert_eq!(audit.provider, provider.owner_address);
+        assert!(audit.passed);
+
+        // the provider keeps the honest proof but now serves different bytes
+        let (mut chunk, proof) = provider.storage.retrieve(result.commitment, chunk_id).await.unwrap().unwrap();
+        chunk.data[0] ^= 1;
+        provider.storage.store(result.commitment, &chunk, &proof).await.unwrap();
+
+        let audit = audit_chunk(&dispencer_handle.base_url, &result.commitment, chunk_id).await.unwrap();
+        assert_eq!(audit.provider, provider.owner_address);
+        assert!(audit.retrieved);
+        assert!(!audit.merkle_valid && !audit.kzg_valid);
+        assert!(!audit.passed);
+    }
+
+    #[tokio::test]
+    async fn test_update_provider_url() {
+        let Setup { poda_address, dispencer_handle: _, storage_server_handles, challenger: _ } = setup_pod(1, RPC_URL, false).await;
+        let poda_client = get_view_poda_client(poda_address).await;
+        let provider = &storage_server_handles[0];
+        assert_eq!(poda_client.get_provider_info(provider.owner_address).await.unwrap().url, provider.base_url);
+
+        let moved_to = "http://moved.example.org:5556";
+        assert!(sync_provider_url(&provider.pod, provider.owner_address, moved_to).await.unwrap());
+        assert_eq!(poda_client.get_provider_info(provider.owner_address).await.unwrap().url, moved_to);
+
+        // already up to date, nothing is sent
+        assert!(!sync_provider_url(&provider.pod, provider.owner_address, moved_to).await.unwrap());
+    }
 }
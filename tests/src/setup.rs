@@ -21,7 +21,7 @@ pub mod setup {
     };
     use storage_provider::{FileStorage};
     use tempfile::TempDir;
-    use tokio::{sync::oneshot, time::sleep};
+    use tokio::{sync::oneshot, time::{sleep, Instant}};
 
     pub struct ServerHandle {
         _temp_dir: Option<TempDir>,
@@ -33,7 +33,7 @@ pub mod setup {
         pub poda_address: Address,
         pub dispencer_handle: DispencerHandle,
         pub storage_server_handles: Vec<StorageServerHandle>,
-        pub challenger: Option<Challenger>,
+        pub challenger: Option<Challenger<PodaClient>>,
     }
 
     pub struct StorageServerHandle {
@@ -52,6 +52,11 @@ pub mod setup {
         pub server: ServerHandle
     }
 
+    const SERVER_START_TIMEOUT: Duration = Duration::from_secs(10);
+    const SERVER_POLL_INITIAL_DELAY: Duration = Duration::from_millis(10);
+    const SERVER_POLL_MAX_DELAY: Duration = Duration::from_millis(500);
+    const SERVER_POLL_MAX_ATTEMPTS: usize = 50;
+
     const FAUCET_PRIVATE_KEY: &str = "6df79891f22b0f3c9e9fb53b966a8861fd6fef69f99772c5c4dbcf303f10d901";
     const MIN_STAKE: u128 = ONE_ETH / 100;
 
@@ -60,7 +65,7 @@ pub mod setup {
     // n_actors: Number of actors in setup. 1 will be dispencer, the rest will be storage providers
     #[cfg(test)]
     pub async fn setup_pod(n_storage_providers: usize, rpc_url: &str, with_challenger: bool) -> Setup {
-        use client::faucet_if_needed;
+        use client::{faucet_if_needed, DEFAULT_FAUCET_AMOUNT, DEFAULT_FAUCET_MIN_BALANCE};
 
         INIT.call_once(|| {
             init_logging();
@@ -76,7 +81,7 @@ pub mod setup {
 
         let actors = get_actors();
         info!("Fauceting actors");
-        faucet_if_needed(&faucet, &actors).await;
+        faucet_if_needed(&faucet, &actors, DEFAULT_FAUCET_MIN_BALANCE, DEFAULT_FAUCET_AMOUNT).await;
 
         let mut clients: Vec<PodaClient> = Vec::new();
         for actor in actors.iter() {
@@ -98,9 +103,9 @@ pub mod setup {
         
         for i in 2..n_storage_providers + 2 {
             let storage_provider = clients[i].clone();
-            info!("Starting storage provider server for provider: {:?}", storage_provider.signer.address());
+            info!("Starting storage provider server for provider: {:?}", storage_provider.signer_address().unwrap());
             let name = format!("storage-provider-{}", i);
-            let handle = start_new_storage_provider_server(&storage_provider, &name).await;
+            let handle = start_new_storage_provider_server(&storage_provider, &name, &dispencer_handle.base_url).await;
             let res = storage_provider.register_provider(name, handle.base_url.to_string(), ONE_ETH).await;
 
             if res.is_err() {
@@ -122,6 +127,32 @@ pub mod setup {
         }
     }
 
+    /// Polls `{base_url}/health` with exponential backoff until it answers with a success status.
+    /// Gives up once `timeout` has passed or after `SERVER_POLL_MAX_ATTEMPTS` polls.
+    pub async fn wait_for_server(base_url: &str, timeout: Duration) -> anyhow::Result<()> {
+        let client = reqwest::Client::new();
+        let url = format!("{}/health", base_url);
+        let deadline = Instant::now() + timeout;
+        let mut delay = SERVER_POLL_INITIAL_DELAY;
+
+        for _ in 0..SERVER_POLL_MAX_ATTEMPTS {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+
+            let response = client.get(&url).timeout(remaining).send().await;
+            if matches!(response, Ok(ref r) if r.status().is_success()) {
+                return Ok(());
+            }
+
+            sleep(delay.min(deadline.saturating_duration_since(Instant::now()))).await;
+            delay = (delay * 2).min(SERVER_POLL_MAX_DELAY);
+        }
+
+        Err(anyhow::anyhow!("Server at {} did not become healthy within {:?}", base_url, timeout))
+    }
+
     #[cfg(test)]
     async fn start_new_dispencer_server(pod: &PodaClient) -> DispencerHandle {
         // Find an available port
@@ -134,7 +165,7 @@ pub mod setup {
         let dispencer_instance = Arc::new(Dispenser::new(pod.clone()));
 
         // Start the server in the background
-        let server = dispencer::http::start_server(dispencer_instance.clone(), port);
+        let server = dispencer::http::start_server(dispencer_instance.clone(), port, dispencer::http::DEFAULT_MAX_BODY_BYTES, Default::default());
         let _ = tokio::spawn(async move {
             let server = server;
             tokio::select! {
@@ -143,10 +174,8 @@ pub mod setup {
             }
         });
 
-        // Wait for server to start
-        sleep(Duration::from_millis(100)).await;
-
         let base_url = format!("http://localhost:{}", port);
+        wait_for_server(&base_url, SERVER_START_TIMEOUT).await.unwrap();
 
         DispencerHandle {
             base_url,
@@ -154,13 +183,13 @@ pub mod setup {
                 _temp_dir: None,
                 _shutdown_tx: shutdown_tx,
             },
-            owner_address: pod.signer.address(),
+            owner_address: pod.signer_address().unwrap(),
             dispencer: dispencer_instance,
         }
     }
 
     #[cfg(test)]
-    async fn start_new_storage_provider_server(pod: &PodaClient, name: &str) -> StorageServerHandle {
+    async fn start_new_storage_provider_server(pod: &PodaClient, name: &str, dispenser_url: &str) -> StorageServerHandle {
         // Find an available port
         let listener = TcpListener::bind("127.0.0.1:0").unwrap();
         let port = listener.local_addr().unwrap().port();
@@ -175,7 +204,14 @@ pub mod setup {
         let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
 
         // Start the server in the background
-        let server = storage_provider::http::start_server(storage.clone(), Arc::new(pod.clone()), port);
+        let config = storage_provider::http::ServerConfig {
+            dispenser_url: Some(dispenser_url.to_string()),
+            provider_address: pod.signer_address(),
+            admin_signers: vec![pod.signer_address().unwrap()],
+            receipt_signer: pod.signer.clone(),
+            ..Default::default()
+        };
+        let server = storage_provider::http::start_server(storage.clone(), Arc::new(pod.clone()), port, config, Default::default());
         let _ = tokio::spawn(async move {
             let server = server;
             tokio::select! {
@@ -184,15 +220,13 @@ pub mod setup {
             }
         });
 
-        // Wait for server to start
-        sleep(Duration::from_millis(100)).await;
-
         let base_url = format!("http://localhost:{}", port);
+        wait_for_server(&base_url, SERVER_START_TIMEOUT).await.unwrap();
 
         StorageServerHandle {
             storage,
             base_url,
-            owner_address: pod.signer.address(),
+            owner_address: pod.signer_address().unwrap(),
             server: ServerHandle {
                 _temp_dir: Some(temp_dir),
                 _shutdown_tx: shutdown_tx,
@@ -201,4 +235,47 @@ pub mod setup {
             pod: pod.clone(),
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::io::{Read, Write};
+
+        // Answers every connection with 200 OK, standing in for a server's /health route
+        fn serve_health_stub() -> String {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let port = listener.local_addr().unwrap().port();
+            std::thread::spawn(move || {
+                for mut stream in listener.incoming().flatten() {
+                    let mut buf = [0u8; 1024];
+                    let _ = stream.read(&mut buf);
+                    let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nOK");
+                }
+            });
+
+            format!("http://127.0.0.1:{}", port)
+        }
+
+        #[tokio::test]
+        async fn test_wait_for_server_returns_once_healthy() {
+            let base_url = serve_health_stub();
+
+            let started = Instant::now();
+            wait_for_server(&base_url, Duration::from_secs(5)).await.unwrap();
+            assert!(started.elapsed() < Duration::from_secs(1));
+        }
+
+        #[tokio::test]
+        async fn test_wait_for_server_times_out_on_dead_port() {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let port = listener.local_addr().unwrap().port();
+            drop(listener);
+
+            let timeout = Duration::from_millis(300);
+            let started = Instant::now();
+            let result = wait_for_server(&format!("http://127.0.0.1:{}", port), timeout).await;
+            assert!(result.is_err());
+            assert!(started.elapsed() < timeout + Duration::from_millis(200));
+        }
+    }
 }